@@ -50,7 +50,7 @@ async function initialize() {
 	gameTokenAccount = await createTokenAccount(mintAccount, ownerKeypair, feePayerKeypair);
 	await mintToken(1000 * Math.pow(10, TOKEN_DECIMALS), mintAccount, gameTokenAccount, authorityKeypair, feePayerKeypair);
 	gameAccount = await createGameAccount(feePayerKeypair);
-	await initializeGame(gameTokenAccount.publicKey, ownerKeypair, gameAccount.publicKey, feePayerKeypair);
+	await initializeGame(gameTokenAccount.publicKey, mintAccount.publicKey, ownerKeypair, gameAccount.publicKey, feePayerKeypair);
 
 	const payoutModule = new AdminModule(feePayerKeypair, ownerKeypair, authorityKeypair, mintAccount, gameTokenAccount, gameAccount, players);
 	const playerModule = new PlayerModule(feePayerKeypair, ownerKeypair, authorityKeypair, mintAccount, gameTokenAccount, gameAccount, players);
@@ -1,10 +1,11 @@
-import { Keypair } from '@solana/web3.js';
+import { Keypair, PublicKey } from '@solana/web3.js';
 
 export class Player {
 	keypair: Keypair;
 	account: Keypair;
-	tokenAccount: Keypair;
-	constructor(keypair: Keypair, account: Keypair, tokenAccount: Keypair) {
+	// Associated token account, created by the program on first claim
+	tokenAccount: PublicKey;
+	constructor(keypair: Keypair, account: Keypair, tokenAccount: PublicKey) {
 		this.keypair = keypair;
 		this.account = account;
 		this.tokenAccount = tokenAccount;
@@ -5,7 +5,7 @@ import { connection } from '../config';
 import { Player } from '../model/player';
 import { SchemaBuilder } from '../schema/builder';
 import { fromSchemaDataToPlayerState, PlayerStateSchema } from '../schema/states';
-import { claimReward, createPlayerAccount, createPlayerKeypair, createTokenAccount, registerPlayer } from '../utils';
+import { claimReward, createPlayerAccount, createPlayerKeypair, getAssociatedTokenAddress, registerPlayer } from '../utils';
 import { BaseModule } from './base';
 
 const prompt = PromptSync();
@@ -70,13 +70,14 @@ async function showPlayerDetailsMenu(players: Player[]) {
 async function printPlayerDetails(player: Player) {
 	console.log('');
 	const walletAccountInfo = await connection.getAccountInfo(player.keypair.publicKey);
-	const tokenAccountInfo = await connection.getTokenAccountBalance(player.tokenAccount.publicKey);
+	// Associated token account only exists after the first claim
+	const tokenAccountInfo = (await connection.getAccountInfo(player.tokenAccount)) ? await connection.getTokenAccountBalance(player.tokenAccount) : undefined;
 	const playerAccountInfo = await connection.getAccountInfo(player.account.publicKey);
 	if (walletAccountInfo) {
 		console.log(player.keypair.publicKey.toBase58(), `${walletAccountInfo.lamports / LAMPORTS_PER_SOL} SOL`);
 	}
 	if (tokenAccountInfo) {
-		console.log(player.tokenAccount.publicKey.toBase58(), `${tokenAccountInfo.value.uiAmountString} SPL token`);
+		console.log(player.tokenAccount.toBase58(), `${tokenAccountInfo.value.uiAmountString} SPL token`);
 	}
 	if (playerAccountInfo) {
 		const playerState = fromSchemaDataToPlayerState(SchemaBuilder.deserialize(PlayerStateSchema, playerAccountInfo.data));
@@ -120,7 +121,7 @@ async function registerNewPlayer(gameAccount: Keypair, mintAccount: Keypair, pla
 		} else {
 			await registerPlayer(playerKeypair, playerAccount.publicKey, gameAccount.publicKey, playerKeypair);
 		}
-		const playerTokenAccount = await createTokenAccount(mintAccount, playerKeypair, playerKeypair);
+		const playerTokenAccount = await getAssociatedTokenAddress(mintAccount.publicKey, playerKeypair.publicKey);
 		console.log('Player created');
 		return new Player(playerKeypair, playerAccount, playerTokenAccount);
 	} catch (error: any) {
@@ -145,8 +146,8 @@ async function claim(players: Player[], gameAccountPubkey: PublicKey, gameTokenA
 				if (playerState.reward_to_claim.eq(new BN(0))) {
 					console.log('No claimable reward');
 				} else {
-					await claimReward(player.keypair, gameAccountPubkey, player.account.publicKey, gameTokenAccountPubkey, player.tokenAccount.publicKey, player.keypair);
-					console.log(`Claimed ${playerState.reward_to_claim.div(new BN(LAMPORTS_PER_SOL)).toString()} SPL token to ${player.tokenAccount.publicKey.toBase58()}`);
+					await claimReward(player.keypair, gameAccountPubkey, player.account.publicKey, gameTokenAccountPubkey, player.tokenAccount, player.keypair);
+					console.log(`Claimed ${playerState.reward_to_claim.div(new BN(LAMPORTS_PER_SOL)).toString()} SPL token to ${player.tokenAccount.toBase58()}`);
 				}
 			}
 		} else if (choice === EXIT) {
@@ -19,22 +19,551 @@ export const AddRewardIxScheme = new Map([
 			fields: [
 				['tag', 'u8'],
 				['reward_amount', 'u64'],
+				['mint', { kind: 'option', type: [32] }],
 			],
 		},
 	],
 ]);
 
-export const ClaimRewardIxSchema = GameInitIxSchema;
+export const ClaimRewardIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['mint', { kind: 'option', type: [32] }],
+			],
+		},
+	],
+]);
 export const PlayerRegisterIxSchema = GameInitIxSchema;
 
+export const PlayerRegisterWithCodeIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['referral_code', { kind: 'option', type: ['u8'] }],
+			],
+		},
+	],
+]);
+
+// Allowlist proof is only required by games gated by an allowlist
+export const PlayerRegisterWithProofIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['referral_code', { kind: 'option', type: ['u8'] }],
+				['allowlist_proof', [[32]]],
+			],
+		},
+	],
+]);
+
+export const ClaimReferralCodeIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['code', ['u8']],
+			],
+		},
+	],
+]);
+
+export const PublishDistributionIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['root', [32]],
+				['total', 'u64'],
+			],
+		},
+	],
+]);
+
+export const ClaimFromDistributionIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['index', 'u64'],
+				['amount', 'u64'],
+				['proof', [[32]]],
+			],
+		},
+	],
+]);
+
+export const FreezePlayerIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['forfeit', 'u8'],
+			],
+		},
+	],
+]);
+
+// Allowlist root is only serialized for RegistrationGate.Allowlist, use SetOpenRegistrationGateIxSchema otherwise
+export const SetRegistrationGateIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['registration_gate', 'u8'],
+				['allowlist_root', [32]],
+			],
+		},
+	],
+]);
+
+export const SetOpenRegistrationGateIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['registration_gate', 'u8'],
+			],
+		},
+	],
+]);
+
+export const SetRegistrationFeeIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['registration_fee', 'u64'],
+				['upline_percent', 'u8'],
+			],
+		},
+	],
+]);
+
+export const CreateSeasonIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['start', 'u64'],
+				['end', 'u64'],
+				['prizes', ['u64']],
+			],
+		},
+	],
+]);
+
+export const SubmitScoreIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['points', 'u64'],
+			],
+		},
+	],
+]);
+
+export const SetStakeRewardRateIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['stake_reward_rate', 'u64'],
+			],
+		},
+	],
+]);
+
+// Stake and Unstake
+export const StakeIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['amount', 'u64'],
+			],
+		},
+	],
+]);
+
+export const CreateQuestIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['reward', 'u64'],
+				['max_completions', { kind: 'option', type: 'u64' }],
+				['expiry', { kind: 'option', type: 'u64' }],
+			],
+		},
+	],
+]);
+
+export const CompleteQuestIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['quest_id', 'u64'],
+			],
+		},
+	],
+]);
+
+export const CommitFlipIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['wager', 'u64'],
+				['commitment', [32]],
+			],
+		},
+	],
+]);
+
+export const HouseCommitFlipIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['commitment', [32]],
+			],
+		},
+	],
+]);
+
+export const RevealFlipIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['secret', [32]],
+			],
+		},
+	],
+]);
+
+export const SetCheckInScheduleIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['schedule', ['u64']],
+			],
+		},
+	],
+]);
+
+export const SetTransferFeeIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['fee_percent', 'u8'],
+			],
+		},
+	],
+]);
+
+export const TransferRewardIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['amount', 'u64'],
+			],
+		},
+	],
+]);
+
+export const SetClaimDelegateIxSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['delegate', { kind: 'option', type: [32] }],
+			],
+		},
+	],
+]);
+
+// Tiers must be LootTierData instances, borsh find the schema of a nested struct by its class
+export class LootTierData extends SchemaData {}
+
+export const SetLootTableIxSchema = new Map<any, any>([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['tag', 'u8'],
+				['price', 'u64'],
+				['tiers', [LootTierData]],
+			],
+		},
+	],
+	[
+		LootTierData,
+		{
+			kind: 'struct',
+			fields: [
+				['weight', 'u32'],
+				['reward', 'u64'],
+			],
+		},
+	],
+]);
+
+// Message signed with Ed25519 by the admin or an operator, verified by the instruction before RedeemVoucher
+export const VoucherSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['program_account', [32]],
+				['player', [32]],
+				['amount', 'u64'],
+				['nonce', 'u64'],
+				['expiry', 'u64'],
+			],
+		},
+	],
+]);
+
+export interface IVoucher {
+	program_account: Uint8Array;
+	// The player program account
+	player: Uint8Array;
+	amount: number;
+	// Each nonce can be redeemed once per player, in any order
+	nonce: number;
+	// Unix timestamp
+	expiry: number;
+}
+
+// Signed by the player owner in an Ed25519 instruction before RelayClaim
+export const ClaimAuthorizationSchema = new Map([
+	[
+		SchemaData,
+		{
+			kind: 'struct',
+			fields: [
+				['program_account', [32]],
+				['player', [32]],
+				['nonce', 'u64'],
+				['expiry', 'u64'],
+			],
+		},
+	],
+]);
+
+export interface IClaimAuthorization {
+	program_account: Uint8Array;
+	// The player program account
+	player: Uint8Array;
+	// Must be greater than the nonce of the last claim authorization relayed for the player
+	nonce: number;
+	// Unix timestamp
+	expiry: number;
+}
+
 interface BaseIx {
 	tag: Tag;
 }
 
 export interface IAddRewardIx extends BaseIx {
 	reward_amount: number;
+	// Mint of a registered reward vault, null for the mint of the game token account
+	mint: Uint8Array | null;
+}
+
+export interface IClaimRewardIx extends BaseIx {
+	// Mint of a registered reward vault, null for the mint of the game token account
+	mint: Uint8Array | null;
 }
 
 export interface IGameInitIx extends BaseIx {}
-export interface IClaimRewardIx extends BaseIx {}
 export interface IPlayerRegisterIx extends BaseIx {}
+
+export interface IPlayerRegisterWithCodeIx extends BaseIx {
+	// At most 16 bytes, the upline is the player owning the code
+	referral_code: Uint8Array | null;
+}
+
+export interface IPlayerRegisterWithProofIx extends IPlayerRegisterWithCodeIx {
+	// Sibling hashes from the leaf of the player wallet up to the allowlist root
+	allowlist_proof: Uint8Array[];
+}
+
+export interface IClaimReferralCodeIx extends BaseIx {
+	code: Uint8Array;
+}
+
+export interface IPublishDistributionIx extends BaseIx {
+	// Merkle root of (index, player program account, amount) leaves
+	root: Uint8Array;
+	total: number;
+}
+
+export interface IClaimFromDistributionIx extends BaseIx {
+	index: number;
+	amount: number;
+	// Sibling hashes from the leaf up to the root
+	proof: Uint8Array[];
+}
+
+export interface IFreezePlayerIx extends BaseIx {
+	// 1 to reset the unclaimed reward of the player
+	forfeit: number;
+}
+
+export interface ISetRegistrationGateIx extends BaseIx {
+	// RegistrationGate
+	registration_gate: number;
+	allowlist_root?: Uint8Array;
+}
+
+export interface ISetRegistrationFeeIx extends BaseIx {
+	// 0 for free registration
+	registration_fee: number;
+	// Percentage of the fee credited to the upline reward, at most 100
+	upline_percent: number;
+}
+
+export interface ICreateSeasonIx extends BaseIx {
+	// Unix timestamps, scores are accepted from start until end
+	start: number;
+	end: number;
+	// Prize of each leaderboard rank, at most 10
+	prizes: number[];
+}
+
+export interface ISubmitScoreIx extends BaseIx {
+	points: number;
+}
+
+export interface ISetStakeRewardRateIx extends BaseIx {
+	// Reward per second shared by all the staked tokens
+	stake_reward_rate: number;
+}
+
+export interface IStakeIx extends BaseIx {
+	// Unstake 0 only credit the staking yield
+	amount: number;
+}
+
+export interface ICreateQuestIx extends BaseIx {
+	// The upline receive 10%
+	reward: number;
+	// No limit when undefined
+	max_completions?: number;
+	// Unix timestamp, never expire when undefined
+	expiry?: number;
+}
+
+export interface ICompleteQuestIx extends BaseIx {
+	quest_id: number;
+}
+
+export interface ICommitFlipIx extends BaseIx {
+	// Taken from the player reward
+	wager: number;
+	// sha256 of the player secret
+	commitment: Uint8Array;
+}
+
+export interface IHouseCommitFlipIx extends BaseIx {
+	// sha256 of the house secret
+	commitment: Uint8Array;
+}
+
+export interface IRevealFlipIx extends BaseIx {
+	secret: Uint8Array;
+}
+
+export interface ILootTier {
+	// Relative chance of the tier
+	weight: number;
+	// Credited to the player reward
+	reward: number;
+}
+
+export interface ISetLootTableIx extends BaseIx {
+	// Taken from the player reward when a box is opened, greater than 0
+	price: number;
+	// 1 to 8 tiers created with new LootTierData(tier: ILootTier), with a total weight greater than 0
+	tiers: LootTierData[];
+}
+
+export interface ISetCheckInScheduleIx extends BaseIx {
+	// Reward of each streak day, at most 7, the last one is repeated once the streak is longer
+	schedule: number[];
+}
+
+export interface ISetTransferFeeIx extends BaseIx {
+	// 0 for free transfer, at most 100
+	fee_percent: number;
+}
+
+export interface ITransferRewardIx extends BaseIx {
+	// Taken from the sender reward, the recipient receive it minus the transfer fee
+	amount: number;
+}
+
+export interface ISetClaimDelegateIx extends BaseIx {
+	// Can sign Claim for the player, the reward still goes to the player. null to remove the delegate
+	delegate: Uint8Array | null;
+}
@@ -14,6 +14,22 @@ export const PlayerStateSchema = new Map([
 				['program_account', ['u8', 32]],
 				['has_upline', ['u8', 4]],
 				['upline', ['u8', 32]],
+				['total_earned', 'u64'],
+				['total_claimed', 'u64'],
+				['total_referral_earned', 'u64'],
+				['downline_count', 'u64'],
+				['direct_referrals', 'u64'],
+				['registered_at', 'u64'],
+				['upline_changed', 'u8'],
+				['frozen', 'u8'],
+				['staked_amount', 'u64'],
+				['stake_reward_debt', 'u128'],
+				['check_in_streak', 'u64'],
+				['last_check_in_day', 'u64'],
+				['has_claim_delegate', ['u8', 4]],
+				['claim_delegate', ['u8', 32]],
+				['payout_token_account', ['u8', 32]],
+				['last_claim_nonce', 'u64'],
 			],
 		},
 	],
@@ -26,8 +42,30 @@ export interface IPlayerState {
 	program_account: PublicKey;
 	has_upline: boolean;
 	upline?: PublicKey;
+	total_earned: BN;
+	total_claimed: BN;
+	total_referral_earned: BN;
+	downline_count: BN;
+	direct_referrals: BN;
+	registered_at: BN;
+	upline_changed: boolean;
+	frozen: boolean;
+	staked_amount: BN;
+	stake_reward_debt: BN;
+	check_in_streak: BN;
+	// Days since the unix epoch
+	last_check_in_day: BN;
+	// Can sign Claim for the player, the reward still goes to the player
+	claim_delegate?: PublicKey;
+	// Associated token account of the owner for the game mint, locked at Register, default pubkey for lamport payout
+	payout_token_account: PublicKey;
+	last_claim_nonce: BN;
 }
 
+export const MAX_OPERATORS = 5;
+export const OPERATOR_BYTE = 52;
+export const MAX_CHECK_IN_DAYS = 7;
+
 export const GameStateSchema = new Map([
 	[
 		SchemaData,
@@ -37,6 +75,33 @@ export const GameStateSchema = new Map([
 				['is_initialized', 'u8'],
 				['admin', ['u8', 32]],
 				['spl_token_account', ['u8', 32]],
+				['operators', ['u8', OPERATOR_BYTE * MAX_OPERATORS]],
+				['token_program', ['u8', 32]],
+				['mint', ['u8', 32]],
+				['decimals', 'u8'],
+				['payout_mode', 'u8'],
+				['player_count', 'u64'],
+				['total_awarded', 'u64'],
+				['total_claimed', 'u64'],
+				['has_max_direct_referrals', ['u8', 4]],
+				['max_direct_referrals', 'u64'],
+				['distribution_count', 'u64'],
+				['total_forfeited', 'u64'],
+				['registration_gate', 'u8'],
+				['allowlist_root', [32]],
+				['registration_fee', 'u64'],
+				['registration_fee_upline_percent', 'u8'],
+				['total_registration_fees', 'u64'],
+				['season_count', 'u64'],
+				['stake_reward_rate', 'u64'],
+				['total_staked', 'u64'],
+				['reward_per_share', 'u128'],
+				['last_stake_update', 'u64'],
+				['quest_count', 'u64'],
+				['check_in_schedule_len', 'u8'],
+				['check_in_schedule', ['u64', MAX_CHECK_IN_DAYS]],
+				['transfer_fee_percent', 'u8'],
+				['total_transfer_fees', 'u64'],
 			],
 		},
 	],
@@ -46,6 +111,44 @@ export interface IGameState {
 	is_initialized: boolean;
 	admin: PublicKey;
 	spl_token_account: PublicKey;
+	token_program: PublicKey;
+	mint: PublicKey;
+	decimals: number;
+	payout_mode: PayoutMode;
+	player_count: BN;
+	total_awarded: BN;
+	total_claimed: BN;
+	max_direct_referrals?: BN;
+	distribution_count: BN;
+	total_forfeited: BN;
+	registration_gate: RegistrationGate;
+	allowlist_root: Uint8Array;
+	registration_fee: BN;
+	registration_fee_upline_percent: number;
+	total_registration_fees: BN;
+	season_count: BN;
+	stake_reward_rate: BN;
+	total_staked: BN;
+	// Scaled by STAKE_REWARD_PRECISION
+	reward_per_share: BN;
+	last_stake_update: BN;
+	quest_count: BN;
+	// Reward of each day of a check-in streak, empty when check-in is disabled
+	check_in_schedule: BN[];
+	// Percentage of a reward transfer between players kept in the vault
+	transfer_fee_percent: number;
+	total_transfer_fees: BN;
+}
+
+export enum PayoutMode {
+	Token,
+	Lamports,
+}
+
+export enum RegistrationGate {
+	Open,
+	AdminSignature,
+	Allowlist,
 }
 
 export function fromSchemaDataToPlayerState(playerStateSchema: any): IPlayerState {
@@ -55,20 +158,68 @@ export function fromSchemaDataToPlayerState(playerStateSchema: any): IPlayerStat
 		reward_to_claim: playerStateSchema.reward_to_claim,
 		owner: new PublicKey(playerStateSchema.owner),
 		program_account: new PublicKey(playerStateSchema.program_account),
+		total_earned: playerStateSchema.total_earned,
+		total_claimed: playerStateSchema.total_claimed,
+		total_referral_earned: playerStateSchema.total_referral_earned,
+		downline_count: playerStateSchema.downline_count,
+		direct_referrals: playerStateSchema.direct_referrals,
+		registered_at: playerStateSchema.registered_at,
+		upline_changed: playerStateSchema.upline_changed === 1,
+		frozen: playerStateSchema.frozen === 1,
+		staked_amount: playerStateSchema.staked_amount,
+		stake_reward_debt: playerStateSchema.stake_reward_debt,
+		check_in_streak: playerStateSchema.check_in_streak,
+		last_check_in_day: playerStateSchema.last_check_in_day,
+		payout_token_account: new PublicKey(playerStateSchema.payout_token_account),
+		last_claim_nonce: playerStateSchema.last_claim_nonce,
 	};
 	if (playerState.has_upline) {
 		playerState.upline = new PublicKey(playerStateSchema.upline);
 	}
+	if (playerStateSchema.has_claim_delegate[0] === 1) {
+		playerState.claim_delegate = new PublicKey(playerStateSchema.claim_delegate);
+	}
 	return playerState;
 }
 
 export function fromSchemaDataToGameState(gameStateSchema: any): IGameState {
-	return {
+	const gameState: IGameState = {
 		is_initialized: gameStateSchema.is_initialized === 1,
 		admin: new PublicKey(gameStateSchema.admin),
 		spl_token_account: new PublicKey(gameStateSchema.spl_token_account),
+		token_program: new PublicKey(gameStateSchema.token_program),
+		mint: new PublicKey(gameStateSchema.mint),
+		decimals: gameStateSchema.decimals,
+		payout_mode: gameStateSchema.payout_mode,
+		player_count: gameStateSchema.player_count,
+		total_awarded: gameStateSchema.total_awarded,
+		total_claimed: gameStateSchema.total_claimed,
+		distribution_count: gameStateSchema.distribution_count,
+		total_forfeited: gameStateSchema.total_forfeited,
+		registration_gate: gameStateSchema.registration_gate,
+		allowlist_root: gameStateSchema.allowlist_root,
+		registration_fee: gameStateSchema.registration_fee,
+		registration_fee_upline_percent: gameStateSchema.registration_fee_upline_percent,
+		total_registration_fees: gameStateSchema.total_registration_fees,
+		season_count: gameStateSchema.season_count,
+		stake_reward_rate: gameStateSchema.stake_reward_rate,
+		total_staked: gameStateSchema.total_staked,
+		reward_per_share: gameStateSchema.reward_per_share,
+		last_stake_update: gameStateSchema.last_stake_update,
+		quest_count: gameStateSchema.quest_count,
+		check_in_schedule: gameStateSchema.check_in_schedule.slice(
+			0,
+			gameStateSchema.check_in_schedule_len,
+		),
+		transfer_fee_percent: gameStateSchema.transfer_fee_percent,
+		total_transfer_fees: gameStateSchema.total_transfer_fees,
 	};
+	if (gameStateSchema.has_max_direct_referrals[0] === 1) {
+		gameState.max_direct_referrals = gameStateSchema.max_direct_referrals;
+	}
+	return gameState;
 }
 
-export const PLAYER_STATE_BYTE = 109;
-export const GAME_STATE_BYTE = 65;
+export const PLAYER_STATE_BYTE = 275;
+export const GAME_STATE_BYTE = 65 + OPERATOR_BYTE * MAX_OPERATORS + 290;
+export const STAKE_REWARD_PRECISION = new BN('1000000000000');
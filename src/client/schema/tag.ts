@@ -3,4 +3,41 @@ export enum Tag {
 	Register,
 	AddReward,
 	Claim,
+	AddOperator,
+	RemoveOperator,
+	RegisterVault,
+	InitLamports,
+	Deposit,
+	SetMaxDirectReferrals,
+	SetUpline,
+	ClaimReferralCode,
+	RedeemVoucher,
+	PublishDistribution,
+	ClaimFromDistribution,
+	FreezePlayer,
+	ThawPlayer,
+	SetRegistrationGate,
+	SetRegistrationFee,
+	CreateSeason,
+	SubmitScore,
+	FinalizeSeason,
+	InitStakeVault,
+	SetStakeRewardRate,
+	Stake,
+	Unstake,
+	CreateQuest,
+	CompleteQuest,
+	CommitFlip,
+	HouseCommitFlip,
+	RevealFlip,
+	ResolveFlipTimeout,
+	SetLootTable,
+	OpenBox,
+	RevealBox,
+	SetCheckInSchedule,
+	CheckIn,
+	SetTransferFee,
+	TransferReward,
+	SetClaimDelegate,
+	RelayClaim,
 }
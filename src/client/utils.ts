@@ -1,5 +1,15 @@
 import * as SplToken from '@solana/spl-token';
-import { AccountMeta, Keypair, LAMPORTS_PER_SOL, PublicKey, sendAndConfirmTransaction, SystemProgram, Transaction, TransactionInstruction } from '@solana/web3.js';
+import {
+	AccountMeta,
+	Keypair,
+	LAMPORTS_PER_SOL,
+	PublicKey,
+	sendAndConfirmTransaction,
+	SystemProgram,
+	SYSVAR_RENT_PUBKEY,
+	Transaction,
+	TransactionInstruction,
+} from '@solana/web3.js';
 import * as fs from 'fs';
 import os from 'os';
 import * as path from 'path';
@@ -16,7 +26,7 @@ import {
 	IPlayerRegisterIx,
 	PlayerRegisterIxSchema,
 } from './schema/instructions';
-import { GAME_STATE_BYTE, PLAYER_STATE_BYTE } from './schema/states';
+import { fromSchemaDataToGameState, GameStateSchema, GAME_STATE_BYTE, IGameState, PLAYER_STATE_BYTE } from './schema/states';
 import { Tag } from './schema/tag';
 
 export async function requestAirdropIfInsufficientBalance(feePayerKeypair: Keypair, signatureCount: number, bytes?: number[]) {
@@ -38,13 +48,17 @@ export async function requestAirdropIfInsufficientBalance(feePayerKeypair: Keypa
 	}
 }
 
-// 0 - [signer]   - The player (holder) account
+// 0 - [signer, writable] - The player (holder) account, pay for the associated token account creation
 // 1 - [writable] - Program account
 // 2 - [writable] - The player program account
 // 3 - [writable] - The token account of the current program
 // 4-  []         - The PDA, owner (in term of token, not account owner) of token account
-// 5 - [writable] - The player token account
+// 5 - [writable] - The player associated token account, created if not exists
 // 6 - []         - The token program
+// 7 - []         - The mint of the token account of the current program
+// 8 - []         - The system program
+// 9 - []         - The associated token account program
+// 10 - []        - The rent sysvar
 export async function claimReward(
 	playerKeypair: Keypair,
 	programAccountPubkey: PublicKey,
@@ -55,22 +69,28 @@ export async function claimReward(
 ) {
 	const programId = (await getDeployedProgramKeypairOrThrow()).publicKey;
 	const [PDA] = await PublicKey.findProgramAddress([Buffer.from(PDA_SEED)], programId);
+	const gameState = await getGameStateOrThrow(programAccountPubkey);
 	const transaction = new Transaction().add(
 		new TransactionInstruction({
 			keys: [
-				{ isSigner: true, isWritable: false, pubkey: playerKeypair.publicKey },
+				{ isSigner: true, isWritable: true, pubkey: playerKeypair.publicKey },
 				{ isSigner: false, isWritable: true, pubkey: programAccountPubkey },
 				{ isSigner: false, isWritable: true, pubkey: playerAccountPubkey },
 				{ isSigner: false, isWritable: true, pubkey: gameTokenAccountPubkey },
 				{ isSigner: false, isWritable: false, pubkey: PDA },
 				{ isSigner: false, isWritable: true, pubkey: playerTokenAccountPubkey },
-				{ isSigner: false, isWritable: false, pubkey: SplToken.TOKEN_PROGRAM_ID },
+				{ isSigner: false, isWritable: false, pubkey: gameState.token_program },
+				{ isSigner: false, isWritable: false, pubkey: gameState.mint },
+				{ isSigner: false, isWritable: false, pubkey: SystemProgram.programId },
+				{ isSigner: false, isWritable: false, pubkey: SplToken.ASSOCIATED_TOKEN_PROGRAM_ID },
+				{ isSigner: false, isWritable: false, pubkey: SYSVAR_RENT_PUBKEY },
 			],
 			programId,
 			data: SchemaBuilder.serialize(
 				ClaimRewardIxSchema,
 				new SchemaData({
 					tag: Tag.Claim,
+					mint: null,
 				} as IClaimRewardIx),
 			),
 		}),
@@ -111,6 +131,7 @@ export async function addReward(
 				new SchemaData({
 					tag: Tag.AddReward,
 					reward_amount: rewardAmountInSol,
+					mint: null,
 				} as IAddRewardIx),
 			),
 		}),
@@ -123,8 +144,15 @@ export async function addReward(
 // 0 - [signer]   - The admin (holder) account
 // 1 - [writable] - Program account
 // 2 - [writable] - An token account created by the admin, and pre-funded
-// 3 - []         - The token program
-export async function initializeGame(tokenAccountPublicKey: PublicKey, adminKeypair: Keypair, gameAccountPubkey: PublicKey, feePayerKeypair: Keypair) {
+// 3 - []         - The token program, SPL TOKEN or Token-2022
+// 4 - []         - The mint of the token account
+export async function initializeGame(
+	tokenAccountPublicKey: PublicKey,
+	mintPublicKey: PublicKey,
+	adminKeypair: Keypair,
+	gameAccountPubkey: PublicKey,
+	feePayerKeypair: Keypair,
+) {
 	const programId = (await getDeployedProgramKeypairOrThrow()).publicKey;
 	const transaction = new Transaction().add(
 		new TransactionInstruction({
@@ -133,6 +161,7 @@ export async function initializeGame(tokenAccountPublicKey: PublicKey, adminKeyp
 				{ isSigner: false, isWritable: true, pubkey: gameAccountPubkey },
 				{ isSigner: false, isWritable: true, pubkey: tokenAccountPublicKey },
 				{ isSigner: false, isWritable: false, pubkey: SplToken.TOKEN_PROGRAM_ID },
+				{ isSigner: false, isWritable: false, pubkey: mintPublicKey },
 			],
 			programId,
 			data: SchemaBuilder.serialize(
@@ -160,10 +189,10 @@ export async function registerPlayer(playerKeypair: Keypair, playerAccountPubkey
 	const keys: AccountMeta[] = [
 		{ isSigner: true, isWritable: false, pubkey: playerKeypair.publicKey },
 		{ isSigner: false, isWritable: true, pubkey: playerAccountPubkey },
-		{ isSigner: false, isWritable: false, pubkey: programAccountPubkey },
+		{ isSigner: false, isWritable: true, pubkey: programAccountPubkey },
 	];
 	if (uplinePubkey) {
-		keys.push({ isSigner: false, isWritable: false, pubkey: uplinePubkey });
+		keys.push({ isSigner: false, isWritable: true, pubkey: uplinePubkey });
 	}
 	const transaction = new Transaction().add(
 		new TransactionInstruction({
@@ -299,3 +328,15 @@ export async function createTokenAccount(mintAccountKeypair: Keypair, ownerKeypa
 	console.log('Token account created', transactionSignature);
 	return tokenAccountKeypair;
 }
+
+export async function getGameStateOrThrow(gameAccountPubkey: PublicKey): Promise<IGameState> {
+	const gameAccountInfo = await connection.getAccountInfo(gameAccountPubkey);
+	if (!gameAccountInfo) {
+		throw new Error('Game account not found');
+	}
+	return fromSchemaDataToGameState(SchemaBuilder.deserialize(GameStateSchema, gameAccountInfo.data));
+}
+
+export async function getAssociatedTokenAddress(mintPubkey: PublicKey, ownerPubkey: PublicKey, tokenProgramId: PublicKey = SplToken.TOKEN_PROGRAM_ID): Promise<PublicKey> {
+	return SplToken.Token.getAssociatedTokenAddress(SplToken.ASSOCIATED_TOKEN_PROGRAM_ID, tokenProgramId, mintPubkey, ownerPubkey);
+}
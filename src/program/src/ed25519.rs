@@ -0,0 +1,79 @@
+use solana_program::{
+    account_info::AccountInfo, ed25519_program, msg, program_error::ProgramError, pubkey::Pubkey,
+    sysvar::instructions,
+};
+
+// Layout of the Ed25519 precompile instruction data, see solana_sdk::ed25519_instruction
+// 1 byte signature count, 1 byte padding, then 14 bytes of offsets for each signature
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+// Instruction index meaning the data is inside the Ed25519 instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+// Return the signer and message verified by the Ed25519 precompile instruction right before the current instruction
+// The runtime reject the transaction if the signature is invalid, so only the layout need to be checked here
+pub fn load_verified_message(
+    instructions_sysvar_account: &AccountInfo,
+) -> Result<(Pubkey, Vec<u8>), ProgramError> {
+    let current_index = instructions::load_current_index_checked(instructions_sysvar_account)?;
+    if current_index == 0 {
+        msg!("Ed25519 instruction must precede the current instruction");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let instruction = instructions::load_instruction_at_checked(
+        current_index as usize - 1,
+        instructions_sysvar_account,
+    )?;
+    if !ed25519_program::check_id(&instruction.program_id) {
+        msg!("Previous instruction is not an Ed25519 instruction");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let data = instruction.data;
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || data[0] != 1 {
+        msg!("Ed25519 instruction must verify exactly one signature");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let offsets: Vec<u16> = data
+        [SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN]
+        .chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect();
+    let (
+        signature_offset,
+        signature_instruction_index,
+        public_key_offset,
+        public_key_instruction_index,
+        message_data_offset,
+        message_data_size,
+        message_instruction_index,
+    ) = (
+        offsets[0] as usize,
+        offsets[1],
+        offsets[2] as usize,
+        offsets[3],
+        offsets[4] as usize,
+        offsets[5] as usize,
+        offsets[6],
+    );
+    // Signature, public key and message pointing to another instruction would let the caller swap the verified data
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        msg!("Ed25519 instruction must carry its own signature, public key and message");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if signature_offset + SIGNATURE_LEN > data.len()
+        || public_key_offset + PUBKEY_LEN > data.len()
+        || message_data_offset + message_data_size > data.len()
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let signer = Pubkey::new(&data[public_key_offset..public_key_offset + PUBKEY_LEN]);
+    let message = data[message_data_offset..message_data_offset + message_data_size].to_vec();
+    Ok((signer, message))
+}
@@ -30,6 +30,217 @@ pub enum GameError {
 
     #[error("Upline cannot be yourself")]
     SelfRecursiveUpline,
+
+    #[error("Player is not registered in the program account")]
+    PlayerGameMismatch,
+
+    #[error("Operator already added")]
+    OperatorAlreadyExists,
+
+    #[error("Operator not found")]
+    OperatorNotFound,
+
+    // All operator slots in the program account are taken
+    #[error("Operator list is full")]
+    OperatorListFull,
+
+    #[error("Operator reward cap exceeded")]
+    OperatorRewardCapExceeded,
+
+    // Token account or mint do not match with the mint recorded during initialization
+    #[error("Token mint mismatch")]
+    MintMismatch,
+
+    #[error("Token account is frozen")]
+    TokenAccountFrozen,
+
+    #[error("Insufficient balance in program token account")]
+    InsufficientVaultBalance,
+
+    #[error("Player token account is not the associated token account")]
+    InvalidPlayerTokenAccount,
+
+    #[error("Reward vault already registered for the mint")]
+    VaultAlreadyExists,
+
+    // Reward vault PDA not created by RegisterVault
+    #[error("Reward vault not found")]
+    VaultNotFound,
+
+    // Account address is not the PDA derived from the expected seeds
+    #[error("Invalid program derived account")]
+    InvalidPdaAccount,
+
+    // Instruction only available when the program account pay reward in SPL token, or in lamports
+    #[error("Instruction not supported by the payout mode")]
+    InvalidPayoutMode,
+
+    // Player or downline count exceed u64
+    #[error("Statistic counter overflow")]
+    StatisticOverflow,
+
+    // Upline already has the maximum number of direct referrals allowed by the program account
+    #[error("Upline direct referral limit reached")]
+    DirectReferralLimitReached,
+
+    // The new upline is a downline of the player
+    #[error("Upline cannot be a downline of the player")]
+    UplineCycle,
+
+    // Player already changed upline, or the grace period after registration is over
+    #[error("Upline change not allowed")]
+    UplineChangeNotAllowed,
+
+    // Referral code is empty or longer than MAX_REFERRAL_CODE_LEN
+    #[error("Invalid referral code")]
+    InvalidReferralCode,
+
+    #[error("Referral code already taken")]
+    ReferralCodeAlreadyExists,
+
+    #[error("Referral code not found")]
+    ReferralCodeNotFound,
+
+    // Voucher is not signed by the admin or an operator, or issued for another player
+    #[error("Invalid voucher")]
+    InvalidVoucher,
+
+    #[error("Voucher expired")]
+    VoucherExpired,
+
+    // Voucher nonce was already redeemed by the player
+    #[error("Voucher already redeemed")]
+    VoucherAlreadyRedeemed,
+
+    // Merkle proof does not lead to the distribution root
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof,
+
+    // Distribution index is already claimed
+    #[error("Distribution already claimed")]
+    DistributionAlreadyClaimed,
+
+    // Claim would exceed the distribution total
+    #[error("Distribution exhausted")]
+    DistributionExhausted,
+
+    // Player is frozen by the admin
+    #[error("Player frozen")]
+    PlayerFrozen,
+
+    // Registration gate of the game is not satisfied
+    #[error("Registration not allowed")]
+    RegistrationNotAllowed,
+
+    // Percentage is greater than 100
+    #[error("Invalid percentage")]
+    InvalidPercentage,
+
+    // Season end is not after start, or too many prizes
+    #[error("Invalid season")]
+    InvalidSeason,
+
+    // Score submitted outside of the season time range
+    #[error("Season not active")]
+    SeasonNotActive,
+
+    // Season finalized before the end
+    #[error("Season not ended")]
+    SeasonNotEnded,
+
+    #[error("Season already finalized")]
+    SeasonAlreadyFinalized,
+
+    // Stake vault of the game is not created
+    #[error("Stake vault not found")]
+    StakeVaultNotFound,
+
+    // Unstake more than the staked amount
+    #[error("Insufficient stake")]
+    InsufficientStake,
+
+    // Game already has MAX_QUESTS quests
+    #[error("Too many quests")]
+    TooManyQuests,
+
+    #[error("Quest expired")]
+    QuestExpired,
+
+    // Quest reached its maximum number of completions
+    #[error("Quest exhausted")]
+    QuestExhausted,
+
+    #[error("Quest already completed")]
+    QuestAlreadyCompleted,
+
+    // Wager is 0 or greater than the player reward
+    #[error("Invalid wager")]
+    InvalidWager,
+
+    // Player already has a flip not settled
+    #[error("Flip in progress")]
+    FlipInProgress,
+
+    // Flip instruction not allowed at the current step of the flip
+    #[error("Invalid flip state")]
+    InvalidFlipState,
+
+    // Revealed secret does not match the commitment
+    #[error("Invalid commitment")]
+    InvalidCommitment,
+
+    #[error("Flip timeout not reached")]
+    FlipTimeoutNotReached,
+
+    // Loot table without tier, too many tiers, zero total weight or zero price
+    #[error("Invalid loot table")]
+    InvalidLootTable,
+
+    // Player reward is lower than the loot box price
+    #[error("Insufficient reward")]
+    InsufficientReward,
+
+    // Player already has a loot box not revealed
+    #[error("Loot box in progress")]
+    LootBoxInProgress,
+
+    #[error("Loot box not opened")]
+    LootBoxNotOpened,
+
+    // The reveal slot is not produced yet
+    #[error("Loot box not ready")]
+    LootBoxNotReady,
+
+    // Check-in schedule longer than MAX_CHECK_IN_DAYS
+    #[error("Invalid check-in schedule")]
+    InvalidCheckInSchedule,
+
+    // Check-in schedule is empty
+    #[error("Check-in disabled")]
+    CheckInDisabled,
+
+    // Player already checked in during the current UTC day
+    #[error("Already checked in")]
+    AlreadyCheckedIn,
+
+    // Transfer of 0, or to the sender itself
+    #[error("Invalid transfer")]
+    InvalidTransfer,
+
+    // Lamports claimed by the claim delegate must go to the player (holder) account
+    #[error("Invalid claim destination")]
+    InvalidClaimDestination,
+
+    // Claim authorization is not signed by the player owner, or issued for another player
+    #[error("Invalid claim authorization")]
+    InvalidClaimAuthorization,
+
+    #[error("Claim authorization expired")]
+    ClaimAuthorizationExpired,
+
+    // Claim authorization nonce is not greater than the last relayed one
+    #[error("Claim authorization already used")]
+    ClaimAuthorizationUsed,
 }
 
 // Implement conversion for GameError to ProgramError
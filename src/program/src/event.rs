@@ -0,0 +1,336 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+use crate::state::{LootTier, PayoutMode, RegistrationGate};
+
+// Machine-readable record of what a Processor handler did, logged as "Program data: <base64 borsh>"
+// New variant must be appended at the end, as the borsh enum tag is the variant index
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    GameInitialized {
+        program_account: Pubkey,
+        admin: Pubkey,
+        payout_mode: PayoutMode,
+        // None when reward is paid in lamports
+        mint: Option<Pubkey>,
+    },
+    PlayerRegistered {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        owner: Pubkey,
+        upline: Option<Pubkey>,
+    },
+    RewardAdded {
+        program_account: Pubkey,
+        // The admin or operator adding the reward
+        authority: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        // None for the mint recorded during initialization, or lamports
+        mint: Option<Pubkey>,
+        player_amount: u64,
+        upline_amount: u64,
+    },
+    RewardClaimed {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        owner: Pubkey,
+        // None for the mint recorded during initialization, or lamports
+        mint: Option<Pubkey>,
+        amount: u64,
+    },
+    OperatorAdded {
+        program_account: Pubkey,
+        operator: Pubkey,
+        reward_cap: Option<u64>,
+    },
+    OperatorRemoved {
+        program_account: Pubkey,
+        operator: Pubkey,
+    },
+    VaultRegistered {
+        program_account: Pubkey,
+        mint: Pubkey,
+        vault: Pubkey,
+    },
+    Deposited {
+        program_account: Pubkey,
+        depositor: Pubkey,
+        amount: u64,
+    },
+    MaxDirectReferralsSet {
+        program_account: Pubkey,
+        max_direct_referrals: Option<u64>,
+    },
+    UplineChanged {
+        program_account: Pubkey,
+        // The admin or the player owner
+        authority: Pubkey,
+        player_program_account: Pubkey,
+        old_upline: Option<Pubkey>,
+        new_upline: Option<Pubkey>,
+    },
+    ReferralCodeClaimed {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        code: Vec<u8>,
+    },
+    VoucherRedeemed {
+        program_account: Pubkey,
+        // The admin or operator signing the voucher
+        authority: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        nonce: u64,
+        player_amount: u64,
+        upline_amount: u64,
+    },
+    DistributionPublished {
+        program_account: Pubkey,
+        distribution: Pubkey,
+        index: u64,
+        root: [u8; 32],
+        total: u64,
+    },
+    DistributionClaimed {
+        program_account: Pubkey,
+        distribution: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        // Leaf index in the distribution
+        index: u64,
+        player_amount: u64,
+        upline_amount: u64,
+    },
+    PlayerFrozen {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        // Unclaimed reward taken from the player, 0 without forfeit
+        forfeited_amount: u64,
+        // Unclaimed reward taken from the reward balances of the player by mint, empty without forfeit
+        forfeited_balances: Vec<(Pubkey, u64)>,
+    },
+    PlayerThawed {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+    },
+    RegistrationGateSet {
+        program_account: Pubkey,
+        registration_gate: RegistrationGate,
+        // Zero unless the registration gate is Allowlist
+        allowlist_root: [u8; 32],
+    },
+    RegistrationFeeSet {
+        program_account: Pubkey,
+        registration_fee: u64,
+        upline_percent: u8,
+    },
+    // Emitted along with PlayerRegistered when the registration fee is set
+    RegistrationFeePaid {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        amount: u64,
+        // Portion of the amount credited to the upline reward
+        upline_amount: u64,
+    },
+    SeasonCreated {
+        program_account: Pubkey,
+        season: Pubkey,
+        index: u64,
+        start: i64,
+        end: i64,
+        prizes: Vec<u64>,
+    },
+    ScoreSubmitted {
+        program_account: Pubkey,
+        season: Pubkey,
+        // The admin or operator submitting the score
+        authority: Pubkey,
+        player_program_account: Pubkey,
+        points: u64,
+        // Season score of the player after the submission
+        score: u64,
+        // Zero based leaderboard rank, None if the player is not on the leaderboard
+        rank: Option<u8>,
+    },
+    SeasonFinalized {
+        program_account: Pubkey,
+        season: Pubkey,
+        // Player program account and prize, from the first rank
+        winners: Vec<(Pubkey, u64)>,
+    },
+    StakeVaultCreated {
+        program_account: Pubkey,
+        stake_vault: Pubkey,
+    },
+    StakeRewardRateSet {
+        program_account: Pubkey,
+        // Reward per second shared by all the staked tokens
+        stake_reward_rate: u64,
+    },
+    Staked {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        amount: u64,
+        // Yield credited to the player reward before the stake changed
+        reward: u64,
+        staked_amount: u64,
+    },
+    Unstaked {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        amount: u64,
+        reward: u64,
+        staked_amount: u64,
+    },
+    QuestCreated {
+        program_account: Pubkey,
+        quest: Pubkey,
+        quest_id: u64,
+        reward: u64,
+        max_completions: Option<u64>,
+        expiry: Option<i64>,
+    },
+    QuestCompleted {
+        program_account: Pubkey,
+        quest: Pubkey,
+        quest_id: u64,
+        // The admin or operator completing the quest
+        authority: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        player_amount: u64,
+        upline_amount: u64,
+    },
+    FlipCommitted {
+        program_account: Pubkey,
+        flip: Pubkey,
+        player_program_account: Pubkey,
+        wager: u64,
+    },
+    FlipHouseCommitted {
+        program_account: Pubkey,
+        flip: Pubkey,
+        house: Pubkey,
+    },
+    FlipRevealed {
+        program_account: Pubkey,
+        flip: Pubkey,
+        // The player holder account or the house
+        revealer: Pubkey,
+    },
+    FlipSettled {
+        program_account: Pubkey,
+        flip: Pubkey,
+        player_program_account: Pubkey,
+        wager: u64,
+        // Credited back to the player reward, twice the wager if the player won, the wager if refunded, 0 if the house won
+        payout: u64,
+        // Settled by timeout instead of both reveals
+        timeout: bool,
+    },
+    LootTableSet {
+        program_account: Pubkey,
+        loot_table: Pubkey,
+        price: u64,
+        tiers: Vec<LootTier>,
+    },
+    LootBoxOpened {
+        program_account: Pubkey,
+        loot_box: Pubkey,
+        player_program_account: Pubkey,
+        price: u64,
+        reveal_slot: u64,
+    },
+    LootBoxRevealed {
+        program_account: Pubkey,
+        loot_box: Pubkey,
+        player_program_account: Pubkey,
+        // None when the reveal slot expired, the box is lost
+        tier: Option<u8>,
+        reward: u64,
+    },
+    CheckInScheduleSet {
+        program_account: Pubkey,
+        schedule: Vec<u64>,
+    },
+    CheckedIn {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        upline: Option<Pubkey>,
+        // Day of the streak, starting from 1
+        streak: u64,
+        player_amount: u64,
+        upline_amount: u64,
+    },
+    TransferFeeSet {
+        program_account: Pubkey,
+        fee_percent: u8,
+    },
+    RewardTransferred {
+        program_account: Pubkey,
+        // The sender player program account
+        from: Pubkey,
+        // The recipient player program account
+        to: Pubkey,
+        // Taken from the sender, the recipient receive amount - fee
+        amount: u64,
+        fee: u64,
+    },
+    ClaimDelegateSet {
+        program_account: Pubkey,
+        player_program_account: Pubkey,
+        // None when the delegate is removed
+        delegate: Option<Pubkey>,
+    },
+}
+
+impl GameEvent {
+    pub fn emit(&self) {
+        // Serializing to a Vec never fail
+        sol_log_data(&[&self.try_to_vec().unwrap()]);
+    }
+}
+
+#[cfg(not(target_arch = "bpf"))]
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+// Parse the events emitted by the program from transaction log messages
+// Only "Program data" logged while the program is the innermost invoked program are decoded, so data logged by CPI callee or other programs is skipped
+#[cfg(not(target_arch = "bpf"))]
+pub fn decode_events(
+    program_id: &Pubkey,
+    log_messages: &[String],
+) -> Result<Vec<GameEvent>, std::io::Error> {
+    let program_id = program_id.to_string();
+    let mut invoke_stack: Vec<&str> = vec![];
+    let mut events = vec![];
+    for log_message in log_messages {
+        if let Some(data) = log_message.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if invoke_stack.last() == Some(&program_id.as_str()) {
+                let data = base64::decode(data)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                events.push(GameEvent::try_from_slice(&data)?);
+            }
+            continue;
+        }
+
+        // "Program <id> invoke [<depth>]", "Program <id> success" or "Program <id> failed: <error>"
+        // Program log and return value are prefixed with "Program log:" and "Program return:", which is never a program id
+        let mut words = log_message.split(' ');
+        if let (Some("Program"), Some(id), Some(status)) =
+            (words.next(), words.next(), words.next())
+        {
+            if id.ends_with(':') {
+                continue;
+            }
+            if status == "invoke" {
+                invoke_stack.push(id);
+            } else if status == "success" || status == "failed:" {
+                invoke_stack.pop();
+            }
+        }
+    }
+    Ok(events)
+}
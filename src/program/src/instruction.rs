@@ -1,4 +1,16 @@
-use solana_program::{msg, program_error::ProgramError, program_option::COption, pubkey::Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg, program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+};
+
+use crate::{
+    error::GameError,
+    merkle::MAX_PROOF_LEN,
+    state::{
+        LootTier, RegistrationGate, MAX_CHECK_IN_DAYS, MAX_LOOT_TIERS, MAX_REFERRAL_CODE_LEN,
+        SEASON_LEADERBOARD_LEN,
+    },
+};
 
 // #[derive(BorshDeserialize, Debug)]
 // pub struct Payload {
@@ -14,35 +26,505 @@ pub enum Command {
     // 0 - [signer]   - The admin (holder) account
     // 1 - [writable] - Program account
     // 2 - [writable] - An token account created by the admin, and pre-funded
-    // 3 - []         - The token program
+    // 3 - []         - The token program, SPL TOKEN or Token-2022
+    // 4 - []         - The mint of the token account
     Init,
 
     // User register themselves to the program
     // tag = 1
     // 0 - [signer]   - The player (holder) account
     // 1 - [writable] - The player account for the program
-    // 2 - []         - The program account
-    // 3 - []         - The upline player account for the program
-    Register,
+    // 2 - [writable] - The program account
+    // 3 - [signer]   - The admin account, only if the registration gate is AdminSignature
+    // Only if the registration fee is set, the player (holder) account must be writable to pay in lamports
+    // 4 - [writable] - The player token account of the mint recorded during initialization, or the lamport vault
+    // 5 - [writable] - The token account of the current program, or the system program
+    // 6 - []         - The mint of the token account of the current program, only for token payout
+    // 7 - []         - The token program, only for token payout
+    // 8 - [writable] - The upline player account for the program
+    // With a referral code, the upline is resolved from the code instead
+    // 8 - []         - The referral code PDA of ["code", program account, code]
+    // 9 - [writable] - The upline player account owning the referral code
+    // Index 4 onward shift by -1 if the registration gate is not AdminSignature
+    // Upline accounts shift by -4 without registration fee, or by -2 for lamport payout
+    Register {
+        // Empty instruction data (no code) is accepted for backward compatibility
+        referral_code: Option<Vec<u8>>,
+        // Only required if the registration gate is Allowlist, can be omitted from instruction data otherwise
+        allowlist_proof: Vec<[u8; 32]>,
+    },
 
-    // Admin add reward to player
+    // Admin add reward to player, in the mint recorded during initialization when mint is None
     // tag = 2
-    // 0 - [signer]   - The admin (holder) account
+    // 0 - [signer]   - The admin (holder) account, writable when mint is provided as it pay for the reward balance creation
     // 1 - [writable] - Program account
     // 2 - [writable] - The player program account
-    // 3 - [writable] - The player upline program account
-    AddReward { reward_amount: u64 },
+    // 3 - [writable] - The player upline program account, only if the player has upline
+    // Only when mint is provided
+    // 4 - []         - The reward vault of the mint
+    // 5 - []         - The system program
+    // 6 - [writable] - The player reward balance of the mint, created if not exists
+    // 7 - [writable] - The upline reward balance of the mint, only if the player has upline which is not frozen
+    // Index 4 to 7 shift by -1 if the player has no upline
+    AddReward {
+        reward_amount: u64,
+        mint: COption<Pubkey>,
+    },
 
-    // Player claim reward
+    // Player claim reward, from the token account of the current program when mint is None
+    // When the program account pay in lamports, only account 0 to 2 and the lamport vault (writable) as account 3 are required, mint must be None
+    // Lamports claimed by the claim delegate go to the player (holder) account, passed as account 4 (writable)
     // tag = 3
-    // 0 - [signer]   - The player (holder) account
+    // 0 - [signer, writable] - The player (holder) account or its claim delegate, pay for the associated token account creation
     // 1 - [writable] - Program account
     // 2 - [writable] - The player program account
-    // 3 - []         - The token account of the current program
+    // 3 - []         - The token account of the current program, or the reward vault of the mint
     // 4-  []         - The PDA, owner (in term of token, not account owner) of token account
-    // 5 - []         - The player token account
+    // 5 - [writable] - The player payout token account, or the player associated token account of the mint, created if not exists
     // 6 - []         - The token program
-    Claim,
+    // 7 - []         - The mint of the token account of the current program
+    // 8 - []         - The system program
+    // 9 - []         - The associated token account program
+    // 10 - []        - The rent sysvar
+    // 11 - [writable] - The player reward balance of the mint, only when mint is provided
+    Claim {
+        mint: COption<Pubkey>,
+    },
+
+    // Admin grant a key permission to add reward, optionally limited by a cap in the game mint
+    // An operator with a cap can't add reward in the mints of other reward vaults
+    // tag = 4
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - []         - The operator (holder) account
+    AddOperator {
+        reward_cap: COption<u64>,
+    },
+
+    // Admin revoke a key permission to add reward
+    // tag = 5
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - []         - The operator (holder) account
+    RemoveOperator,
+
+    // Admin register a reward vault for another mint, the vault is a token account at PDA of the mint
+    // tag = 6
+    // 0 - [signer, writable] - The admin (holder) account, pay for the reward vault creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The reward vault, PDA of ["vault", program account, mint]
+    // 3 - []         - The PDA, owner (in term of token, not account owner) of the reward vault
+    // 4 - []         - The mint of the reward vault
+    // 5 - []         - The token program, SPL TOKEN or Token-2022
+    // 6 - []         - The system program
+    // 7 - []         - The rent sysvar
+    RegisterVault,
+
+    // Start initialize the program account, paying reward in lamports instead of SPL token
+    // tag = 7
+    // 0 - [signer, writable] - The admin (holder) account, pay for the lamport vault creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The lamport vault, PDA of ["lamport_vault", program account]
+    // 3 - []         - The system program
+    InitLamports,
+
+    // Fund the lamport vault
+    // tag = 8
+    // 0 - [signer, writable] - The depositor (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The lamport vault
+    // 3 - []         - The system program
+    Deposit {
+        amount: u64,
+    },
+
+    // Admin limit the number of players registered with the same upline, None to remove the limit
+    // tag = 9
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetMaxDirectReferrals {
+        max_direct_referrals: COption<u64>,
+    },
+
+    // Change the upline of a registered player, None to remove the upline
+    // The admin can change it any time, the player only once within the grace period after registration
+    // tag = 10
+    // 0 - [signer]   - The admin or the player (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The current upline player program account, only if the player has upline
+    // 4 - [writable] - The new upline player program account, only if upline is provided
+    // 5.. - []       - The upline chain of the new upline, from its upline up to the player without upline
+    // Index 4 onward shift by -1 if the player has no upline
+    SetUpline {
+        upline: COption<Pubkey>,
+    },
+
+    // Player claim a referral code, so other players can register with the code instead of the player account
+    // tag = 11
+    // 0 - [signer, writable] - The player (holder) account, pay for the referral code creation
+    // 1 - []         - Program account
+    // 2 - []         - The player program account
+    // 3 - [writable] - The referral code PDA of ["code", program account, code]
+    // 4 - []         - The system program
+    ClaimReferralCode {
+        code: Vec<u8>,
+    },
+
+    // Player redeem a reward voucher signed by the admin or an operator
+    // The previous instruction must be an Ed25519 instruction verifying the signature of the borsh serialized Voucher
+    // Reward is credited in the mint recorded during initialization (or lamports), with upline commission
+    // tag = 12
+    // 0 - [signer, writable] - The player (holder) account, pay for the voucher bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - []         - The instructions sysvar
+    // 4 - [writable] - The voucher bitmap PDA of ["vouchers", player program account, nonce / VOUCHER_BITMAP_BITS]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    RedeemVoucher,
+
+    // Admin publish a bulk reward as the Merkle root of (index, player program account, amount) leaves
+    // Reward is credited in the mint recorded during initialization (or lamports), with upline commission
+    // tag = 13
+    // 0 - [signer, writable] - The admin (holder) account, pay for the distribution creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The distribution PDA of ["distribution", program account, distribution count]
+    // 3 - []         - The system program
+    PublishDistribution {
+        root: [u8; 32],
+        total: u64,
+    },
+
+    // Player claim its leaf of a published distribution, each index can only be claimed once
+    // tag = 14
+    // 0 - [signer, writable] - The player (holder) account, pay for the claimed bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The distribution PDA
+    // 4 - [writable] - The claimed bitmap PDA of ["claimed", distribution, index / DISTRIBUTION_BITMAP_BITS]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    ClaimFromDistribution {
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    },
+
+    // Admin freeze a player, a frozen player cannot receive or claim reward
+    // With forfeit, the unclaimed reward of the player and of the given reward balances is reset
+    // With forfeit, the unclaimed reward of the player and of the given reward balances is reset and kept in the vault
+    // tag = 15
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3.. - [writable] - The reward balance PDAs of the player to forfeit, only with forfeit
+    FreezePlayer {
+        forfeit: bool,
+    },
+
+    // Admin thaw a frozen player
+    // tag = 16
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    ThawPlayer,
+
+    // Admin restrict who can register in the game, players already registered are not affected
+    // tag = 17
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetRegistrationGate {
+        registration_gate: RegistrationGate,
+        // Only provided in instruction data if the registration gate is Allowlist
+        allowlist_root: [u8; 32],
+    },
+
+    // Admin set the fee paid by the player on registration, in the mint recorded during initialization (or lamports)
+    // tag = 18
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetRegistrationFee {
+        // 0 for free registration
+        registration_fee: u64,
+        // Percentage of the fee credited to the upline reward, at most 100
+        upline_percent: u8,
+    },
+
+    // Admin create a season, scores can be submitted from start (inclusive) to end (exclusive)
+    // tag = 19
+    // 0 - [signer, writable] - The admin (holder) account, pay for the season creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The season PDA of ["season", program account, season count]
+    // 3 - []         - The system program
+    CreateSeason {
+        start: i64,
+        end: i64,
+        // Prize of each rank from the first, at most SEASON_LEADERBOARD_LEN
+        prizes: Vec<u64>,
+    },
+
+    // Admin or operator add points to the season score of a player, and update the leaderboard
+    // tag = 20
+    // 0 - [signer, writable] - The admin or operator (holder) account, pay for the season score creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The season PDA
+    // 3 - []         - The player program account
+    // 4 - [writable] - The season score PDA of ["score", season, player program account]
+    // 5 - []         - The system program
+    SubmitScore {
+        points: u64,
+    },
+
+    // Admin award the prizes to the leaderboard players after the season end
+    // Prizes are credited in the mint recorded during initialization (or lamports), without upline commission
+    // tag = 21
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The season PDA
+    // 3.. - [writable] - The player program account of each leaderboard rank with prize, from the first rank
+    FinalizeSeason,
+
+    // Admin create the stake vault, a token account of the mint recorded during initialization owned by the PDA
+    // tag = 22
+    // 0 - [signer, writable] - The admin (holder) account, pay for the stake vault creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The stake vault PDA of ["stake", program account]
+    // 3 - []         - The PDA account
+    // 4 - []         - The mint recorded during initialization
+    // 5 - []         - The token program recorded during initialization
+    // 6 - []         - The system program
+    // 7 - []         - The rent sysvar
+    InitStakeVault,
+
+    // Admin set the staking reward per second, shared by all the staked tokens
+    // The yield accrued with the previous rate is accumulated first
+    // tag = 23
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetStakeRewardRate {
+        stake_reward_rate: u64,
+    },
+
+    // Player lock tokens in the stake vault, the yield accrued so far is credited to the player reward
+    // tag = 24
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The token account of the player holding the tokens to stake
+    // 4 - [writable] - The stake vault PDA
+    // 5 - []         - The mint recorded during initialization
+    // 6 - []         - The token program recorded during initialization
+    Stake {
+        amount: u64,
+    },
+
+    // Player withdraw staked tokens, the yield accrued so far is credited to the player reward
+    // Unstake 0 only credit the yield
+    // tag = 25
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The token account of the player receiving the tokens, owned by the player holder account
+    // 4 - [writable] - The stake vault PDA
+    // 5 - []         - The PDA account
+    // 6 - []         - The mint recorded during initialization
+    // 7 - []         - The token program recorded during initialization
+    Unstake {
+        amount: u64,
+    },
+
+    // Admin create a quest, the quest id is the current quest count
+    // tag = 26
+    // 0 - [signer, writable] - The admin (holder) account, pay for the quest creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The quest PDA of ["quest", program account, quest count]
+    // 3 - []         - The system program
+    CreateQuest {
+        // In the mint recorded during initialization (or lamports), the upline receive 10%
+        reward: u64,
+        // No limit when None
+        max_completions: COption<u64>,
+        // Unix timestamp, never expire when None
+        expiry: COption<i64>,
+    },
+
+    // Admin or operator complete a quest for a player, each player can complete a quest once
+    // Operator reward cap apply to the quest reward
+    // tag = 27
+    // 0 - [signer, writable] - The admin or operator (holder) account, pay for the quest bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The quest PDA
+    // 3 - [writable] - The player program account
+    // 4 - [writable] - The quest bitmap PDA of ["quests", player program account]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    CompleteQuest {
+        quest_id: u64,
+    },
+
+    // Player start a coin flip against the house, the wager is taken from the player reward
+    // tag = 28
+    // 0 - [signer, writable] - The player (holder) account, pay for the flip creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The flip PDA of ["flip", player program account]
+    // 4 - []         - The system program
+    CommitFlip {
+        wager: u64,
+        // sha256 of the player secret
+        commitment: [u8; 32],
+    },
+
+    // Admin or operator commit as the house of the flip
+    // The wager is charged to the operator reward cap, the part not won by the player is given back on settlement
+    // tag = 29
+    // 0 - [signer]   - The admin or operator (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The flip PDA
+    HouseCommitFlip {
+        // sha256 of the house secret
+        commitment: [u8; 32],
+    },
+
+    // Player or house reveal its secret, the flip is settled once both are revealed
+    // The player win if the lowest bit of sha256(player secret, house secret) first byte is 0
+    // tag = 30
+    // 0 - [signer]   - The player (holder) account or the house
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The flip PDA
+    RevealFlip {
+        secret: [u8; 32],
+    },
+
+    // Settle a flip stuck for FLIP_TIMEOUT seconds after the last commit, anyone can call
+    // Without house commitment or any reveal the wager is refunded, otherwise the only party who revealed win
+    // tag = 31
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The flip PDA
+    ResolveFlipTimeout,
+
+    // Admin set the price and the reward tiers of the loot boxes, the loot table is created on the first call
+    // tag = 32
+    // 0 - [signer, writable] - The admin (holder) account, pay for the loot table creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The loot table PDA of ["loot_table", program account]
+    // 3 - []         - The system program
+    SetLootTable {
+        price: u64,
+        // 1 to MAX_LOOT_TIERS tiers, with a total weight greater than 0
+        tiers: Vec<LootTier>,
+    },
+
+    // Player open a loot box, the price is taken from the player reward
+    // The tier is decided by the hash of the slot LOOT_BOX_DELAY slots later, see RevealBox
+    // tag = 33
+    // 0 - [signer, writable] - The player (holder) account, pay for the loot box creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - []         - The loot table PDA
+    // 4 - [writable] - The loot box PDA of ["box", player program account]
+    // 5 - []         - The system program
+    OpenBox,
+
+    // Credit the reward of the tier picked by the hash of the reveal slot, anyone can call
+    // A box not revealed while the SlotHashes sysvar still has the reveal slot is lost
+    // The tiers are copied into the loot box by OpenBox, a later SetLootTable does not change them
+    // tag = 34
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The loot box PDA
+    // 3 - []         - The SlotHashes sysvar
+    RevealBox,
+
+    // Admin set the reward of each day of a check-in streak, an empty schedule disable check-in
+    // tag = 35
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetCheckInSchedule {
+        // At most MAX_CHECK_IN_DAYS rewards, the last one is repeated once the streak is longer
+        schedule: Vec<u64>,
+    },
+
+    // Player check in once per UTC day for the reward of its streak day, the upline receive 10%
+    // The streak restart from the first day when a day is missed
+    // tag = 36
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The player upline program account, only if the player has upline
+    CheckIn,
+
+    // Admin set the percentage of a reward transfer between players kept in the vault
+    // tag = 37
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    SetTransferFee {
+        // 0 for free transfer, at most 100
+        fee_percent: u8,
+    },
+
+    // Player give part of its reward to another player of the same game
+    // tag = 38
+    // 0 - [signer]   - The sender player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The sender player program account
+    // 3 - [writable] - The recipient player program account
+    TransferReward {
+        // Taken from the sender reward, the recipient receive it minus the transfer fee
+        amount: u64,
+    },
+
+    // Player allow another account to sign Claim on its behalf, the reward still goes to the player
+    // tag = 39
+    // 0 - [signer]   - The player (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    SetClaimDelegate {
+        // None to remove the delegate
+        delegate: COption<Pubkey>,
+    },
+
+    // Anyone relay a claim authorized by the player, so the player doesn't need to sign or pay the fee
+    // The previous instruction must be an Ed25519 instruction verifying the owner signature of the borsh serialized ClaimAuthorization
+    // Reward is paid in the mint recorded during initialization to the payout token account locked at Register, which must exist
+    // tag = 40
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The token account of the current program
+    // 3 - []         - The PDA, owner (in term of token, not account owner) of token account
+    // 4 - [writable] - The player payout token account
+    // 5 - []         - The token program
+    // 6 - []         - The mint of the token account of the current program
+    // 7 - []         - The instructions sysvar
+    RelayClaim,
+}
+
+// Message signed by the admin or an operator to award a player off-chain
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Voucher {
+    pub program_account: Pubkey,
+    // The player program account
+    pub player: Pubkey,
+    pub amount: u64,
+    // Each nonce can be redeemed once per player, in any order
+    pub nonce: u64,
+    // Unix timestamp after which the voucher can no longer be redeemed
+    pub expiry: i64,
+}
+
+// Message signed by the player owner to let anyone submit its claim
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ClaimAuthorization {
+    pub program_account: Pubkey,
+    // The player program account
+    pub player: Pubkey,
+    // Must be greater than the nonce of the last claim authorization relayed for the player
+    pub nonce: u64,
+    // Unix timestamp after which the authorization can no longer be relayed
+    pub expiry: i64,
 }
 
 impl Command {
@@ -62,18 +544,368 @@ impl Command {
         msg!("Instruction tag {}", tag);
         Ok(match tag {
             0 => Self::Init, // use statement instead of return, which terminate the function. The Self::Init will be passed into Ok enum return return by unpack function
-            1 => Self::Register,
+            1 => {
+                let (referral_code, rest) = match rest.split_first() {
+                    None => (None, rest),
+                    Some((&0, rest)) => (None, rest),
+                    Some((&1, rest)) => {
+                        let (referral_code, rest) = Self::unpack_referral_code(rest)?;
+                        (Some(referral_code), rest)
+                    }
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                let allowlist_proof = if rest.is_empty() {
+                    Vec::new()
+                } else {
+                    Self::unpack_proof(rest)?.0
+                };
+                Self::Register {
+                    referral_code,
+                    allowlist_proof,
+                }
+            }
             2 => {
-                // Get 8 bytes (u64)
-                // Borsh serialization is little endian, therefore use from_le_bytes
-                let reward_amount = u64::from_le_bytes(rest[0..8].try_into().unwrap());
-                Self::AddReward { reward_amount }
+                let (reward_amount, rest) = Self::unpack_u64(rest)?;
+                let (mint, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::AddReward {
+                    reward_amount,
+                    mint,
+                }
+            }
+            3 => {
+                let (mint, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::Claim { mint }
+            }
+            4 => {
+                let (reward_cap, _rest) = Self::unpack_u64_option(rest)?;
+                Self::AddOperator { reward_cap }
+            }
+            5 => Self::RemoveOperator,
+            6 => Self::RegisterVault,
+            7 => Self::InitLamports,
+            8 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::Deposit { amount }
+            }
+            9 => {
+                let (max_direct_referrals, _rest) = Self::unpack_u64_option(rest)?;
+                Self::SetMaxDirectReferrals {
+                    max_direct_referrals,
+                }
+            }
+            10 => {
+                let (upline, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::SetUpline { upline }
+            }
+            11 => {
+                let (code, _rest) = Self::unpack_referral_code(rest)?;
+                Self::ClaimReferralCode { code }
+            }
+            12 => Self::RedeemVoucher,
+            13 => {
+                let (root, rest) = Self::unpack_hash(rest)?;
+                let (total, _rest) = Self::unpack_u64(rest)?;
+                Self::PublishDistribution { root, total }
+            }
+            14 => {
+                let (index, rest) = Self::unpack_u64(rest)?;
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                let (proof, _rest) = Self::unpack_proof(rest)?;
+                Self::ClaimFromDistribution {
+                    index,
+                    amount,
+                    proof,
+                }
+            }
+            15 => {
+                let forfeit = match rest.first() {
+                    Some(&0) => false,
+                    Some(&1) => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                Self::FreezePlayer { forfeit }
+            }
+            16 => Self::ThawPlayer,
+            17 => {
+                let (registration_gate, rest) = match rest.split_first() {
+                    Some((&0, rest)) => (RegistrationGate::Open, rest),
+                    Some((&1, rest)) => (RegistrationGate::AdminSignature, rest),
+                    Some((&2, rest)) => (RegistrationGate::Allowlist, rest),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                let allowlist_root = match registration_gate {
+                    RegistrationGate::Allowlist => Self::unpack_hash(rest)?.0,
+                    _ => [0; 32],
+                };
+                Self::SetRegistrationGate {
+                    registration_gate,
+                    allowlist_root,
+                }
+            }
+            18 => {
+                let (registration_fee, rest) = Self::unpack_u64(rest)?;
+                let upline_percent = *rest.first().ok_or(ProgramError::InvalidInstructionData)?;
+                Self::SetRegistrationFee {
+                    registration_fee,
+                    upline_percent,
+                }
+            }
+            19 => {
+                let (start, rest) = Self::unpack_i64(rest)?;
+                let (end, rest) = Self::unpack_i64(rest)?;
+                let (prizes, _rest) = Self::unpack_prizes(rest)?;
+                Self::CreateSeason { start, end, prizes }
+            }
+            20 => {
+                let (points, _rest) = Self::unpack_u64(rest)?;
+                Self::SubmitScore { points }
             }
-            3 => Self::Claim,
+            21 => Self::FinalizeSeason,
+            22 => Self::InitStakeVault,
+            23 => {
+                let (stake_reward_rate, _rest) = Self::unpack_u64(rest)?;
+                Self::SetStakeRewardRate { stake_reward_rate }
+            }
+            24 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::Stake { amount }
+            }
+            25 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::Unstake { amount }
+            }
+            26 => {
+                let (reward, rest) = Self::unpack_u64(rest)?;
+                let (max_completions, rest) = Self::unpack_u64_option(rest)?;
+                let (expiry, _rest) = Self::unpack_i64_option(rest)?;
+                Self::CreateQuest {
+                    reward,
+                    max_completions,
+                    expiry,
+                }
+            }
+            27 => {
+                let (quest_id, _rest) = Self::unpack_u64(rest)?;
+                Self::CompleteQuest { quest_id }
+            }
+            28 => {
+                let (wager, rest) = Self::unpack_u64(rest)?;
+                let (commitment, _rest) = Self::unpack_hash(rest)?;
+                Self::CommitFlip { wager, commitment }
+            }
+            29 => {
+                let (commitment, _rest) = Self::unpack_hash(rest)?;
+                Self::HouseCommitFlip { commitment }
+            }
+            30 => {
+                let (secret, _rest) = Self::unpack_hash(rest)?;
+                Self::RevealFlip { secret }
+            }
+            31 => Self::ResolveFlipTimeout,
+            32 => {
+                let (price, rest) = Self::unpack_u64(rest)?;
+                let (tiers, _rest) = Self::unpack_loot_tiers(rest)?;
+                Self::SetLootTable { price, tiers }
+            }
+            33 => Self::OpenBox,
+            34 => Self::RevealBox,
+            35 => {
+                let (schedule, _rest) = Self::unpack_check_in_schedule(rest)?;
+                Self::SetCheckInSchedule { schedule }
+            }
+            36 => Self::CheckIn,
+            37 => {
+                let fee_percent = *rest.first().ok_or(ProgramError::InvalidInstructionData)?;
+                Self::SetTransferFee { fee_percent }
+            }
+            38 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::TransferReward { amount }
+            }
+            39 => {
+                let (delegate, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::SetClaimDelegate { delegate }
+            }
+            40 => Self::RelayClaim,
             _ => return Err(ProgramError::InvalidInstructionData), // early return the unpack function with Err, instead of returning the Err as argument for Ok
         })
     }
 
+    // Get 8 bytes (u64)
+    // Borsh serialization is little endian, therefore use from_le_bytes
+    pub fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (amount, rest) = input.split_at(8);
+        let amount = u64::from_le_bytes(
+            amount
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        );
+        Ok((amount, rest))
+    }
+
+    // Same as unpack_u64, but signed
+    pub fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+        let (value, rest) = Self::unpack_u64(input)?;
+        Ok((value as i64, rest))
+    }
+
+    // Prizes are serialized as borsh Vec<u64>, 4 bytes length followed by the prizes
+    pub fn unpack_prizes(input: &[u8]) -> Result<(Vec<u64>, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, mut rest) = input.split_at(4);
+        let len = u32::from_le_bytes(
+            len.try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ) as usize;
+        if len > SEASON_LEADERBOARD_LEN {
+            msg!("Season must have at most {} prizes", SEASON_LEADERBOARD_LEN);
+            return Err(GameError::InvalidSeason.into());
+        }
+        let mut prizes = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (prize, next) = Self::unpack_u64(rest)?;
+            prizes.push(prize);
+            rest = next;
+        }
+        Ok((prizes, rest))
+    }
+
+    // Schedule is serialized as borsh Vec<u64>, 4 bytes length followed by the rewards
+    pub fn unpack_check_in_schedule(input: &[u8]) -> Result<(Vec<u64>, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, mut rest) = input.split_at(4);
+        let len = u32::from_le_bytes(
+            len.try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ) as usize;
+        if len > MAX_CHECK_IN_DAYS {
+            msg!(
+                "Check-in schedule must have at most {} days",
+                MAX_CHECK_IN_DAYS
+            );
+            return Err(GameError::InvalidCheckInSchedule.into());
+        }
+        let mut schedule = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (reward, next) = Self::unpack_u64(rest)?;
+            schedule.push(reward);
+            rest = next;
+        }
+        Ok((schedule, rest))
+    }
+
+    // Tiers are serialized as borsh Vec<LootTier>, 4 bytes length followed by the weight (u32) and reward (u64) of each tier
+    pub fn unpack_loot_tiers(input: &[u8]) -> Result<(Vec<LootTier>, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, rest) = input.split_at(4);
+        let len = u32::from_le_bytes(
+            len.try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ) as usize;
+        if len == 0 || len > MAX_LOOT_TIERS {
+            msg!("Loot table must have 1 to {} tiers", MAX_LOOT_TIERS);
+            return Err(GameError::InvalidLootTable.into());
+        }
+        if rest.len() < len * LootTier::LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (tiers, rest) = rest.split_at(len * LootTier::LEN);
+        let tiers = tiers
+            .chunks_exact(LootTier::LEN)
+            .map(LootTier::unpack_from_slice)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((tiers, rest))
+    }
+
+    // Get 32 bytes hash
+    pub fn unpack_hash(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (hash, rest) = input.split_at(32);
+        let hash = hash
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok((hash, rest))
+    }
+
+    // Merkle proof is serialized as borsh Vec<[u8; 32]>, 4 bytes length followed by the hashes
+    pub fn unpack_proof(input: &[u8]) -> Result<(Vec<[u8; 32]>, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, mut rest) = input.split_at(4);
+        let len = u32::from_le_bytes(
+            len.try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ) as usize;
+        if len > MAX_PROOF_LEN {
+            msg!("Merkle proof must be at most {} hashes", MAX_PROOF_LEN);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let mut proof = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (hash, next) = Self::unpack_hash(rest)?;
+            proof.push(hash);
+            rest = next;
+        }
+        Ok((proof, rest))
+    }
+
+    // Referral code is serialized as borsh Vec<u8>, 4 bytes length followed by the code
+    pub fn unpack_referral_code(input: &[u8]) -> Result<(Vec<u8>, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (len, rest) = input.split_at(4);
+        let len = u32::from_le_bytes(
+            len.try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ) as usize;
+        if len == 0 || len > MAX_REFERRAL_CODE_LEN {
+            msg!("Referral code must be 1 to {} bytes", MAX_REFERRAL_CODE_LEN);
+            return Err(GameError::InvalidReferralCode.into());
+        }
+        if rest.len() < len {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (code, rest) = rest.split_at(len);
+        Ok((code.to_vec(), rest))
+    }
+
+    // Same as unpack_pubkey_option, but the following 8 bytes will be u64
+    pub fn unpack_u64_option(input: &[u8]) -> Result<(COption<u64>, &[u8]), ProgramError> {
+        match input.split_first() {
+            Option::Some((&0, rest)) => Ok((COption::None, rest)),
+            Option::Some((&1, rest)) => {
+                let (amount, rest) = Self::unpack_u64(rest)?;
+                Ok((COption::Some(amount), rest))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // Same as unpack_u64_option, but the following 8 bytes will be i64
+    pub fn unpack_i64_option(input: &[u8]) -> Result<(COption<i64>, &[u8]), ProgramError> {
+        match input.split_first() {
+            Option::Some((&0, rest)) => Ok((COption::None, rest)),
+            Option::Some((&1, rest)) => {
+                let (timestamp, rest) = Self::unpack_i64(rest)?;
+                Ok((COption::Some(timestamp), rest))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
     // Assume the received byte buffer, the starting of it will be Option<Pubkey>
     // When Option being serialized, 1st byte will indicate it is Option::None or Option::Some
     // The following 32 bytes will be the Pubkey
@@ -83,7 +915,7 @@ impl Command {
             // 1st byte is 0
             Option::Some((&0, rest)) => Ok((COption::None, rest)),
             // 1st byte is 1
-            Option::Some((&1, rest)) => {
+            Option::Some((&1, rest)) if rest.len() >= 32 => {
                 // Take 32 bytes after the indication byte, the following byte after the public key will shadow the rest variable in argument
                 let (pub_key, rest) = rest.split_at(32);
                 // Create PubKey instance
@@ -1,7 +1,12 @@
+pub mod ed25519;
 pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod merkle;
 pub mod processor;
+pub mod slot_hash;
 pub mod state;
+pub mod token;
 
 // If other solana program import our program, the entrypoint of this program can be switch off at Cargo.toml
 #[cfg(not(feature = "no-entrypoint"))]
@@ -0,0 +1,31 @@
+use solana_program::hash::hashv;
+
+// Prefix of leaf and inner node hashes, so a leaf can never be passed as an inner node
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+// Enough for a tree of 2^32 leaves
+pub const MAX_PROOF_LEN: usize = 32;
+
+// Hash of a leaf, the data is concatenated as is
+pub fn hash_leaf(data: &[&[u8]]) -> [u8; 32] {
+    let mut vals = vec![LEAF_PREFIX];
+    vals.extend_from_slice(data);
+    hashv(&vals).to_bytes()
+}
+
+// Hash of an inner node, children are sorted so the proof does not need the left / right position
+pub fn hash_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[NODE_PREFIX, a, b]).to_bytes()
+    } else {
+        hashv(&[NODE_PREFIX, b, a]).to_bytes()
+    }
+}
+
+// Check the leaf is part of the tree, proof is the sibling hashes from the leaf up to the root
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |hash, sibling| hash_node(&hash, sibling));
+    computed == *root
+}
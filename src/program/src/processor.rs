@@ -1,22 +1,76 @@
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
+    hash::hashv,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::{clock::Clock, Sysvar},
 };
 pub struct Processor;
 
 // Import command module, for parsing instruction_data
-use crate::instruction::Command;
+use crate::ed25519;
+use crate::instruction::{ClaimAuthorization, Command, Voucher};
 // Import state module
 use crate::error::GameError;
-use crate::state::{GameInfo, Player};
+use crate::event::GameEvent;
+use crate::merkle;
+use crate::slot_hash::{self, SlotHash};
+use crate::state::{
+    Distribution, Flip, FlipStatus, GameInfo, LeaderboardEntry, LootBox, LootTable, LootTier,
+    Operator, PayoutMode, Player, Quest, ReferralCode, RegistrationGate, RewardBalance, Season,
+    SeasonScore, DISTRIBUTION_BITMAP_BITS, DISTRIBUTION_BITMAP_LEN, MAX_CHECK_IN_DAYS,
+    MAX_LOOT_TIERS, MAX_QUESTS, QUEST_BITMAP_LEN, SEASON_LEADERBOARD_LEN, VOUCHER_BITMAP_BITS,
+    VOUCHER_BITMAP_LEN,
+};
+use crate::token;
 
 const PDA_SEED: &str = "game_seed";
+// Seed of the reward vault PDA, followed by the program account and the mint
+const VAULT_SEED: &str = "vault";
+// Seed of the player reward balance PDA, followed by the player program account and the mint
+const BALANCE_SEED: &str = "balance";
+// Seed of the lamport vault PDA, followed by the program account
+const LAMPORT_VAULT_SEED: &str = "lamport_vault";
+// Seed of the referral code PDA, followed by the program account and the code
+const REFERRAL_CODE_SEED: &str = "code";
+// Seconds after registration during which the player can change upline once
+const UPLINE_CHANGE_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
+// Seed of the player voucher bitmap PDA, followed by the player program account and the bitmap index
+const VOUCHER_BITMAP_SEED: &str = "vouchers";
+// Seed of the distribution PDA, followed by the program account and the distribution index
+const DISTRIBUTION_SEED: &str = "distribution";
+// Seed of the distribution claimed bitmap PDA, followed by the distribution and the bitmap index
+const DISTRIBUTION_BITMAP_SEED: &str = "claimed";
+// Seed of the season PDA, followed by the program account and the season index
+const SEASON_SEED: &str = "season";
+// Seed of the season score PDA, followed by the season and the player program account
+const SEASON_SCORE_SEED: &str = "score";
+// Seed of the stake vault PDA, followed by the program account
+const STAKE_VAULT_SEED: &str = "stake";
+// Seed of the quest PDA, followed by the program account and the quest id
+const QUEST_SEED: &str = "quest";
+// Seed of the player quest bitmap PDA, followed by the player program account
+const QUEST_BITMAP_SEED: &str = "quests";
+// Seed of the flip PDA, followed by the player program account
+const FLIP_SEED: &str = "flip";
+// Seconds after the last commit from which a stuck flip can be resolved
+const FLIP_TIMEOUT: i64 = 60 * 60;
+// Seed of the loot table PDA, followed by the program account
+const LOOT_TABLE_SEED: &str = "loot_table";
+// Seed of the loot box PDA, followed by the player program account
+const LOOT_BOX_SEED: &str = "box";
+// Slots between opening a loot box and the slot whose hash decides its tier
+const LOOT_BOX_DELAY: u64 = 10;
+// Check-in days are UTC days, counted from the unix epoch
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
 
 impl Processor {
     pub fn process(
@@ -27,92 +81,4075 @@ impl Processor {
         let instruction = Command::unpack(instruction_data)?;
         match instruction {
             Command::Init => Self::process_init(program_id, accounts),
-            Command::Register => Self::process_register(program_id, accounts),
-            Command::AddReward { reward_amount } => {
-                Self::process_add_reward(program_id, accounts, reward_amount)
+            Command::Register {
+                referral_code,
+                allowlist_proof,
+            } => Self::process_register(program_id, accounts, referral_code, allowlist_proof),
+            Command::AddReward {
+                reward_amount,
+                mint,
+            } => Self::process_add_reward(program_id, accounts, reward_amount, mint),
+            Command::Claim { mint } => Self::process_claim_reward(program_id, accounts, mint),
+            Command::AddOperator { reward_cap } => {
+                Self::process_add_operator(program_id, accounts, reward_cap)
+            }
+            Command::RemoveOperator => Self::process_remove_operator(program_id, accounts),
+            Command::RegisterVault => Self::process_register_vault(program_id, accounts),
+            Command::InitLamports => Self::process_init_lamports(program_id, accounts),
+            Command::Deposit { amount } => Self::process_deposit(program_id, accounts, amount),
+            Command::SetMaxDirectReferrals {
+                max_direct_referrals,
+            } => Self::process_set_max_direct_referrals(program_id, accounts, max_direct_referrals),
+            Command::SetUpline { upline } => Self::process_set_upline(program_id, accounts, upline),
+            Command::ClaimReferralCode { code } => {
+                Self::process_claim_referral_code(program_id, accounts, code)
+            }
+            Command::RedeemVoucher => Self::process_redeem_voucher(program_id, accounts),
+            Command::PublishDistribution { root, total } => {
+                Self::process_publish_distribution(program_id, accounts, root, total)
+            }
+            Command::ClaimFromDistribution {
+                index,
+                amount,
+                proof,
+            } => Self::process_claim_from_distribution(program_id, accounts, index, amount, proof),
+            Command::FreezePlayer { forfeit } => {
+                Self::process_freeze_player(program_id, accounts, forfeit)
+            }
+            Command::ThawPlayer => Self::process_thaw_player(program_id, accounts),
+            Command::SetRegistrationGate {
+                registration_gate,
+                allowlist_root,
+            } => Self::process_set_registration_gate(
+                program_id,
+                accounts,
+                registration_gate,
+                allowlist_root,
+            ),
+            Command::SetRegistrationFee {
+                registration_fee,
+                upline_percent,
+            } => Self::process_set_registration_fee(
+                program_id,
+                accounts,
+                registration_fee,
+                upline_percent,
+            ),
+            Command::CreateSeason { start, end, prizes } => {
+                Self::process_create_season(program_id, accounts, start, end, prizes)
+            }
+            Command::SubmitScore { points } => {
+                Self::process_submit_score(program_id, accounts, points)
+            }
+            Command::FinalizeSeason => Self::process_finalize_season(program_id, accounts),
+            Command::InitStakeVault => Self::process_init_stake_vault(program_id, accounts),
+            Command::SetStakeRewardRate { stake_reward_rate } => {
+                Self::process_set_stake_reward_rate(program_id, accounts, stake_reward_rate)
+            }
+            Command::Stake { amount } => Self::process_stake(program_id, accounts, amount),
+            Command::Unstake { amount } => Self::process_unstake(program_id, accounts, amount),
+            Command::CreateQuest {
+                reward,
+                max_completions,
+                expiry,
+            } => Self::process_create_quest(program_id, accounts, reward, max_completions, expiry),
+            Command::CompleteQuest { quest_id } => {
+                Self::process_complete_quest(program_id, accounts, quest_id)
+            }
+            Command::CommitFlip { wager, commitment } => {
+                Self::process_commit_flip(program_id, accounts, wager, commitment)
+            }
+            Command::HouseCommitFlip { commitment } => {
+                Self::process_house_commit_flip(program_id, accounts, commitment)
+            }
+            Command::RevealFlip { secret } => {
+                Self::process_reveal_flip(program_id, accounts, secret)
+            }
+            Command::ResolveFlipTimeout => Self::process_resolve_flip_timeout(program_id, accounts),
+            Command::SetLootTable { price, tiers } => {
+                Self::process_set_loot_table(program_id, accounts, price, tiers)
+            }
+            Command::OpenBox => Self::process_open_box(program_id, accounts),
+            Command::RevealBox => Self::process_reveal_box(program_id, accounts),
+            Command::SetCheckInSchedule { schedule } => {
+                Self::process_set_check_in_schedule(program_id, accounts, schedule)
+            }
+            Command::CheckIn => Self::process_check_in(program_id, accounts),
+            Command::SetTransferFee { fee_percent } => {
+                Self::process_set_transfer_fee(program_id, accounts, fee_percent)
+            }
+            Command::TransferReward { amount } => {
+                Self::process_transfer_reward(program_id, accounts, amount)
+            }
+            Command::SetClaimDelegate { delegate } => {
+                Self::process_set_claim_delegate(program_id, accounts, delegate)
             }
-            Command::Claim => Self::process_claim_reward(program_id, accounts),
+            Command::RelayClaim => Self::process_relay_claim(program_id, accounts),
         }
     }
 
-    // 0 - [signer]   - The player (holder) account
-    // 1 - []         - Program account
-    // 2 - [writable] - The player program account
-    // 3 - []         - The token account of the current program
-    // 4-  []         - The PDA, owner (in term of token, not account owner) of token account
-    // 5 - []         - The player token account
-    // 6 - []         - The token program
-    pub fn process_claim_reward(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        msg!("process_claim_reward");
+    // 0 - [signer, writable] - The admin (holder) account, pay for the lamport vault creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The lamport vault, PDA of ["lamport_vault", program account]
+    // 3 - []         - The system program
+    pub fn process_init_lamports(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_init_lamports");
         let account_iter = &mut accounts.iter();
-        let player_holder_account = next_account_info(account_iter)?;
-
-        // Make sure signature for player who claim the reward is provided
-        if !player_holder_account.is_signer {
-            msg!("Player program account must be signed");
+        let admin_account = next_account_info(account_iter)?;
+        if !admin_account.is_signer {
+            msg!("Admin account must be signed");
             return Err(ProgramError::MissingRequiredSignature);
         }
 
         let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
 
-        // Make sure program account owner is the current program
+        let mut program_account_data =
+            GameInfo::unpack_unchecked(&program_account.try_borrow_data()?)?;
+        if program_account_data.is_initialized {
+            msg!("Program account already initialized");
+            return Err(GameError::AlreadyInitialize.into());
+        }
+
+        let lamport_vault_account = next_account_info(account_iter)?;
+        let system_program_account = next_account_info(account_iter)?;
+
+        let (lamport_vault, nonce) =
+            Self::find_lamport_vault_address(program_id, program_account.key);
+        if *lamport_vault_account.key != lamport_vault {
+            msg!("Lamport vault is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        // The vault hold no data, it is owned by the current program so lamports can be debited directly on claim
+        msg!("Create lamport vault");
+        Self::create_pda_account(
+            admin_account,
+            lamport_vault_account,
+            system_program_account,
+            0,
+            program_id,
+            &[
+                LAMPORT_VAULT_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &[nonce],
+            ],
+        )?;
+
+        program_account_data.admin = *admin_account.key;
+        program_account_data.is_initialized = true;
+        // Same decimals as SOL, token related fields are left empty
+        program_account_data.decimals = 9;
+        program_account_data.payout_mode = PayoutMode::Lamports;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::GameInitialized {
+            program_account: *program_account.key,
+            admin: *admin_account.key,
+            payout_mode: PayoutMode::Lamports,
+            mint: None,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The depositor (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The lamport vault
+    // 3 - []         - The system program
+    pub fn process_deposit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        msg!("process_deposit");
+        let account_iter = &mut accounts.iter();
+        let depositor_account = next_account_info(account_iter)?;
+        if !depositor_account.is_signer {
+            msg!("Depositor account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
         if program_account.owner != program_id {
             msg!("Program account owner is not current program");
             return Err(ProgramError::IncorrectProgramId);
         }
 
         let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
-        let player_program_account = next_account_info(account_iter)?;
+        if program_account_data.payout_mode != PayoutMode::Lamports {
+            msg!("Program account do not pay reward in lamports");
+            return Err(GameError::InvalidPayoutMode.into());
+        }
 
-        // Make sure player program account owner is the current program
-        if player_program_account.owner != program_id {
-            msg!("Player program account owner is not current program");
-            return Err(ProgramError::IncorrectProgramId);
+        let lamport_vault_account = next_account_info(account_iter)?;
+        let system_program_account = next_account_info(account_iter)?;
+
+        let (lamport_vault, _nonce) =
+            Self::find_lamport_vault_address(program_id, program_account.key);
+        if *lamport_vault_account.key != lamport_vault {
+            msg!("Lamport vault is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
         }
 
-        let mut player_program_account_data =
-            Player::unpack(&player_program_account.try_borrow_data()?)?;
+        // System program only allow transfer from account it owns, so the depositor transfer through it
+        invoke(
+            &system_instruction::transfer(depositor_account.key, lamport_vault_account.key, amount),
+            &[
+                depositor_account.clone(),
+                lamport_vault_account.clone(),
+                system_program_account.clone(),
+            ],
+        )?;
 
-        // Make sure player program account owned by signer
-        if player_program_account_data.owner != *player_holder_account.key {
-            msg!("Player program account do not belongs to signer");
-            return Err(ProgramError::MissingRequiredSignature);
+        GameEvent::Deposited {
+            program_account: *program_account.key,
+            depositor: *depositor_account.key,
+            amount,
         }
+        .emit();
 
-        // Make sure there's reward to claim
-        if player_program_account_data.reward_to_claim == 0 {
+        Ok(())
+    }
+
+    // Pay the player reward from the lamport vault, the remaining accounts start from the lamport vault
+    // The claimer is the player (holder) account or its claim delegate
+    fn claim_lamports<'a, 'b>(
+        program_id: &Pubkey,
+        program_account: &AccountInfo<'b>,
+        program_account_data: GameInfo,
+        claimer_account: &'a AccountInfo<'b>,
+        player_program_account: &AccountInfo<'b>,
+        player_program_account_data: Player,
+        account_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> ProgramResult {
+        let lamport_vault_account = next_account_info(account_iter)?;
+        let (lamport_vault, _nonce) =
+            Self::find_lamport_vault_address(program_id, program_account.key);
+        if *lamport_vault_account.key != lamport_vault {
+            msg!("Lamport vault is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if lamport_vault_account.owner != program_id {
+            msg!("Lamport vault owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let reward_to_claim = player_program_account_data.reward_to_claim;
+        if reward_to_claim == 0 {
             msg!("No reward to claim");
             return Err(GameError::UnclaimableAmount.into());
         }
 
-        let program_token_account = next_account_info(account_iter)?;
+        // The vault must stay rent exempt after the claim
+        let available_lamports = lamport_vault_account
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(lamport_vault_account.data_len()));
+        if available_lamports < reward_to_claim {
+            msg!("Lamport vault balance {} is not enough", available_lamports);
+            return Err(GameError::InsufficientVaultBalance.into());
+        }
+
+        // Lamports always go to the player, the delegate only sign the claim
+        let player_holder_account = if player_program_account_data.owner == *claimer_account.key {
+            claimer_account
+        } else {
+            let player_holder_account = next_account_info(account_iter)?;
+            if *player_holder_account.key != player_program_account_data.owner {
+                msg!("Lamports claimed by the delegate must go to the player holder account");
+                return Err(GameError::InvalidClaimDestination.into());
+            }
+            player_holder_account
+        };
+
+        msg!("Claim reward by transfer from lamport vault to the player");
+        // The vault is owned by the current program, so lamports are moved directly instead of system program transfer
+        **lamport_vault_account.try_borrow_mut_lamports()? -= reward_to_claim;
+        let player_lamports = u64::checked_add(player_holder_account.lamports(), reward_to_claim)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        **player_holder_account.try_borrow_mut_lamports()? = player_lamports;
+
+        Self::record_claim(
+            program_account,
+            program_account_data,
+            player_program_account,
+            player_program_account_data,
+            reward_to_claim,
+        )?;
+
+        GameEvent::RewardClaimed {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            owner: *player_holder_account.key,
+            mint: None,
+            amount: reward_to_claim,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn find_lamport_vault_address(program_id: &Pubkey, program_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[LAMPORT_VAULT_SEED.as_bytes(), program_account.as_ref()],
+            program_id,
+        )
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the reward vault creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The reward vault, PDA of ["vault", program account, mint]
+    // 3 - []         - The PDA, owner (in term of token, not account owner) of the reward vault
+    // 4 - []         - The mint of the reward vault
+    // 5 - []         - The token program, SPL TOKEN or Token-2022
+    // 6 - []         - The system program
+    // 7 - []         - The rent sysvar
+    pub fn process_register_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_register_vault");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let vault_account = next_account_info(account_iter)?;
         let pda_account = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        let system_program_account = next_account_info(account_iter)?;
+        let rent_sysvar_account = next_account_info(account_iter)?;
+
+        if !token::is_token_program(token_program.key) {
+            msg!("Token program is not SPL TOKEN or Token-2022 program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if mint_account.owner != token_program.key {
+            msg!("Mint account owner is not the token program");
+            return Err(ProgramError::IllegalOwner);
+        }
+        // The vault is created with the base token account size, mint extensions would require a bigger account
+        if mint_account.data_len() != spl_token::state::Mint::LEN {
+            msg!("Mint with extensions is not supported by reward vault");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        token::unpack_mint(&mint_account.try_borrow_data()?)?;
+
+        // The mint recorded during initialization already has the program token account as vault
+        if *mint_account.key == program_account_data.mint {
+            msg!("Mint already used by the program token account");
+            return Err(GameError::VaultAlreadyExists.into());
+        }
+
+        let (vault, vault_nonce) =
+            Self::find_vault_address(program_id, program_account.key, mint_account.key);
+        if *vault_account.key != vault {
+            msg!("Reward vault is not the PDA of the mint");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if !vault_account.data_is_empty() {
+            msg!("Reward vault already registered");
+            return Err(GameError::VaultAlreadyExists.into());
+        }
+
+        let (pda, _nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
+        if *pda_account.key != pda {
+            msg!("PDA account is not the token owner PDA");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        msg!("Create reward vault");
+        Self::create_pda_account(
+            admin_holder_account,
+            vault_account,
+            system_program_account,
+            spl_token::state::Account::LEN,
+            token_program.key,
+            &[
+                VAULT_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                mint_account.key.as_ref(),
+                &[vault_nonce],
+            ],
+        )?;
+
+        // Same as the program token account, the PDA own the reward vault
+        invoke(
+            &token::initialize_account(
+                token_program.key,
+                vault_account.key,
+                mint_account.key,
+                &pda,
+            )?,
+            &[
+                vault_account.clone(),
+                mint_account.clone(),
+                pda_account.clone(),
+                rent_sysvar_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        GameEvent::VaultRegistered {
+            program_account: *program_account.key,
+            mint: *mint_account.key,
+            vault,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn find_vault_address(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        mint: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                VAULT_SEED.as_bytes(),
+                program_account.as_ref(),
+                mint.as_ref(),
+            ],
+            program_id,
+        )
+    }
+
+    fn find_reward_balance_address(
+        program_id: &Pubkey,
+        player_program_account: &Pubkey,
+        mint: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                BALANCE_SEED.as_bytes(),
+                player_program_account.as_ref(),
+                mint.as_ref(),
+            ],
+            program_id,
+        )
+    }
+
+    // Create a rent exempt account at the PDA, which sign through the seeds
+    // create_account fail if the address already hold lamports, so a pre-funded PDA is topped up, allocated and assigned instead
+    fn create_pda_account<'a>(
+        payer_account: &AccountInfo<'a>,
+        new_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        space: usize,
+        owner: &Pubkey,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let lamports = Rent::get()?.minimum_balance(space);
+        if new_account.lamports() == 0 {
+            return invoke_signed(
+                &system_instruction::create_account(
+                    payer_account.key,
+                    new_account.key,
+                    lamports,
+                    space as u64,
+                    owner,
+                ),
+                &[
+                    payer_account.clone(),
+                    new_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[seeds],
+            );
+        }
+
+        let required_lamports = lamports.saturating_sub(new_account.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    payer_account.key,
+                    new_account.key,
+                    required_lamports,
+                ),
+                &[
+                    payer_account.clone(),
+                    new_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(new_account.key, space as u64),
+            &[new_account.clone(), system_program_account.clone()],
+            &[seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(new_account.key, owner),
+            &[new_account.clone(), system_program_account.clone()],
+            &[seeds],
+        )
+    }
+
+    // Add reward to the player reward balance of the mint, the balance is created on the first reward
+    // is_referral_reward is true when the player receive the reward as upline
+    #[allow(clippy::too_many_arguments)]
+    fn add_reward_balance<'a>(
+        program_id: &Pubkey,
+        payer_account: &AccountInfo<'a>,
+        reward_balance_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        player_program_account: &Pubkey,
+        mint: &Pubkey,
+        reward_amount: u64,
+        is_referral_reward: bool,
+    ) -> ProgramResult {
+        let (reward_balance, nonce) =
+            Self::find_reward_balance_address(program_id, player_program_account, mint);
+        if *reward_balance_account.key != reward_balance {
+            msg!("Reward balance account is not the PDA of the player and mint");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        if reward_balance_account.data_is_empty() {
+            Self::create_pda_account(
+                payer_account,
+                reward_balance_account,
+                system_program_account,
+                RewardBalance::LEN,
+                program_id,
+                &[
+                    BALANCE_SEED.as_bytes(),
+                    player_program_account.as_ref(),
+                    mint.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        }
+
+        if reward_balance_account.owner != program_id {
+            msg!("Reward balance account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut reward_balance_data =
+            RewardBalance::unpack_unchecked(&reward_balance_account.try_borrow_data()?)?;
+        if !reward_balance_data.is_initialized {
+            reward_balance_data.is_initialized = true;
+            reward_balance_data.player = *player_program_account;
+            reward_balance_data.mint = *mint;
+        }
+        reward_balance_data.reward_to_claim =
+            u64::checked_add(reward_balance_data.reward_to_claim, reward_amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        reward_balance_data.total_earned =
+            u64::checked_add(reward_balance_data.total_earned, reward_amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        if is_referral_reward {
+            reward_balance_data.total_referral_earned =
+                u64::checked_add(reward_balance_data.total_referral_earned, reward_amount)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+        }
+
+        RewardBalance::pack(
+            reward_balance_data,
+            &mut reward_balance_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - []         - The operator (holder) account
+    pub fn process_add_operator(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reward_cap: COption<u64>,
+    ) -> ProgramResult {
+        msg!("process_add_operator");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let operator_account = next_account_info(account_iter)?;
+
+        if program_account_data
+            .find_operator(operator_account.key)
+            .is_some()
+        {
+            msg!("Operator already added");
+            return Err(GameError::OperatorAlreadyExists.into());
+        }
+
+        let slot = program_account_data
+            .find_empty_operator_slot()
+            .ok_or(GameError::OperatorListFull)?;
+
+        program_account_data.operators[slot] = Operator {
+            key: *operator_account.key,
+            reward_cap,
+            total_rewarded: 0,
+        };
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::OperatorAdded {
+            program_account: *program_account.key,
+            operator: *operator_account.key,
+            reward_cap: reward_cap.into(),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - []         - The operator (holder) account
+    pub fn process_remove_operator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_remove_operator");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let operator_account = next_account_info(account_iter)?;
+
+        let slot = program_account_data
+            .find_operator(operator_account.key)
+            .ok_or(GameError::OperatorNotFound)?;
+
+        // Reset the slot, so it can be reused
+        program_account_data.operators[slot] = Operator::default();
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::OperatorRemoved {
+            program_account: *program_account.key,
+            operator: *operator_account.key,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_max_direct_referrals(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_direct_referrals: COption<u64>,
+    ) -> ProgramResult {
+        msg!("process_set_max_direct_referrals");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        // Upline already above a lowered limit keep their referrals, but cannot get more
+        program_account_data.max_direct_referrals = max_direct_referrals;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::MaxDirectReferralsSet {
+            program_account: *program_account.key,
+            max_direct_referrals: max_direct_referrals.into(),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin or the player (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The current upline player program account, only if the player has upline
+    // 4 - [writable] - The new upline player program account, only if upline is provided
+    // 5.. - []       - The upline chain of the new upline, from its upline up to the player without upline
+    pub fn process_set_upline(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        upline: COption<Pubkey>,
+    ) -> ProgramResult {
+        msg!("process_set_upline");
+        let account_iter = &mut accounts.iter();
+        let authority_account = next_account_info(account_iter)?;
+        if !authority_account.is_signer {
+            msg!("Admin or player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        if *authority_account.key == program_account_data.admin {
+            msg!("Upline changed by admin");
+        } else if *authority_account.key == player_data.owner {
+            let now = Clock::get()?.unix_timestamp;
+            let grace_period_end =
+                i64::checked_add(player_data.registered_at, UPLINE_CHANGE_GRACE_PERIOD)
+                    .ok_or(GameError::UplineChangeNotAllowed)?;
+            if player_data.upline_changed || now > grace_period_end {
+                msg!("Player can only change upline once within the grace period");
+                return Err(GameError::UplineChangeNotAllowed.into());
+            }
+            player_data.upline_changed = true;
+        } else {
+            msg!("Only admin or player can change upline");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let old_upline = player_data.upline;
+        // Nothing to change, and the referral counters must not be updated again
+        if upline == old_upline {
+            msg!("Player already has this upline");
+            return Ok(());
+        }
+
+        if let COption::Some(old_upline) = old_upline {
+            let old_upline_account = next_account_info(account_iter)?;
+            if *old_upline_account.key != old_upline {
+                msg!("Current upline account does not match the player upline");
+                return Err(GameError::InvalidUpline.into());
+            }
+            let mut old_upline_data =
+                Self::unpack_game_player(program_id, program_account.key, old_upline_account)?;
+            old_upline_data.direct_referrals =
+                u64::checked_sub(old_upline_data.direct_referrals, 1)
+                    .ok_or(GameError::StatisticOverflow)?;
+            Player::pack(
+                old_upline_data,
+                &mut old_upline_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        if let COption::Some(new_upline) = upline {
+            if new_upline == *player_program_account.key {
+                msg!("Upline cannot be same account as current player");
+                return Err(GameError::SelfRecursiveUpline.into());
+            }
+            let new_upline_account = next_account_info(account_iter)?;
+            if *new_upline_account.key != new_upline {
+                msg!("New upline account does not match the instruction upline");
+                return Err(GameError::InvalidUpline.into());
+            }
+            let mut new_upline_data =
+                Self::unpack_game_player(program_id, program_account.key, new_upline_account)?;
+
+            // Walk up the upline chain of the new upline, the player must not be one of them
+            let mut ancestor = new_upline_data.upline;
+            while let COption::Some(ancestor_key) = ancestor {
+                if ancestor_key == *player_program_account.key {
+                    msg!("New upline is a downline of the player");
+                    return Err(GameError::UplineCycle.into());
+                }
+                let ancestor_account = next_account_info(account_iter)?;
+                if *ancestor_account.key != ancestor_key {
+                    msg!("Upline chain account does not match the upline");
+                    return Err(GameError::InvalidUpline.into());
+                }
+                ancestor =
+                    Self::unpack_game_player(program_id, program_account.key, ancestor_account)?
+                        .upline;
+            }
+
+            if let COption::Some(max_direct_referrals) = program_account_data.max_direct_referrals {
+                if new_upline_data.direct_referrals >= max_direct_referrals {
+                    msg!(
+                        "Upline already has {} direct referrals",
+                        max_direct_referrals
+                    );
+                    return Err(GameError::DirectReferralLimitReached.into());
+                }
+            }
+            new_upline_data.direct_referrals =
+                u64::checked_add(new_upline_data.direct_referrals, 1)
+                    .ok_or(GameError::StatisticOverflow)?;
+            new_upline_data.downline_count = u64::checked_add(new_upline_data.downline_count, 1)
+                .ok_or(GameError::StatisticOverflow)?;
+            Player::pack(
+                new_upline_data,
+                &mut new_upline_account.try_borrow_mut_data()?,
+            )?;
+        }
+
+        player_data.upline = upline;
+        Player::pack(
+            player_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::UplineChanged {
+            program_account: *program_account.key,
+            authority: *authority_account.key,
+            player_program_account: *player_program_account.key,
+            old_upline: old_upline.into(),
+            new_upline: upline.into(),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // Unpack the registered player of the program account
+    fn unpack_game_player(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        player_program_account: &AccountInfo,
+    ) -> Result<Player, ProgramError> {
+        if player_program_account.owner != program_id {
+            msg!("Player program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let player_data = Player::unpack(&player_program_account.try_borrow_data()?)?;
+        if player_data.program_account != *program_account {
+            msg!("Player is not registered in the program account");
+            return Err(GameError::PlayerGameMismatch.into());
+        }
+        Ok(player_data)
+    }
+
+    // Take the admin signer and program account from the iterator, and make sure the signer is the admin of the initialized program account
+    fn next_game_info_with_admin<'a, 'b>(
+        program_id: &Pubkey,
+        account_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> Result<(&'a AccountInfo<'b>, &'a AccountInfo<'b>, GameInfo), ProgramError> {
+        let admin_holder_account = next_account_info(account_iter)?;
+        if !admin_holder_account.is_signer {
+            msg!("Admin account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let program_account_data = GameInfo::unpack_unchecked(&program_account.try_borrow_data()?)?;
+        if !program_account_data.is_initialized {
+            msg!("Program account not initialized");
+            return Err(GameError::NotInitialize.into());
+        }
+
+        if program_account_data.admin != *admin_holder_account.key {
+            msg!("Only admin can execute this instruction");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Ok((admin_holder_account, program_account, program_account_data))
+    }
+
+    // 0 - [signer, writable] - The player (holder) account or its claim delegate, pay for the associated token account creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - []         - The token account of the current program, or the reward vault of the mint
+    // 4-  []         - The PDA, owner (in term of token, not account owner) of token account
+    // 5 - [writable] - The player associated token account, created if not exists
+    // 6 - []         - The token program
+    // 7 - []         - The mint of the token account of the current program
+    // 8 - []         - The system program
+    // 9 - []         - The associated token account program
+    // 10 - []        - The rent sysvar
+    // 11 - [writable] - The player reward balance of the mint, only when mint is provided
+    pub fn process_claim_reward(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mint: COption<Pubkey>,
+    ) -> ProgramResult {
+        msg!("process_claim_reward");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+
+        // Make sure signature for player who claim the reward is provided
+        if !player_holder_account.is_signer {
+            msg!("Player program account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+
+        // Make sure program account owner is the current program
+        if program_account.owner != program_id {
+            msg!("Program account owner is not current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+        let player_program_account = next_account_info(account_iter)?;
+
+        // The player must belong to the program account, its vault is paid by the global PDA
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        // Make sure player program account owned by signer, or delegated to the signer
+        if !player_program_account_data.can_claim(player_holder_account.key) {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        if program_account_data.payout_mode == PayoutMode::Lamports && mint.is_none() {
+            return Self::claim_lamports(
+                program_id,
+                program_account,
+                program_account_data,
+                player_holder_account,
+                player_program_account,
+                player_program_account_data,
+                account_iter,
+            );
+        }
+
+        let program_token_account = next_account_info(account_iter)?;
+        let pda_account = next_account_info(account_iter)?;
+        let player_token_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+
+        let decimals = match mint {
+            COption::None => {
+                Self::check_game_token_accounts(
+                    &program_account_data,
+                    program_token_account,
+                    token_program,
+                    mint_account,
+                )?;
+                program_account_data.decimals
+            }
+            COption::Some(mint) => {
+                if *mint_account.key != mint {
+                    msg!("Mint account do not match with the claimed mint");
+                    return Err(GameError::MintMismatch.into());
+                }
+
+                let (vault, _vault_nonce) =
+                    Self::find_vault_address(program_id, program_account.key, &mint);
+                if *program_token_account.key != vault {
+                    msg!("Program token account is not the reward vault of the mint");
+                    return Err(GameError::InvalidPdaAccount.into());
+                }
+
+                if !token::is_token_program(token_program.key) {
+                    msg!("Token program is not SPL TOKEN or Token-2022 program");
+                    return Err(ProgramError::IncorrectProgramId);
+                }
+
+                if mint_account.owner != token_program.key {
+                    msg!("Mint account owner is not the token program");
+                    return Err(ProgramError::IllegalOwner);
+                }
+
+                token::unpack_mint(&mint_account.try_borrow_data()?)?.decimals
+            }
+        };
+
+        let system_program_account = next_account_info(account_iter)?;
+        let associated_token_program = next_account_info(account_iter)?;
+        let rent_sysvar_account = next_account_info(account_iter)?;
+
+        // Reward of other mint is kept in the player reward balance instead of the player program account
+        let reward_balance = match mint {
+            COption::None => None,
+            COption::Some(mint) => {
+                let reward_balance_account = next_account_info(account_iter)?;
+                let (reward_balance, _nonce) = Self::find_reward_balance_address(
+                    program_id,
+                    player_program_account.key,
+                    &mint,
+                );
+                if *reward_balance_account.key != reward_balance {
+                    msg!("Reward balance account is not the PDA of the player and mint");
+                    return Err(GameError::InvalidPdaAccount.into());
+                }
+                if reward_balance_account.owner != program_id {
+                    msg!("Reward balance account not found");
+                    return Err(GameError::UnclaimableAmount.into());
+                }
+                let reward_balance_data =
+                    RewardBalance::unpack(&reward_balance_account.try_borrow_data()?)?;
+                Some((reward_balance_account, reward_balance_data))
+            }
+        };
+
+        let reward_to_claim = match &reward_balance {
+            Some((_, reward_balance_data)) => reward_balance_data.reward_to_claim,
+            None => player_program_account_data.reward_to_claim,
+        };
+
+        // Make sure there's reward to claim
+        if reward_to_claim == 0 {
+            msg!("No reward to claim");
+            return Err(GameError::UnclaimableAmount.into());
+        }
+
+        // Reward always goes to the payout token account locked at Register, or the associated token account of other mint
+        let player_payout_token_account = match mint {
+            COption::None => player_program_account_data.payout_token_account,
+            COption::Some(_) => token::get_associated_token_address(
+                &player_program_account_data.owner,
+                mint_account.key,
+                token_program.key,
+            ),
+        };
+        if *player_token_account.key != player_payout_token_account {
+            msg!("Player token account is not the player payout token account");
+            return Err(GameError::InvalidPlayerTokenAccount.into());
+        }
+
+        // Create the associated token account when it doesn't exist yet, paid by the claimer
+        if player_token_account.data_is_empty() {
+            // The ATA program require the wallet account, which is not passed when the delegate claim
+            if player_program_account_data.owner != *player_holder_account.key {
+                msg!("Player associated token account must exist before the delegate claim");
+                return Err(GameError::InvalidPlayerTokenAccount.into());
+            }
+            if !token::spl_associated_token_account::check_id(associated_token_program.key) {
+                msg!("Associated token program is not SPL ASSOCIATED TOKEN ACCOUNT program");
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            msg!("Create player associated token account");
+            invoke(
+                &token::create_associated_token_account(
+                    player_holder_account.key,
+                    &player_program_account_data.owner,
+                    mint_account.key,
+                    token_program.key,
+                ),
+                &[
+                    player_holder_account.clone(),
+                    player_token_account.clone(),
+                    player_holder_account.clone(),
+                    mint_account.clone(),
+                    system_program_account.clone(),
+                    token_program.clone(),
+                    rent_sysvar_account.clone(),
+                    associated_token_program.clone(),
+                ],
+            )?;
+        }
+
+        Self::transfer_from_vault(
+            program_id,
+            program_token_account,
+            pda_account,
+            player_token_account,
+            token_program,
+            mint_account,
+            reward_to_claim,
+            decimals,
+        )?;
+
+        let player_owner = player_program_account_data.owner;
+        // After transfer, reset reward amount for player
+        match reward_balance {
+            Some((reward_balance_account, mut reward_balance_data)) => {
+                reward_balance_data.reward_to_claim = 0;
+                reward_balance_data.total_claimed =
+                    u64::checked_add(reward_balance_data.total_claimed, reward_to_claim)
+                        .ok_or(GameError::RewardAmountOverflow)?;
+                RewardBalance::pack(
+                    reward_balance_data,
+                    &mut reward_balance_account.try_borrow_mut_data()?,
+                )?;
+            }
+            None => Self::record_claim(
+                program_account,
+                program_account_data,
+                player_program_account,
+                player_program_account_data,
+                reward_to_claim,
+            )?,
+        }
+
+        GameEvent::RewardClaimed {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            owner: player_owner,
+            mint: mint.into(),
+            amount: reward_to_claim,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin or operator (holder) account, writable when mint is provided as it pay for the reward balance creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The player upline program account, only if the player has upline
+    // Only when mint is provided
+    // 4 - []         - The reward vault of the mint
+    // 5 - []         - The system program
+    // 6 - [writable] - The player reward balance of the mint, created if not exists
+    // 7 - [writable] - The upline reward balance of the mint, only if the player has upline which is not frozen
+    // Index 4 to 7 shift by -1 if the player has no upline
+    pub fn process_add_reward(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reward_amount: u64,
+        mint: COption<Pubkey>,
+    ) -> ProgramResult {
+        msg!("process_add_reward");
+        let account_iter = &mut accounts.iter();
+        let admin_holder_account = next_account_info(account_iter)?;
+
+        if !admin_holder_account.is_signer {
+            msg!("Admin account must be signed");
+            return Err(ProgramError::IllegalOwner);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+
+        let mut program_account_data =
+            GameInfo::unpack_unchecked(&program_account.try_borrow_data()?)?;
+        if !program_account_data.is_initialized {
+            msg!("Program account not initialized");
+            return Err(GameError::NotInitialize.into());
+        }
+
+        match mint {
+            COption::None => Self::charge_reward_authority(
+                &mut program_account_data,
+                admin_holder_account.key,
+                reward_amount,
+            )?,
+            COption::Some(_) => {
+                Self::check_vault_reward_authority(&program_account_data, admin_holder_account.key)?
+            }
+        }
+
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let upline_player_program_account = Self::next_upline(
+            program_id,
+            program_account.key,
+            account_iter,
+            &player_program_account_data,
+        )?;
+        let upline_player_program_account_key = upline_player_program_account
+            .as_ref()
+            .map(|(upline_player_program_account, _)| *upline_player_program_account.key);
+
+        let (player_reward, upline_reward) = match mint {
+            COption::None => Self::credit_reward(
+                &mut program_account_data,
+                player_program_account,
+                player_program_account_data,
+                upline_player_program_account,
+                reward_amount,
+            )?,
+            COption::Some(mint) => {
+                let (player_reward, upline_reward) =
+                    Self::split_reward(reward_amount, upline_player_program_account.is_some());
+
+                // Reward can only be denominated in mint with a registered reward vault
+                let vault_account = next_account_info(account_iter)?;
+                let (vault, _nonce) =
+                    Self::find_vault_address(program_id, program_account.key, &mint);
+                if *vault_account.key != vault {
+                    msg!("Reward vault is not the PDA of the mint");
+                    return Err(GameError::InvalidPdaAccount.into());
+                }
+                if vault_account.data_is_empty() {
+                    msg!("Reward vault of the mint not registered");
+                    return Err(GameError::VaultNotFound.into());
+                }
+
+                let system_program_account = next_account_info(account_iter)?;
+                let reward_balance_account = next_account_info(account_iter)?;
+
+                msg!("Player reward {}", player_reward);
+                Self::add_reward_balance(
+                    program_id,
+                    admin_holder_account,
+                    reward_balance_account,
+                    system_program_account,
+                    player_program_account.key,
+                    &mint,
+                    player_reward,
+                    false,
+                )?;
+
+                match upline_player_program_account {
+                    // The commission of a frozen upline is withheld
+                    Some((_, upline_player_data)) if upline_player_data.frozen => {
+                        msg!(
+                            "Upline is frozen, commission {} is forfeited",
+                            upline_reward
+                        );
+                        (player_reward, 0)
+                    }
+                    Some((upline_player_program_account, _)) => {
+                        let upline_reward_balance_account = next_account_info(account_iter)?;
+
+                        msg!("Upline reward {}", upline_reward);
+                        Self::add_reward_balance(
+                            program_id,
+                            admin_holder_account,
+                            upline_reward_balance_account,
+                            system_program_account,
+                            upline_player_program_account.key,
+                            &mint,
+                            upline_reward,
+                            true,
+                        )?;
+                        (player_reward, upline_reward)
+                    }
+                    None => (player_reward, upline_reward),
+                }
+            }
+        };
+
+        // Operator reward usage and statistics
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::RewardAdded {
+            program_account: *program_account.key,
+            authority: *admin_holder_account.key,
+            player_program_account: *player_program_account.key,
+            upline: upline_player_program_account_key,
+            mint: mint.into(),
+            player_amount: player_reward,
+            upline_amount: upline_reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The player (holder) account, pay for the voucher bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - []         - The instructions sysvar
+    // 4 - [writable] - The voucher bitmap PDA of ["vouchers", player program account, nonce / VOUCHER_BITMAP_BITS]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    pub fn process_redeem_voucher(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_redeem_voucher");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Only the player can redeem the voucher");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let instructions_sysvar_account = next_account_info(account_iter)?;
+        let (authority, message) = ed25519::load_verified_message(instructions_sysvar_account)?;
+        let voucher = Voucher::try_from_slice(&message).map_err(|_| {
+            msg!("Signed message is not a voucher");
+            GameError::InvalidVoucher
+        })?;
+        if voucher.program_account != *program_account.key
+            || voucher.player != *player_program_account.key
+        {
+            msg!("Voucher is issued for another player");
+            return Err(GameError::InvalidVoucher.into());
+        }
+        if Clock::get()?.unix_timestamp > voucher.expiry {
+            msg!("Voucher expired at {}", voucher.expiry);
+            return Err(GameError::VoucherExpired.into());
+        }
+
+        // The voucher signer take the role of the AddReward signer
+        if authority != program_account_data.admin
+            && program_account_data.find_operator(&authority).is_none()
+        {
+            msg!("Voucher must be signed by admin or operator");
+            return Err(GameError::InvalidVoucher.into());
+        }
+        Self::charge_reward_authority(&mut program_account_data, &authority, voucher.amount)?;
+
+        // Each nonce is redeemed once, vouchers can be redeemed in any order
+        let bitmap_account = next_account_info(account_iter)?;
+        let bitmap_index = voucher.nonce / VOUCHER_BITMAP_BITS;
+        let (bitmap, nonce) =
+            Self::find_voucher_bitmap_address(program_id, player_program_account.key, bitmap_index);
+        if *bitmap_account.key != bitmap {
+            msg!("Voucher bitmap account is not the PDA of the nonce");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The bitmap is created by the first voucher redeemed in its nonce range
+        if bitmap_account.data_is_empty() {
+            Self::create_pda_account(
+                player_holder_account,
+                bitmap_account,
+                system_program_account,
+                VOUCHER_BITMAP_LEN,
+                program_id,
+                &[
+                    VOUCHER_BITMAP_SEED.as_bytes(),
+                    player_program_account.key.as_ref(),
+                    &bitmap_index.to_le_bytes(),
+                    &[nonce],
+                ],
+            )?;
+        }
+        {
+            let mut bitmap_data = bitmap_account.try_borrow_mut_data()?;
+            let bit = voucher.nonce % VOUCHER_BITMAP_BITS;
+            let (byte, mask) = ((bit / 8) as usize, 1u8 << (bit % 8));
+            if bitmap_data[byte] & mask != 0 {
+                msg!("Voucher nonce {} already redeemed", voucher.nonce);
+                return Err(GameError::VoucherAlreadyRedeemed.into());
+            }
+            bitmap_data[byte] |= mask;
+        }
+
+        let upline_player_program_account = Self::next_upline(
+            program_id,
+            program_account.key,
+            account_iter,
+            &player_program_account_data,
+        )?;
+        let upline = upline_player_program_account
+            .as_ref()
+            .map(|(upline_player_program_account, _)| *upline_player_program_account.key);
+
+        let (player_reward, upline_reward) = Self::credit_reward(
+            &mut program_account_data,
+            player_program_account,
+            player_program_account_data,
+            upline_player_program_account,
+            voucher.amount,
+        )?;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::VoucherRedeemed {
+            program_account: *program_account.key,
+            authority,
+            player_program_account: *player_program_account.key,
+            upline,
+            nonce: voucher.nonce,
+            player_amount: player_reward,
+            upline_amount: upline_reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the distribution creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The distribution PDA of ["distribution", program account, distribution count]
+    // 3 - []         - The system program
+    pub fn process_publish_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        root: [u8; 32],
+        total: u64,
+    ) -> ProgramResult {
+        msg!("process_publish_distribution");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let index = program_account_data.distribution_count;
+        let distribution_account = next_account_info(account_iter)?;
+        let (distribution, nonce) =
+            Self::find_distribution_address(program_id, program_account.key, index);
+        if *distribution_account.key != distribution {
+            msg!("Distribution account is not the PDA of the next distribution");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        Self::create_pda_account(
+            admin_holder_account,
+            distribution_account,
+            system_program_account,
+            Distribution::LEN,
+            program_id,
+            &[
+                DISTRIBUTION_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &index.to_le_bytes(),
+                &[nonce],
+            ],
+        )?;
+
+        Distribution::pack(
+            Distribution {
+                is_initialized: true,
+                program_account: *program_account.key,
+                root,
+                total,
+                claimed: 0,
+            },
+            &mut distribution_account.try_borrow_mut_data()?,
+        )?;
+
+        program_account_data.distribution_count =
+            u64::checked_add(index, 1).ok_or(GameError::StatisticOverflow)?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::DistributionPublished {
+            program_account: *program_account.key,
+            distribution,
+            index,
+            root,
+            total,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The player (holder) account, pay for the claimed bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The distribution PDA
+    // 4 - [writable] - The claimed bitmap PDA of ["claimed", distribution, index / DISTRIBUTION_BITMAP_BITS]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    pub fn process_claim_from_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        msg!("process_claim_from_distribution");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Only the player can claim from the distribution");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let distribution_account = next_account_info(account_iter)?;
+        if distribution_account.owner != program_id {
+            msg!("Distribution not published");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut distribution_data = Distribution::unpack(&distribution_account.try_borrow_data()?)?;
+        if distribution_data.program_account != *program_account.key {
+            msg!("Distribution is published by another program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let leaf = merkle::hash_leaf(&[
+            &index.to_le_bytes(),
+            player_program_account.key.as_ref(),
+            &amount.to_le_bytes(),
+        ]);
+        if !merkle::verify(&proof, &distribution_data.root, leaf) {
+            msg!("Merkle proof does not match the distribution root");
+            return Err(GameError::InvalidMerkleProof.into());
+        }
+
+        let bitmap_account = next_account_info(account_iter)?;
+        let bitmap_index = index / DISTRIBUTION_BITMAP_BITS;
+        let (bitmap, nonce) = Self::find_distribution_bitmap_address(
+            program_id,
+            distribution_account.key,
+            bitmap_index,
+        );
+        if *bitmap_account.key != bitmap {
+            msg!("Claimed bitmap account is not the PDA of the index");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The bitmap is created by the first claimant of its index range
+        if bitmap_account.data_is_empty() {
+            Self::create_pda_account(
+                player_holder_account,
+                bitmap_account,
+                system_program_account,
+                DISTRIBUTION_BITMAP_LEN,
+                program_id,
+                &[
+                    DISTRIBUTION_BITMAP_SEED.as_bytes(),
+                    distribution_account.key.as_ref(),
+                    &bitmap_index.to_le_bytes(),
+                    &[nonce],
+                ],
+            )?;
+        }
+        {
+            let mut bitmap_data = bitmap_account.try_borrow_mut_data()?;
+            let bit = index % DISTRIBUTION_BITMAP_BITS;
+            let (byte, mask) = ((bit / 8) as usize, 1u8 << (bit % 8));
+            if bitmap_data[byte] & mask != 0 {
+                msg!("Distribution index {} already claimed", index);
+                return Err(GameError::DistributionAlreadyClaimed.into());
+            }
+            bitmap_data[byte] |= mask;
+        }
+
+        distribution_data.claimed = u64::checked_add(distribution_data.claimed, amount)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        if distribution_data.claimed > distribution_data.total {
+            msg!("Distribution total {} exceeded", distribution_data.total);
+            return Err(GameError::DistributionExhausted.into());
+        }
+
+        let upline_player_program_account = Self::next_upline(
+            program_id,
+            program_account.key,
+            account_iter,
+            &player_program_account_data,
+        )?;
+        let upline = upline_player_program_account
+            .as_ref()
+            .map(|(upline_player_program_account, _)| *upline_player_program_account.key);
+
+        let (player_reward, upline_reward) = Self::credit_reward(
+            &mut program_account_data,
+            player_program_account,
+            player_program_account_data,
+            upline_player_program_account,
+            amount,
+        )?;
+
+        Distribution::pack(
+            distribution_data,
+            &mut distribution_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::DistributionClaimed {
+            program_account: *program_account.key,
+            distribution: *distribution_account.key,
+            player_program_account: *player_program_account.key,
+            upline,
+            index,
+            player_amount: player_reward,
+            upline_amount: upline_reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn find_distribution_address(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        index: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                DISTRIBUTION_SEED.as_bytes(),
+                program_account.as_ref(),
+                &index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    fn find_voucher_bitmap_address(
+        program_id: &Pubkey,
+        player_program_account: &Pubkey,
+        bitmap_index: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                VOUCHER_BITMAP_SEED.as_bytes(),
+                player_program_account.as_ref(),
+                &bitmap_index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    fn find_distribution_bitmap_address(
+        program_id: &Pubkey,
+        distribution: &Pubkey,
+        bitmap_index: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                DISTRIBUTION_BITMAP_SEED.as_bytes(),
+                distribution.as_ref(),
+                &bitmap_index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3.. - [writable] - The reward balance PDAs of the player to forfeit, only with forfeit
+    pub fn process_freeze_player(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        forfeit: bool,
+    ) -> ProgramResult {
+        msg!("process_freeze_player");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        player_program_account_data.frozen = true;
+
+        let forfeited_amount = if forfeit {
+            player_program_account_data.reward_to_claim
+        } else {
+            0
+        };
+        player_program_account_data.reward_to_claim -= forfeited_amount;
+        program_account_data.total_forfeited =
+            u64::checked_add(program_account_data.total_forfeited, forfeited_amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+
+        let mut forfeited_balances = Vec::new();
+        if forfeit {
+            for reward_balance_account in account_iter {
+                if reward_balance_account.owner != program_id {
+                    msg!("Reward balance account not found");
+                    return Err(ProgramError::IncorrectProgramId);
+                }
+                let mut reward_balance_data =
+                    RewardBalance::unpack(&reward_balance_account.try_borrow_data()?)?;
+                let (reward_balance, _nonce) = Self::find_reward_balance_address(
+                    program_id,
+                    player_program_account.key,
+                    &reward_balance_data.mint,
+                );
+                if reward_balance_data.player != *player_program_account.key
+                    || *reward_balance_account.key != reward_balance
+                {
+                    msg!("Reward balance account is not the PDA of the player and mint");
+                    return Err(GameError::InvalidPdaAccount.into());
+                }
+                forfeited_balances.push((
+                    reward_balance_data.mint,
+                    reward_balance_data.reward_to_claim,
+                ));
+                reward_balance_data.reward_to_claim = 0;
+                RewardBalance::pack(
+                    reward_balance_data,
+                    &mut reward_balance_account.try_borrow_mut_data()?,
+                )?;
+            }
+        }
+
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::PlayerFrozen {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            forfeited_amount,
+            forfeited_balances,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_registration_gate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        registration_gate: RegistrationGate,
+        allowlist_root: [u8; 32],
+    ) -> ProgramResult {
+        msg!("process_set_registration_gate");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        program_account_data.registration_gate = registration_gate;
+        program_account_data.allowlist_root = allowlist_root;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::RegistrationGateSet {
+            program_account: *program_account.key,
+            registration_gate,
+            allowlist_root,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_registration_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        registration_fee: u64,
+        upline_percent: u8,
+    ) -> ProgramResult {
+        msg!("process_set_registration_fee");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        if upline_percent > 100 {
+            msg!("Upline percentage must be at most 100");
+            return Err(GameError::InvalidPercentage.into());
+        }
+        program_account_data.registration_fee = registration_fee;
+        program_account_data.registration_fee_upline_percent = upline_percent;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::RegistrationFeeSet {
+            program_account: *program_account.key,
+            registration_fee,
+            upline_percent,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    pub fn process_thaw_player(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_thaw_player");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, _program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        player_program_account_data.frozen = false;
+
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::PlayerThawed {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the season creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The season PDA of ["season", program account, season count]
+    // 3 - []         - The system program
+    pub fn process_create_season(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        start: i64,
+        end: i64,
+        prizes: Vec<u64>,
+    ) -> ProgramResult {
+        msg!("process_create_season");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        if end <= start {
+            msg!("Season end must be after start");
+            return Err(GameError::InvalidSeason.into());
+        }
+        if prizes.len() > SEASON_LEADERBOARD_LEN {
+            msg!("Season must have at most {} prizes", SEASON_LEADERBOARD_LEN);
+            return Err(GameError::InvalidSeason.into());
+        }
+
+        let index = program_account_data.season_count;
+        let season_account = next_account_info(account_iter)?;
+        let (season, nonce) = Self::find_season_address(program_id, program_account.key, index);
+        if *season_account.key != season {
+            msg!("Season account is not the PDA of the next season");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        Self::create_pda_account(
+            admin_holder_account,
+            season_account,
+            system_program_account,
+            Season::LEN,
+            program_id,
+            &[
+                SEASON_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &index.to_le_bytes(),
+                &[nonce],
+            ],
+        )?;
+
+        let mut season_prizes = [0; SEASON_LEADERBOARD_LEN];
+        season_prizes[..prizes.len()].copy_from_slice(&prizes);
+        Season::pack(
+            Season {
+                is_initialized: true,
+                program_account: *program_account.key,
+                start,
+                end,
+                finalized: false,
+                prizes: season_prizes,
+                leaderboard: [LeaderboardEntry::default(); SEASON_LEADERBOARD_LEN],
+            },
+            &mut season_account.try_borrow_mut_data()?,
+        )?;
+
+        program_account_data.season_count =
+            u64::checked_add(index, 1).ok_or(GameError::StatisticOverflow)?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::SeasonCreated {
+            program_account: *program_account.key,
+            season,
+            index,
+            start,
+            end,
+            prizes,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The admin or operator (holder) account, pay for the season score creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The season PDA
+    // 3 - []         - The player program account
+    // 4 - [writable] - The season score PDA of ["score", season, player program account]
+    // 5 - []         - The system program
+    pub fn process_submit_score(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        points: u64,
+    ) -> ProgramResult {
+        msg!("process_submit_score");
+        let account_iter = &mut accounts.iter();
+        let authority_account = next_account_info(account_iter)?;
+        if !authority_account.is_signer {
+            msg!("Admin or operator account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+        if program_account_data.admin != *authority_account.key
+            && program_account_data
+                .find_operator(authority_account.key)
+                .is_none()
+        {
+            msg!("Submit score only can be executed by admin or operator");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let season_account = next_account_info(account_iter)?;
+        let mut season_data =
+            Self::unpack_game_season(program_id, program_account.key, season_account)?;
+        let now = Clock::get()?.unix_timestamp;
+        if now < season_data.start || now >= season_data.end {
+            msg!(
+                "Season accept score from {} to {}",
+                season_data.start,
+                season_data.end
+            );
+            return Err(GameError::SeasonNotActive.into());
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let season_score_account = next_account_info(account_iter)?;
+        let (season_score, nonce) = Pubkey::find_program_address(
+            &[
+                SEASON_SCORE_SEED.as_bytes(),
+                season_account.key.as_ref(),
+                player_program_account.key.as_ref(),
+            ],
+            program_id,
+        );
+        if *season_score_account.key != season_score {
+            msg!("Season score account is not the PDA of the season and player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The season score is created on the first submission of the player
+        if season_score_account.data_is_empty() {
+            Self::create_pda_account(
+                authority_account,
+                season_score_account,
+                system_program_account,
+                SeasonScore::LEN,
+                program_id,
+                &[
+                    SEASON_SCORE_SEED.as_bytes(),
+                    season_account.key.as_ref(),
+                    player_program_account.key.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        }
+        let mut season_score_data =
+            SeasonScore::unpack_unchecked(&season_score_account.try_borrow_data()?)?;
+        if !season_score_data.is_initialized {
+            season_score_data.is_initialized = true;
+            season_score_data.season = *season_account.key;
+            season_score_data.player = *player_program_account.key;
+        }
+        season_score_data.score = u64::checked_add(season_score_data.score, points)
+            .ok_or(GameError::StatisticOverflow)?;
+        let score = season_score_data.score;
+
+        let rank = season_data.update_leaderboard(player_program_account.key, score);
+        msg!("Player score {}, rank {:?}", score, rank);
+
+        SeasonScore::pack(
+            season_score_data,
+            &mut season_score_account.try_borrow_mut_data()?,
+        )?;
+        Season::pack(season_data, &mut season_account.try_borrow_mut_data()?)?;
+
+        GameEvent::ScoreSubmitted {
+            program_account: *program_account.key,
+            season: *season_account.key,
+            authority: *authority_account.key,
+            player_program_account: *player_program_account.key,
+            points,
+            score,
+            rank: rank.map(|rank| rank as u8),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The season PDA
+    // 3.. - [writable] - The player program account of each leaderboard rank with prize, from the first rank
+    pub fn process_finalize_season(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_finalize_season");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        let season_account = next_account_info(account_iter)?;
+        let mut season_data =
+            Self::unpack_game_season(program_id, program_account.key, season_account)?;
+        if season_data.finalized {
+            msg!("Season already finalized");
+            return Err(GameError::SeasonAlreadyFinalized.into());
+        }
+        if Clock::get()?.unix_timestamp < season_data.end {
+            msg!("Season end at {}", season_data.end);
+            return Err(GameError::SeasonNotEnded.into());
+        }
+
+        let mut winners = Vec::new();
+        for (entry, prize) in season_data
+            .leaderboard
+            .iter()
+            .zip(season_data.prizes.iter())
+            .filter(|(entry, prize)| entry.is_active() && **prize > 0)
+        {
+            let player_program_account = next_account_info(account_iter)?;
+            if *player_program_account.key != entry.player {
+                msg!("Player program account does not match the leaderboard rank");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let mut player_data =
+                Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+            // The prize of a frozen player is forfeited, the rank is not shifted
+            if player_data.frozen {
+                msg!("Player is frozen, prize {} is forfeited", prize);
+                program_account_data.total_forfeited =
+                    u64::checked_add(program_account_data.total_forfeited, *prize)
+                        .ok_or(GameError::RewardAmountOverflow)?;
+                continue;
+            }
+            player_data.reward_to_claim = u64::checked_add(player_data.reward_to_claim, *prize)
+                .ok_or(GameError::RewardAmountOverflow)?;
+            player_data.total_earned = u64::checked_add(player_data.total_earned, *prize)
+                .ok_or(GameError::RewardAmountOverflow)?;
+            program_account_data.total_awarded =
+                u64::checked_add(program_account_data.total_awarded, *prize)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+            Player::pack(
+                player_data,
+                &mut player_program_account.try_borrow_mut_data()?,
+            )?;
+            winners.push((entry.player, *prize));
+        }
+
+        season_data.finalized = true;
+        Season::pack(season_data, &mut season_account.try_borrow_mut_data()?)?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::SeasonFinalized {
+            program_account: *program_account.key,
+            season: *season_account.key,
+            winners,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // Unpack the season of the program account
+    fn unpack_game_season(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        season_account: &AccountInfo,
+    ) -> Result<Season, ProgramError> {
+        if season_account.owner != program_id {
+            msg!("Season not created");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let season_data = Season::unpack(&season_account.try_borrow_data()?)?;
+        if season_data.program_account != *program_account {
+            msg!("Season is created by another program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        Ok(season_data)
+    }
+
+    fn find_season_address(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        index: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                SEASON_SEED.as_bytes(),
+                program_account.as_ref(),
+                &index.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the stake vault creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The stake vault PDA of ["stake", program account]
+    // 3 - []         - The PDA account
+    // 4 - []         - The mint recorded during initialization
+    // 5 - []         - The token program recorded during initialization
+    // 6 - []         - The system program
+    // 7 - []         - The rent sysvar
+    pub fn process_init_stake_vault(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        msg!("process_init_stake_vault");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        if program_account_data.payout_mode != PayoutMode::Token {
+            msg!("Program account do not pay reward in token");
+            return Err(GameError::InvalidPayoutMode.into());
+        }
+
+        let stake_vault_account = next_account_info(account_iter)?;
+        let pda_account = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        let system_program_account = next_account_info(account_iter)?;
+        let rent_sysvar_account = next_account_info(account_iter)?;
+
+        if *token_program.key != program_account_data.token_program {
+            msg!("Token program do not match with current program token program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *mint_account.key != program_account_data.mint {
+            msg!("Mint account do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+        // The stake vault is created with the base token account size, mint extensions would require a bigger account
+        if mint_account.data_len() != spl_token::state::Mint::LEN {
+            msg!("Mint with extensions is not supported by stake vault");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (stake_vault, stake_vault_nonce) =
+            Self::find_stake_vault_address(program_id, program_account.key);
+        if *stake_vault_account.key != stake_vault {
+            msg!("Stake vault is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if !stake_vault_account.data_is_empty() {
+            msg!("Stake vault already created");
+            return Err(GameError::VaultAlreadyExists.into());
+        }
+
+        let (pda, _nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
+        if *pda_account.key != pda {
+            msg!("PDA account is not the token owner PDA");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        msg!("Create stake vault");
+        Self::create_pda_account(
+            admin_holder_account,
+            stake_vault_account,
+            system_program_account,
+            spl_token::state::Account::LEN,
+            token_program.key,
+            &[
+                STAKE_VAULT_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &[stake_vault_nonce],
+            ],
+        )?;
+
+        // Same as the reward vaults, the PDA own the stake vault
+        invoke(
+            &token::initialize_account(
+                token_program.key,
+                stake_vault_account.key,
+                mint_account.key,
+                &pda,
+            )?,
+            &[
+                stake_vault_account.clone(),
+                mint_account.clone(),
+                pda_account.clone(),
+                rent_sysvar_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        GameEvent::StakeVaultCreated {
+            program_account: *program_account.key,
+            stake_vault,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_stake_reward_rate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        stake_reward_rate: u64,
+    ) -> ProgramResult {
+        msg!("process_set_stake_reward_rate");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        // Yield accrued until now use the previous rate
+        program_account_data
+            .update_reward_per_share(Clock::get()?.unix_timestamp)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.stake_reward_rate = stake_reward_rate;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::StakeRewardRateSet {
+            program_account: *program_account.key,
+            stake_reward_rate,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The token account of the player holding the tokens to stake
+    // 4 - [writable] - The stake vault PDA
+    // 5 - []         - The mint recorded during initialization
+    // 6 - []         - The token program recorded during initialization
+    pub fn process_stake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        msg!("process_stake");
+        let account_iter = &mut accounts.iter();
+        let (
+            player_holder_account,
+            program_account,
+            mut program_account_data,
+            player_program_account,
+            mut player_program_account_data,
+        ) = Self::next_game_staker(program_id, account_iter)?;
+
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let player_token_account = next_account_info(account_iter)?;
+        let stake_vault_account = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        Self::check_stake_vault(
+            program_id,
+            program_account.key,
+            &program_account_data,
+            stake_vault_account,
+            mint_account,
+            token_program,
+        )?;
+
+        let reward =
+            Self::settle_stake_reward(&mut program_account_data, &mut player_program_account_data)?;
+        player_program_account_data.staked_amount =
+            u64::checked_add(player_program_account_data.staked_amount, amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_staked =
+            u64::checked_add(program_account_data.total_staked, amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        player_program_account_data
+            .reset_stake_reward_debt(program_account_data.reward_per_share)
+            .ok_or(GameError::RewardAmountOverflow)?;
+
+        msg!("Stake {}", amount);
+        invoke(
+            &token::transfer_checked(
+                token_program.key,
+                player_token_account.key,
+                mint_account.key,
+                stake_vault_account.key,
+                player_holder_account.key,
+                amount,
+                program_account_data.decimals,
+            )?,
+            &[
+                player_token_account.clone(),
+                mint_account.clone(),
+                stake_vault_account.clone(),
+                player_holder_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        let staked_amount = player_program_account_data.staked_amount;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::Staked {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            amount,
+            reward,
+            staked_amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The token account of the player receiving the tokens, owned by the player holder account
+    // 4 - [writable] - The stake vault PDA
+    // 5 - []         - The PDA account
+    // 6 - []         - The mint recorded during initialization
+    // 7 - []         - The token program recorded during initialization
+    pub fn process_unstake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        msg!("process_unstake");
+        let account_iter = &mut accounts.iter();
+        let (
+            _player_holder_account,
+            program_account,
+            mut program_account_data,
+            player_program_account,
+            mut player_program_account_data,
+        ) = Self::next_game_staker(program_id, account_iter)?;
+
+        let player_token_account = next_account_info(account_iter)?;
+        let stake_vault_account = next_account_info(account_iter)?;
+        let pda_account = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        Self::check_stake_vault(
+            program_id,
+            program_account.key,
+            &program_account_data,
+            stake_vault_account,
+            mint_account,
+            token_program,
+        )?;
+
+        if amount > player_program_account_data.staked_amount {
+            msg!(
+                "Player staked amount {} is not enough",
+                player_program_account_data.staked_amount
+            );
+            return Err(GameError::InsufficientStake.into());
+        }
+
+        let reward =
+            Self::settle_stake_reward(&mut program_account_data, &mut player_program_account_data)?;
+        player_program_account_data.staked_amount -= amount;
+        program_account_data.total_staked -= amount;
+        player_program_account_data
+            .reset_stake_reward_debt(program_account_data.reward_per_share)
+            .ok_or(GameError::RewardAmountOverflow)?;
+
+        if amount > 0 {
+            let (pda, nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
+            if *pda_account.key != pda {
+                msg!("PDA account is not the token owner PDA");
+                return Err(GameError::InvalidPdaAccount.into());
+            }
+
+            // Staked tokens only go back to the player holder account
+            if player_token_account.owner != token_program.key {
+                msg!("Player token account owner is not the token program");
+                return Err(ProgramError::IllegalOwner);
+            }
+            let player_token_account_data =
+                token::unpack_account(&player_token_account.try_borrow_data()?)?;
+            if player_token_account_data.owner != player_program_account_data.owner {
+                msg!("Player token account is not owned by the player holder account");
+                return Err(GameError::InvalidPlayerTokenAccount.into());
+            }
+
+            msg!("Unstake {}", amount);
+            invoke_signed(
+                &token::transfer_checked(
+                    token_program.key,
+                    stake_vault_account.key,
+                    mint_account.key,
+                    player_token_account.key,
+                    &pda,
+                    amount,
+                    program_account_data.decimals,
+                )?,
+                &[
+                    stake_vault_account.clone(),
+                    mint_account.clone(),
+                    player_token_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[PDA_SEED.as_bytes(), &[nonce]]],
+            )?;
+        }
+
+        let staked_amount = player_program_account_data.staked_amount;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::Unstaked {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            amount,
+            reward,
+            staked_amount,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // Take the player signer, program account and player program account from the iterator, for the stake instructions
+    #[allow(clippy::type_complexity)]
+    fn next_game_staker<'a, 'b>(
+        program_id: &Pubkey,
+        account_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> Result<
+        (
+            &'a AccountInfo<'b>,
+            &'a AccountInfo<'b>,
+            GameInfo,
+            &'a AccountInfo<'b>,
+            Player,
+        ),
+        ProgramError,
+    > {
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+        if program_account_data.payout_mode != PayoutMode::Token {
+            msg!("Program account do not pay reward in token");
+            return Err(GameError::InvalidPayoutMode.into());
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        Ok((
+            player_holder_account,
+            program_account,
+            program_account_data,
+            player_program_account,
+            player_program_account_data,
+        ))
+    }
+
+    // Make sure the stake vault is created, and the mint and token program are the ones recorded during initialization
+    fn check_stake_vault(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        program_account_data: &GameInfo,
+        stake_vault_account: &AccountInfo,
+        mint_account: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> ProgramResult {
+        if *token_program.key != program_account_data.token_program {
+            msg!("Token program do not match with current program token program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *mint_account.key != program_account_data.mint {
+            msg!("Mint account do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+
+        let (stake_vault, _nonce) = Self::find_stake_vault_address(program_id, program_account);
+        if *stake_vault_account.key != stake_vault {
+            msg!("Stake vault is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if stake_vault_account.owner != token_program.key {
+            msg!("Stake vault not created");
+            return Err(GameError::StakeVaultNotFound.into());
+        }
+        Ok(())
+    }
+
+    // Accumulate the staking yield until now, and credit the yield of the player to the player reward
+    // Staking yield has no upline commission, and a frozen player forfeit it
+    fn settle_stake_reward(
+        program_account_data: &mut GameInfo,
+        player_data: &mut Player,
+    ) -> Result<u64, ProgramError> {
+        program_account_data
+            .update_reward_per_share(Clock::get()?.unix_timestamp)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        let reward = player_data
+            .pending_stake_reward(program_account_data.reward_per_share)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        if player_data.frozen {
+            msg!("Frozen player forfeit staking yield {}", reward);
+            return Ok(0);
+        }
+
+        player_data.reward_to_claim = u64::checked_add(player_data.reward_to_claim, reward)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        player_data.total_earned = u64::checked_add(player_data.total_earned, reward)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_awarded =
+            u64::checked_add(program_account_data.total_awarded, reward)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        msg!("Staking yield {}", reward);
+        Ok(reward)
+    }
+
+    fn find_stake_vault_address(program_id: &Pubkey, program_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[STAKE_VAULT_SEED.as_bytes(), program_account.as_ref()],
+            program_id,
+        )
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the quest creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The quest PDA of ["quest", program account, quest count]
+    // 3 - []         - The system program
+    pub fn process_create_quest(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reward: u64,
+        max_completions: COption<u64>,
+        expiry: COption<i64>,
+    ) -> ProgramResult {
+        msg!("process_create_quest");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        // Quest id must fit in the player quest bitmap
+        let quest_id = program_account_data.quest_count;
+        if quest_id >= MAX_QUESTS {
+            msg!("Game already has {} quests", MAX_QUESTS);
+            return Err(GameError::TooManyQuests.into());
+        }
+
+        let quest_account = next_account_info(account_iter)?;
+        let (quest, nonce) = Self::find_quest_address(program_id, program_account.key, quest_id);
+        if *quest_account.key != quest {
+            msg!("Quest account is not the PDA of the next quest");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        Self::create_pda_account(
+            admin_holder_account,
+            quest_account,
+            system_program_account,
+            Quest::LEN,
+            program_id,
+            &[
+                QUEST_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &quest_id.to_le_bytes(),
+                &[nonce],
+            ],
+        )?;
+        Quest::pack(
+            Quest {
+                is_initialized: true,
+                program_account: *program_account.key,
+                reward,
+                max_completions,
+                expiry,
+                completions: 0,
+            },
+            &mut quest_account.try_borrow_mut_data()?,
+        )?;
+
+        program_account_data.quest_count = quest_id + 1;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::QuestCreated {
+            program_account: *program_account.key,
+            quest,
+            quest_id,
+            reward,
+            max_completions: max_completions.into(),
+            expiry: expiry.into(),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The admin or operator (holder) account, pay for the quest bitmap creation
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The quest PDA
+    // 3 - [writable] - The player program account
+    // 4 - [writable] - The quest bitmap PDA of ["quests", player program account]
+    // 5 - []         - The system program
+    // 6 - [writable] - The player upline program account, only if the player has upline
+    pub fn process_complete_quest(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quest_id: u64,
+    ) -> ProgramResult {
+        msg!("process_complete_quest");
+        let account_iter = &mut accounts.iter();
+        let authority_account = next_account_info(account_iter)?;
+        if !authority_account.is_signer {
+            msg!("Admin or operator account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let quest_account = next_account_info(account_iter)?;
+        let (quest, _nonce) = Self::find_quest_address(program_id, program_account.key, quest_id);
+        if *quest_account.key != quest {
+            msg!("Quest account is not the PDA of the quest id");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if quest_account.owner != program_id {
+            msg!("Quest not created");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut quest_data = Quest::unpack(&quest_account.try_borrow_data()?)?;
+
+        // Quest reward is charged to the operator like any other reward
+        Self::charge_reward_authority(
+            &mut program_account_data,
+            authority_account.key,
+            quest_data.reward,
+        )?;
+
+        if let COption::Some(expiry) = quest_data.expiry {
+            if Clock::get()?.unix_timestamp > expiry {
+                msg!("Quest expired at {}", expiry);
+                return Err(GameError::QuestExpired.into());
+            }
+        }
+        quest_data.completions =
+            u64::checked_add(quest_data.completions, 1).ok_or(GameError::StatisticOverflow)?;
+        if let COption::Some(max_completions) = quest_data.max_completions {
+            if quest_data.completions > max_completions {
+                msg!("Quest already completed {} times", max_completions);
+                return Err(GameError::QuestExhausted.into());
+            }
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let bitmap_account = next_account_info(account_iter)?;
+        let (bitmap, nonce) = Pubkey::find_program_address(
+            &[
+                QUEST_BITMAP_SEED.as_bytes(),
+                player_program_account.key.as_ref(),
+            ],
+            program_id,
+        );
+        if *bitmap_account.key != bitmap {
+            msg!("Quest bitmap account is not the PDA of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The bitmap is created on the first quest completed by the player
+        if bitmap_account.data_is_empty() {
+            Self::create_pda_account(
+                authority_account,
+                bitmap_account,
+                system_program_account,
+                QUEST_BITMAP_LEN,
+                program_id,
+                &[
+                    QUEST_BITMAP_SEED.as_bytes(),
+                    player_program_account.key.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        }
+        {
+            let mut bitmap_data = bitmap_account.try_borrow_mut_data()?;
+            let (byte, mask) = ((quest_id / 8) as usize, 1u8 << (quest_id % 8));
+            if bitmap_data[byte] & mask != 0 {
+                msg!("Quest {} already completed by the player", quest_id);
+                return Err(GameError::QuestAlreadyCompleted.into());
+            }
+            bitmap_data[byte] |= mask;
+        }
+
+        let upline_player_program_account = Self::next_upline(
+            program_id,
+            program_account.key,
+            account_iter,
+            &player_program_account_data,
+        )?;
+        let upline = upline_player_program_account
+            .as_ref()
+            .map(|(upline_player_program_account, _)| *upline_player_program_account.key);
+
+        let (player_reward, upline_reward) = Self::credit_reward(
+            &mut program_account_data,
+            player_program_account,
+            player_program_account_data,
+            upline_player_program_account,
+            quest_data.reward,
+        )?;
+
+        Quest::pack(quest_data, &mut quest_account.try_borrow_mut_data()?)?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::QuestCompleted {
+            program_account: *program_account.key,
+            quest,
+            quest_id,
+            authority: *authority_account.key,
+            player_program_account: *player_program_account.key,
+            upline,
+            player_amount: player_reward,
+            upline_amount: upline_reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn find_quest_address(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        quest_id: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                QUEST_SEED.as_bytes(),
+                program_account.as_ref(),
+                &quest_id.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    // 0 - [signer, writable] - The player (holder) account, pay for the flip creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The flip PDA of ["flip", player program account]
+    // 4 - []         - The system program
+    pub fn process_commit_flip(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        wager: u64,
+        commitment: [u8; 32],
+    ) -> ProgramResult {
+        msg!("process_commit_flip");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+        if wager == 0 || wager > player_program_account_data.reward_to_claim {
+            msg!(
+                "Wager must be between 1 and the player reward {}",
+                player_program_account_data.reward_to_claim
+            );
+            return Err(GameError::InvalidWager.into());
+        }
+
+        let flip_account = next_account_info(account_iter)?;
+        let (flip, nonce) = Self::find_flip_address(program_id, player_program_account.key);
+        if *flip_account.key != flip {
+            msg!("Flip account is not the PDA of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The flip is created on the first flip of the player, and reused once settled
+        if flip_account.data_is_empty() {
+            Self::create_pda_account(
+                player_holder_account,
+                flip_account,
+                system_program_account,
+                Flip::LEN,
+                program_id,
+                &[
+                    FLIP_SEED.as_bytes(),
+                    player_program_account.key.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        } else if Flip::unpack(&flip_account.try_borrow_data()?)?.status != FlipStatus::Settled {
+            msg!("Previous flip of the player is not settled");
+            return Err(GameError::FlipInProgress.into());
+        }
+
+        player_program_account_data.reward_to_claim -= wager;
+        Flip::pack(
+            Flip {
+                is_initialized: true,
+                program_account: *program_account.key,
+                player: *player_program_account.key,
+                status: FlipStatus::PlayerCommitted,
+                wager,
+                player_commitment: commitment,
+                house: Pubkey::default(),
+                house_commitment: [0; 32],
+                player_secret: [0; 32],
+                house_secret: [0; 32],
+                player_revealed: false,
+                house_revealed: false,
+                committed_at: Clock::get()?.unix_timestamp,
+            },
+            &mut flip_account.try_borrow_mut_data()?,
+        )?;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::FlipCommitted {
+            program_account: *program_account.key,
+            flip,
+            player_program_account: *player_program_account.key,
+            wager,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin or operator (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The flip PDA
+    pub fn process_house_commit_flip(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        commitment: [u8; 32],
+    ) -> ProgramResult {
+        msg!("process_house_commit_flip");
+        let account_iter = &mut accounts.iter();
+        let house_account = next_account_info(account_iter)?;
+        if !house_account.is_signer {
+            msg!("Admin or operator account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+        if program_account_data.admin != *house_account.key
+            && program_account_data
+                .find_operator(house_account.key)
+                .is_none()
+        {
+            msg!("House commit only can be executed by admin or operator");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let flip_account = next_account_info(account_iter)?;
+        let mut flip_data = Self::unpack_game_flip(program_id, program_account.key, flip_account)?;
+        if flip_data.status != FlipStatus::PlayerCommitted {
+            msg!("Flip is not waiting for the house commitment");
+            return Err(GameError::InvalidFlipState.into());
+        }
+
+        // The house can lose at most the wager, which is reserved from its reward cap until the flip is settled
+        Self::charge_reward_authority(
+            &mut program_account_data,
+            house_account.key,
+            flip_data.wager,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        flip_data.status = FlipStatus::HouseCommitted;
+        flip_data.house = *house_account.key;
+        flip_data.house_commitment = commitment;
+        flip_data.committed_at = Clock::get()?.unix_timestamp;
+        Flip::pack(flip_data, &mut flip_account.try_borrow_mut_data()?)?;
+
+        GameEvent::FlipHouseCommitted {
+            program_account: *program_account.key,
+            flip: *flip_account.key,
+            house: *house_account.key,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The player (holder) account or the house
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The flip PDA
+    pub fn process_reveal_flip(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        secret: [u8; 32],
+    ) -> ProgramResult {
+        msg!("process_reveal_flip");
+        let account_iter = &mut accounts.iter();
+        let revealer_account = next_account_info(account_iter)?;
+        if !revealer_account.is_signer {
+            msg!("Player or house account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        let flip_account = next_account_info(account_iter)?;
+        let mut flip_data = Self::unpack_game_flip(program_id, program_account.key, flip_account)?;
+        if flip_data.player != *player_program_account.key {
+            msg!("Flip is not the flip of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        // Secrets are only revealed once both commitments are made, so neither side can adapt its commitment
+        if flip_data.status != FlipStatus::HouseCommitted {
+            msg!("Flip is not waiting for reveal");
+            return Err(GameError::InvalidFlipState.into());
+        }
+
+        let commitment = hashv(&[&secret]).to_bytes();
+        if *revealer_account.key == player_program_account_data.owner && !flip_data.player_revealed
+        {
+            if commitment != flip_data.player_commitment {
+                msg!("Secret does not match the player commitment");
+                return Err(GameError::InvalidCommitment.into());
+            }
+            flip_data.player_secret = secret;
+            flip_data.player_revealed = true;
+        } else if *revealer_account.key == flip_data.house && !flip_data.house_revealed {
+            if commitment != flip_data.house_commitment {
+                msg!("Secret does not match the house commitment");
+                return Err(GameError::InvalidCommitment.into());
+            }
+            flip_data.house_secret = secret;
+            flip_data.house_revealed = true;
+        } else {
+            msg!("Signer is not a party of the flip, or already revealed");
+            return Err(GameError::InvalidFlipState.into());
+        }
+
+        GameEvent::FlipRevealed {
+            program_account: *program_account.key,
+            flip: *flip_account.key,
+            revealer: *revealer_account.key,
+        }
+        .emit();
+
+        if flip_data.player_revealed && flip_data.house_revealed {
+            let outcome = hashv(&[&flip_data.player_secret, &flip_data.house_secret]).to_bytes();
+            let payout = if outcome[0] & 1 == 0 {
+                msg!("Player win the flip");
+                u64::checked_mul(flip_data.wager, 2).ok_or(GameError::RewardAmountOverflow)?
+            } else {
+                msg!("House win the flip");
+                0
+            };
+            Self::settle_flip(
+                &mut program_account_data,
+                &mut player_program_account_data,
+                &mut flip_data,
+                flip_account.key,
+                payout,
+                false,
+            )?;
+            Player::pack(
+                player_program_account_data,
+                &mut player_program_account.try_borrow_mut_data()?,
+            )?;
+            GameInfo::pack(
+                program_account_data,
+                &mut program_account.try_borrow_mut_data()?,
+            )?;
+        }
+        Flip::pack(flip_data, &mut flip_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The flip PDA
+    pub fn process_resolve_flip_timeout(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        msg!("process_resolve_flip_timeout");
+        let account_iter = &mut accounts.iter();
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        let flip_account = next_account_info(account_iter)?;
+        let mut flip_data = Self::unpack_game_flip(program_id, program_account.key, flip_account)?;
+        if flip_data.player != *player_program_account.key {
+            msg!("Flip is not the flip of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if flip_data.status == FlipStatus::Settled {
+            msg!("Flip already settled");
+            return Err(GameError::InvalidFlipState.into());
+        }
+
+        let deadline = flip_data
+            .committed_at
+            .checked_add(FLIP_TIMEOUT)
+            .ok_or(GameError::StatisticOverflow)?;
+        if Clock::get()?.unix_timestamp < deadline {
+            msg!("Flip can be resolved by timeout from {}", deadline);
+            return Err(GameError::FlipTimeoutNotReached.into());
+        }
+
+        // The party who revealed is honest, the other one is assumed to withhold a losing secret
+        let wager = flip_data.wager;
+        let payout = match (flip_data.player_revealed, flip_data.house_revealed) {
+            (true, false) => u64::checked_mul(wager, 2).ok_or(GameError::RewardAmountOverflow)?,
+            (false, true) => 0,
+            _ => wager,
+        };
+        Self::settle_flip(
+            &mut program_account_data,
+            &mut player_program_account_data,
+            &mut flip_data,
+            flip_account.key,
+            payout,
+            true,
+        )?;
+
+        Flip::pack(flip_data, &mut flip_account.try_borrow_mut_data()?)?;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        Ok(())
+    }
+
+    fn unpack_game_flip(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        flip_account: &AccountInfo,
+    ) -> Result<Flip, ProgramError> {
+        if flip_account.owner != program_id {
+            msg!("Flip not created");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let flip_data = Flip::unpack(&flip_account.try_borrow_data()?)?;
+        if flip_data.program_account != *program_account {
+            msg!("Flip is played in another program account");
+            return Err(GameError::PlayerGameMismatch.into());
+        }
+        Ok(flip_data)
+    }
+
+    // Credit the payout to the player reward, the part above the wager is won from the vault
+    fn settle_flip(
+        program_account_data: &mut GameInfo,
+        player_data: &mut Player,
+        flip_data: &mut Flip,
+        flip: &Pubkey,
+        payout: u64,
+        timeout: bool,
+    ) -> ProgramResult {
+        let winning = payout.saturating_sub(flip_data.wager);
+        // Release the part of the wager reserved by the house commit that the player did not win
+        if flip_data.status == FlipStatus::HouseCommitted {
+            Self::release_reward_authority(
+                program_account_data,
+                &flip_data.house,
+                flip_data.wager - winning,
+            );
+        }
+        player_data.reward_to_claim = u64::checked_add(player_data.reward_to_claim, payout)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        player_data.total_earned = u64::checked_add(player_data.total_earned, winning)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_awarded =
+            u64::checked_add(program_account_data.total_awarded, winning)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        flip_data.status = FlipStatus::Settled;
+
+        GameEvent::FlipSettled {
+            program_account: flip_data.program_account,
+            flip: *flip,
+            player_program_account: flip_data.player,
+            wager: flip_data.wager,
+            payout,
+            timeout,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn find_flip_address(program_id: &Pubkey, player_program_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[FLIP_SEED.as_bytes(), player_program_account.as_ref()],
+            program_id,
+        )
+    }
+
+    // 0 - [signer, writable] - The admin (holder) account, pay for the loot table creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The loot table PDA of ["loot_table", program account]
+    // 3 - []         - The system program
+    pub fn process_set_loot_table(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        price: u64,
+        tiers: Vec<LootTier>,
+    ) -> ProgramResult {
+        msg!("process_set_loot_table");
+        let account_iter = &mut accounts.iter();
+        let (admin_holder_account, program_account, _program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        // A free box could be opened again and again for a reward
+        if price == 0 {
+            msg!("Loot box price must be greater than 0");
+            return Err(GameError::InvalidLootTable.into());
+        }
+        if tiers.is_empty() || tiers.len() > MAX_LOOT_TIERS {
+            msg!("Loot table must have 1 to {} tiers", MAX_LOOT_TIERS);
+            return Err(GameError::InvalidLootTable.into());
+        }
+        let mut loot_table_data = LootTable {
+            is_initialized: true,
+            program_account: *program_account.key,
+            price,
+            tier_count: tiers.len() as u8,
+            tiers: [LootTier::default(); MAX_LOOT_TIERS],
+        };
+        loot_table_data.tiers[..tiers.len()].copy_from_slice(&tiers);
+        if loot_table_data.total_weight() == 0 {
+            msg!("Loot table total weight must be greater than 0");
+            return Err(GameError::InvalidLootTable.into());
+        }
+
+        let loot_table_account = next_account_info(account_iter)?;
+        let (loot_table, nonce) = Self::find_loot_table_address(program_id, program_account.key);
+        if *loot_table_account.key != loot_table {
+            msg!("Loot table account is not the PDA of the program account");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        if loot_table_account.data_is_empty() {
+            Self::create_pda_account(
+                admin_holder_account,
+                loot_table_account,
+                system_program_account,
+                LootTable::LEN,
+                program_id,
+                &[
+                    LOOT_TABLE_SEED.as_bytes(),
+                    program_account.key.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        }
+        LootTable::pack(
+            loot_table_data,
+            &mut loot_table_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::LootTableSet {
+            program_account: *program_account.key,
+            loot_table,
+            price,
+            tiers,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The player (holder) account, pay for the loot box creation
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    // 3 - []         - The loot table PDA
+    // 4 - [writable] - The loot box PDA of ["box", player program account]
+    // 5 - []         - The system program
+    pub fn process_open_box(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_open_box");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let loot_table_account = next_account_info(account_iter)?;
+        let loot_table_data =
+            Self::unpack_game_loot_table(program_id, program_account.key, loot_table_account)?;
+        let price = loot_table_data.price;
+        if price > player_program_account_data.reward_to_claim {
+            msg!(
+                "Loot box price {} is greater than the player reward {}",
+                price,
+                player_program_account_data.reward_to_claim
+            );
+            return Err(GameError::InsufficientReward.into());
+        }
+
+        let loot_box_account = next_account_info(account_iter)?;
+        let (loot_box, nonce) = Self::find_loot_box_address(program_id, player_program_account.key);
+        if *loot_box_account.key != loot_box {
+            msg!("Loot box account is not the PDA of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        // The loot box is created on the first opening of the player, and reused once revealed
+        if loot_box_account.data_is_empty() {
+            Self::create_pda_account(
+                player_holder_account,
+                loot_box_account,
+                system_program_account,
+                LootBox::LEN,
+                program_id,
+                &[
+                    LOOT_BOX_SEED.as_bytes(),
+                    player_program_account.key.as_ref(),
+                    &[nonce],
+                ],
+            )?;
+        } else if LootBox::unpack(&loot_box_account.try_borrow_data()?)?.pending {
+            msg!("Previous loot box of the player is not revealed");
+            return Err(GameError::LootBoxInProgress.into());
+        }
+
+        let reveal_slot = Clock::get()?
+            .slot
+            .checked_add(LOOT_BOX_DELAY)
+            .ok_or(GameError::StatisticOverflow)?;
+        player_program_account_data.reward_to_claim -= price;
+        LootBox::pack(
+            LootBox {
+                is_initialized: true,
+                program_account: *program_account.key,
+                player: *player_program_account.key,
+                pending: true,
+                price,
+                reveal_slot,
+                tier_count: loot_table_data.tier_count,
+                tiers: loot_table_data.tiers,
+            },
+            &mut loot_box_account.try_borrow_mut_data()?,
+        )?;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::LootBoxOpened {
+            program_account: *program_account.key,
+            loot_box,
+            player_program_account: *player_program_account.key,
+            price,
+            reveal_slot,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The loot box PDA
+    // 3 - []         - The SlotHashes sysvar
+    pub fn process_reveal_box(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_reveal_box");
+        let account_iter = &mut accounts.iter();
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+
+        let loot_box_account = next_account_info(account_iter)?;
+        if loot_box_account.owner != program_id {
+            msg!("Loot box not created");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut loot_box_data = LootBox::unpack(&loot_box_account.try_borrow_data()?)?;
+        if loot_box_data.program_account != *program_account.key
+            || loot_box_data.player != *player_program_account.key
+        {
+            msg!("Loot box is not the loot box of the player");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if !loot_box_data.pending {
+            msg!("Loot box already revealed");
+            return Err(GameError::LootBoxNotOpened.into());
+        }
+
+        let slot_hashes_account = next_account_info(account_iter)?;
+        let (tier, reward) =
+            match slot_hash::find_slot_hash(slot_hashes_account, loot_box_data.reveal_slot)? {
+                SlotHash::NotReady => {
+                    msg!(
+                        "Loot box can be revealed after slot {}",
+                        loot_box_data.reveal_slot
+                    );
+                    return Err(GameError::LootBoxNotReady.into());
+                }
+                SlotHash::Expired => {
+                    msg!("Reveal slot expired, the loot box is lost");
+                    (None, 0)
+                }
+                SlotHash::Found(hash) => {
+                    // Mixed with the player so boxes sharing the reveal slot get different tiers
+                    let roll = hashv(&[&hash, player_program_account.key.as_ref()]).to_bytes();
+                    let roll = u64::from_le_bytes(
+                        roll[..8]
+                            .try_into()
+                            .map_err(|_| ProgramError::InvalidAccountData)?,
+                    );
+                    // Rolled against the tiers of the opening, not the current loot table
+                    let tier = loot_box_data
+                        .pick_tier(roll)
+                        .ok_or(GameError::InvalidLootTable)?;
+                    (Some(tier as u8), loot_box_data.tiers[tier].reward)
+                }
+            };
+
+        // Same as a flip, only the part above the price is won from the vault
+        let winning = reward.saturating_sub(loot_box_data.price);
+        player_program_account_data.reward_to_claim =
+            u64::checked_add(player_program_account_data.reward_to_claim, reward)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        player_program_account_data.total_earned =
+            u64::checked_add(player_program_account_data.total_earned, winning)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_awarded =
+            u64::checked_add(program_account_data.total_awarded, winning)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        loot_box_data.pending = false;
+
+        LootBox::pack(loot_box_data, &mut loot_box_account.try_borrow_mut_data()?)?;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::LootBoxRevealed {
+            program_account: *program_account.key,
+            loot_box: *loot_box_account.key,
+            player_program_account: *player_program_account.key,
+            tier,
+            reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    fn unpack_game_loot_table(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        loot_table_account: &AccountInfo,
+    ) -> Result<LootTable, ProgramError> {
+        if loot_table_account.owner != program_id {
+            msg!("Loot table not created");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let loot_table_data = LootTable::unpack(&loot_table_account.try_borrow_data()?)?;
+        if loot_table_data.program_account != *program_account {
+            msg!("Loot table belongs to another program account");
+            return Err(GameError::PlayerGameMismatch.into());
+        }
+        Ok(loot_table_data)
+    }
+
+    fn find_loot_table_address(program_id: &Pubkey, program_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[LOOT_TABLE_SEED.as_bytes(), program_account.as_ref()],
+            program_id,
+        )
+    }
+
+    fn find_loot_box_address(program_id: &Pubkey, player_program_account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[LOOT_BOX_SEED.as_bytes(), player_program_account.as_ref()],
+            program_id,
+        )
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_check_in_schedule(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        schedule: Vec<u64>,
+    ) -> ProgramResult {
+        msg!("process_set_check_in_schedule");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        if schedule.len() > MAX_CHECK_IN_DAYS {
+            msg!(
+                "Check-in schedule must have at most {} days",
+                MAX_CHECK_IN_DAYS
+            );
+            return Err(GameError::InvalidCheckInSchedule.into());
+        }
+        program_account_data.check_in_schedule_len = schedule.len() as u8;
+        program_account_data.check_in_schedule = [0; MAX_CHECK_IN_DAYS];
+        program_account_data.check_in_schedule[..schedule.len()].copy_from_slice(&schedule);
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::CheckInScheduleSet {
+            program_account: *program_account.key,
+            schedule,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The player program account
+    // 3 - [writable] - The player upline program account, only if the player has upline
+    pub fn process_check_in(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_check_in");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let today = Clock::get()?.unix_timestamp.div_euclid(SECONDS_PER_DAY);
+        let last_day = player_program_account_data.last_check_in_day;
+        let streak = player_program_account_data.check_in_streak;
+        let streak = if streak > 0 && last_day == today {
+            msg!(
+                "Player already checked in today, next check-in on day {}",
+                today + 1
+            );
+            return Err(GameError::AlreadyCheckedIn.into());
+        } else if streak > 0 && last_day == today - 1 {
+            streak.checked_add(1).ok_or(GameError::StatisticOverflow)?
+        } else {
+            1
+        };
+        let reward = match program_account_data.check_in_reward(streak) {
+            Some(reward) => reward,
+            None => {
+                msg!("Check-in schedule not set");
+                return Err(GameError::CheckInDisabled.into());
+            }
+        };
+        player_program_account_data.check_in_streak = streak;
+        player_program_account_data.last_check_in_day = today;
+
+        let upline_player_program_account = Self::next_upline(
+            program_id,
+            program_account.key,
+            account_iter,
+            &player_program_account_data,
+        )?;
+        let upline = upline_player_program_account
+            .as_ref()
+            .map(|(upline_player_program_account, _)| *upline_player_program_account.key);
+
+        let (player_reward, upline_reward) = Self::credit_reward(
+            &mut program_account_data,
+            player_program_account,
+            player_program_account_data,
+            upline_player_program_account,
+            reward,
+        )?;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::CheckedIn {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            upline,
+            streak,
+            player_amount: player_reward,
+            upline_amount: upline_reward,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The admin (holder) account
+    // 1 - [writable] - Program account
+    pub fn process_set_transfer_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_percent: u8,
+    ) -> ProgramResult {
+        msg!("process_set_transfer_fee");
+        let account_iter = &mut accounts.iter();
+        let (_admin_holder_account, program_account, mut program_account_data) =
+            Self::next_game_info_with_admin(program_id, account_iter)?;
+
+        if fee_percent > 100 {
+            msg!("Transfer fee percentage must be at most 100");
+            return Err(GameError::InvalidPercentage.into());
+        }
+        program_account_data.transfer_fee_percent = fee_percent;
+
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::TransferFeeSet {
+            program_account: *program_account.key,
+            fee_percent,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The sender player (holder) account
+    // 1 - [writable] - Program account
+    // 2 - [writable] - The sender player program account
+    // 3 - [writable] - The recipient player program account
+    pub fn process_transfer_reward(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        msg!("process_transfer_reward");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let recipient_program_account = next_account_info(account_iter)?;
+        if recipient_program_account.key == player_program_account.key {
+            msg!("Reward can't be transferred to the sender");
+            return Err(GameError::InvalidTransfer.into());
+        }
+        let mut recipient_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, recipient_program_account)?;
+
+        // A frozen player can neither send its reward away, nor receive reward from another player
+        if player_program_account_data.frozen || recipient_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+        if amount == 0 {
+            msg!("Transfer amount must be greater than 0");
+            return Err(GameError::InvalidTransfer.into());
+        }
+        if amount > player_program_account_data.reward_to_claim {
+            msg!(
+                "Transfer amount {} is greater than the player reward {}",
+                amount,
+                player_program_account_data.reward_to_claim
+            );
+            return Err(GameError::InsufficientReward.into());
+        }
+
+        let fee = u64::checked_mul(amount, program_account_data.transfer_fee_percent as u64)
+            .ok_or(GameError::RewardAmountOverflow)?
+            / 100;
+        player_program_account_data.reward_to_claim -= amount;
+        recipient_program_account_data.reward_to_claim =
+            u64::checked_add(recipient_program_account_data.reward_to_claim, amount - fee)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_transfer_fees =
+            u64::checked_add(program_account_data.total_transfer_fees, fee)
+                .ok_or(GameError::StatisticOverflow)?;
+
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+        Player::pack(
+            recipient_program_account_data,
+            &mut recipient_program_account.try_borrow_mut_data()?,
+        )?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::RewardTransferred {
+            program_account: *program_account.key,
+            from: *player_program_account.key,
+            to: *recipient_program_account.key,
+            amount,
+            fee,
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer]   - The player (holder) account
+    // 1 - []         - Program account
+    // 2 - [writable] - The player program account
+    pub fn process_set_claim_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        delegate: COption<Pubkey>,
+    ) -> ProgramResult {
+        msg!("process_set_claim_delegate");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        // The delegate itself can't change the delegate
+        if player_program_account_data.owner != *player_holder_account.key {
+            msg!("Player program account do not belongs to signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        player_program_account_data.claim_delegate = delegate;
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::ClaimDelegateSet {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            delegate: delegate.into(),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [writable] - Program account
+    // 1 - [writable] - The player program account
+    // 2 - [writable] - The token account of the current program
+    // 3 - []         - The PDA, owner (in term of token, not account owner) of token account
+    // 4 - [writable] - The player payout token account
+    // 5 - []         - The token program
+    // 6 - []         - The mint of the token account of the current program
+    // 7 - []         - The instructions sysvar
+    pub fn process_relay_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("process_relay_claim");
+        let account_iter = &mut accounts.iter();
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let program_account_data = GameInfo::unpack(&program_account.try_borrow_data()?)?;
+        if program_account_data.payout_mode == PayoutMode::Lamports {
+            msg!("Only reward paid in token can be relayed");
+            return Err(GameError::InvalidPayoutMode.into());
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let mut player_program_account_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_program_account_data.frozen {
+            msg!("Player is frozen");
+            return Err(GameError::PlayerFrozen.into());
+        }
+
+        let program_token_account = next_account_info(account_iter)?;
+        let pda_account = next_account_info(account_iter)?;
+        let player_token_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        Self::check_game_token_accounts(
+            &program_account_data,
+            program_token_account,
+            token_program,
+            mint_account,
+        )?;
+        // The relayer choose the accounts, so the destination must be the locked one
+        if *player_token_account.key != player_program_account_data.payout_token_account {
+            msg!("Player token account is not the player payout token account");
+            return Err(GameError::InvalidClaimDestination.into());
+        }
+
+        // The owner signature replace the player signature of Claim, the fee payer can be anyone
+        let instructions_sysvar_account = next_account_info(account_iter)?;
+        let (signer, message) = ed25519::load_verified_message(instructions_sysvar_account)?;
+        let authorization = ClaimAuthorization::try_from_slice(&message).map_err(|_| {
+            msg!("Signed message is not a claim authorization");
+            GameError::InvalidClaimAuthorization
+        })?;
+        if signer != player_program_account_data.owner
+            || authorization.program_account != *program_account.key
+            || authorization.player != *player_program_account.key
+        {
+            msg!("Claim authorization must be signed by the player owner for this player");
+            return Err(GameError::InvalidClaimAuthorization.into());
+        }
+        if Clock::get()?.unix_timestamp > authorization.expiry {
+            msg!("Claim authorization expired at {}", authorization.expiry);
+            return Err(GameError::ClaimAuthorizationExpired.into());
+        }
+        if authorization.nonce <= player_program_account_data.last_claim_nonce {
+            msg!(
+                "Claim authorization nonce must be greater than {}",
+                player_program_account_data.last_claim_nonce
+            );
+            return Err(GameError::ClaimAuthorizationUsed.into());
+        }
+        player_program_account_data.last_claim_nonce = authorization.nonce;
+
+        let reward_to_claim = player_program_account_data.reward_to_claim;
+        if reward_to_claim == 0 {
+            msg!("No reward to claim");
+            return Err(GameError::UnclaimableAmount.into());
+        }
+
+        // Nobody pays for the associated token account creation here, it must be created before
+        if player_token_account.data_is_empty() {
+            msg!("Player payout token account must exist before the relayed claim");
+            return Err(GameError::InvalidPlayerTokenAccount.into());
+        }
+
+        let decimals = program_account_data.decimals;
+        Self::transfer_from_vault(
+            program_id,
+            program_token_account,
+            pda_account,
+            player_token_account,
+            token_program,
+            mint_account,
+            reward_to_claim,
+            decimals,
+        )?;
+
+        let player_owner = player_program_account_data.owner;
+        Self::record_claim(
+            program_account,
+            program_account_data,
+            player_program_account,
+            player_program_account_data,
+            reward_to_claim,
+        )?;
 
-        // When init, program_token_account ownership (not account owner) has been transfer to pda (an account without private key)
-        let (pda, nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
+        GameEvent::RewardClaimed {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            owner: player_owner,
+            mint: None,
+            amount: reward_to_claim,
+        }
+        .emit();
+
+        Ok(())
+    }
 
-        // Make sure it is the token account used during the program initialization
+    // Make sure the accounts are the ones recorded during the program initialization
+    fn check_game_token_accounts(
+        program_account_data: &GameInfo,
+        program_token_account: &AccountInfo,
+        token_program: &AccountInfo,
+        mint_account: &AccountInfo,
+    ) -> ProgramResult {
         if *program_token_account.key != program_account_data.spl_token_account {
             msg!("Program token account do not match with current program token account");
             return Err(ProgramError::InvalidAccountData);
         }
-
-        let player_token_account = next_account_info(account_iter)?;
-        // No need to check player_token_account owner == spl_token::id(), if invalid owner, program will panic when do transfer
-        let token_program = next_account_info(account_iter)?;
-        if !spl_token::check_id(token_program.key) {
-            msg!("Token program is not SPL TOKEN program");
+        if *token_program.key != program_account_data.token_program {
+            msg!("Token program do not match with current program token program");
             return Err(ProgramError::IncorrectProgramId);
         }
+        if *mint_account.key != program_account_data.mint {
+            msg!("Mint account do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+        Ok(())
+    }
+
+    // Pay the claimed reward from the vault, signed by the PDA
+    // Validate both token accounts before transfer, so mismatch fail with GameError instead of error from token program
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_from_vault<'a>(
+        program_id: &Pubkey,
+        program_token_account: &AccountInfo<'a>,
+        pda_account: &AccountInfo<'a>,
+        player_token_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        if program_token_account.owner != token_program.key {
+            msg!("Program token account owner is not the token program");
+            return Err(ProgramError::IllegalOwner);
+        }
+        let program_token_account_data =
+            token::unpack_account(&program_token_account.try_borrow_data()?)?;
+        if program_token_account_data.mint != *mint_account.key {
+            msg!("Program token account mint do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+        if program_token_account_data.amount < amount {
+            msg!(
+                "Program token account balance {} is not enough",
+                program_token_account_data.amount
+            );
+            return Err(GameError::InsufficientVaultBalance.into());
+        }
+
+        if player_token_account.owner != token_program.key {
+            msg!("Player token account owner is not the token program");
+            return Err(ProgramError::IllegalOwner);
+        }
+        let player_token_account_data =
+            token::unpack_account(&player_token_account.try_borrow_data()?)?;
+        if player_token_account_data.mint != *mint_account.key {
+            msg!("Player token account mint do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+        if player_token_account_data.is_frozen() {
+            msg!("Player token account is frozen");
+            return Err(GameError::TokenAccountFrozen.into());
+        }
+
+        // When init, program_token_account ownership (not account owner) has been transfer to pda (an account without private key)
+        let (pda, nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
 
-        //https://docs.rs/spl-token/3.2.0/spl_token/instruction/fn.transfer.html
-        let transfer_to_player_instruction = spl_token::instruction::transfer(
-            &spl_token::id(),
+        // transfer_checked validate the mint and decimals, which required by Token-2022 mint extensions such as transfer fee
+        let transfer_to_player_instruction = token::transfer_checked(
+            token_program.key,
             program_token_account.key,
+            mint_account.key,
             player_token_account.key,
             &pda,
-            &[&pda],
-            player_program_account_data.reward_to_claim,
+            amount,
+            decimals,
         )?;
 
         msg!("Claim reward by transfer from program token account to the player");
@@ -121,137 +4158,216 @@ impl Processor {
             &transfer_to_player_instruction,
             &[
                 program_token_account.clone(),
+                mint_account.clone(),
                 player_token_account.clone(),
-                token_program.clone(),
                 pda_account.clone(),
+                token_program.clone(),
             ],
             &[&[PDA_SEED.as_bytes(), &[nonce]]],
-        )?;
+        )
+    }
 
-        // After transfer, reset reward amount for player
+    // After transfer, reset reward amount for player and record the claim in the statistics
+    fn record_claim(
+        program_account: &AccountInfo,
+        mut program_account_data: GameInfo,
+        player_program_account: &AccountInfo,
+        mut player_program_account_data: Player,
+        amount: u64,
+    ) -> ProgramResult {
         player_program_account_data.reward_to_claim = 0;
-
+        player_program_account_data.total_claimed =
+            u64::checked_add(player_program_account_data.total_claimed, amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
         Player::pack(
             player_program_account_data,
             &mut player_program_account.try_borrow_mut_data()?,
         )?;
 
-        Ok(())
+        program_account_data.total_claimed =
+            u64::checked_add(program_account_data.total_claimed, amount)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )
     }
 
-    // 0 - [signer]   - The admin (holder) account
-    // 1 - []         - Program account
-    // 2 - [writable] - The player program account
-    // 3 - [writable] - The player upline program account
-    pub fn process_add_reward(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo],
+    // Admin can add any amount of reward, other signer must be an operator and stay within the reward cap
+    fn charge_reward_authority(
+        program_account_data: &mut GameInfo,
+        authority: &Pubkey,
         reward_amount: u64,
     ) -> ProgramResult {
-        msg!("process_add_reward");
-        let account_iter = &mut accounts.iter();
-        let admin_holder_account = next_account_info(account_iter)?;
-
-        if !admin_holder_account.is_signer {
-            msg!("Admin account must be signed");
-            return Err(ProgramError::IllegalOwner);
+        if program_account_data.admin == *authority {
+            return Ok(());
         }
-
-        let program_account = next_account_info(account_iter)?;
-        if program_account.owner != program_id {
-            msg!("Program account owner is not the current program");
-            return Err(ProgramError::IncorrectProgramId);
+        let slot = match program_account_data.find_operator(authority) {
+            Some(slot) => slot,
+            None => {
+                msg!("Add reward only can be executed by admin or operator");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        };
+        let operator = &mut program_account_data.operators[slot];
+        let total_rewarded = u64::checked_add(operator.total_rewarded, reward_amount)
+            .ok_or(GameError::RewardAmountOverflow)?;
+        if let COption::Some(reward_cap) = operator.reward_cap {
+            if total_rewarded > reward_cap {
+                msg!("Operator reward cap {} exceeded", reward_cap);
+                return Err(GameError::OperatorRewardCapExceeded.into());
+            }
         }
+        operator.total_rewarded = total_rewarded;
+        Ok(())
+    }
 
-        let player_program_account = next_account_info(account_iter)?;
-        if player_program_account.owner != program_id {
-            msg!("Player program account owner is not the current program");
-            return Err(ProgramError::IncorrectProgramId);
+    // Give back reward charged to an operator which was not awarded in the end
+    // Nothing to do for the admin, or an operator removed since
+    fn release_reward_authority(
+        program_account_data: &mut GameInfo,
+        authority: &Pubkey,
+        reward_amount: u64,
+    ) {
+        if let Some(slot) = program_account_data.find_operator(authority) {
+            let operator = &mut program_account_data.operators[slot];
+            operator.total_rewarded = operator.total_rewarded.saturating_sub(reward_amount);
         }
+    }
 
-        let program_account_data = GameInfo::unpack_unchecked(&program_account.try_borrow_data()?)?;
-        if !program_account_data.is_initialized {
-            msg!("Program account not initialized");
-            return Err(GameError::NotInitialize.into());
+    // The reward cap is denominated in the game mint, so reward in other mints is limited to admin and uncapped operators
+    fn check_vault_reward_authority(
+        program_account_data: &GameInfo,
+        authority: &Pubkey,
+    ) -> ProgramResult {
+        if program_account_data.admin == *authority {
+            return Ok(());
         }
-
-        if program_account_data.admin != *admin_holder_account.key {
-            msg!("Add reward only can be executed by admin");
-            return Err(ProgramError::MissingRequiredSignature);
+        match program_account_data.find_operator(authority) {
+            Some(slot) if program_account_data.operators[slot].reward_cap.is_some() => {
+                msg!("Operator with a reward cap can only add reward in the game mint");
+                Err(GameError::OperatorRewardCapExceeded.into())
+            }
+            Some(_) => Ok(()),
+            None => {
+                msg!("Add reward only can be executed by admin or operator");
+                Err(ProgramError::MissingRequiredSignature)
+            }
         }
+    }
 
-        let mut player_program_account_data =
-            Player::unpack_unchecked(&player_program_account.try_borrow_data()?)?;
-
-        if !player_program_account_data.is_initialized {
-            msg!("Player program account is not initialized");
-            return Err(GameError::NotInitialize.into());
-        }
+    // Take the upline player account from the iterator, only if the player has upline
+    fn next_upline<'a, 'b>(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        account_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+        player_data: &Player,
+    ) -> Result<Option<(&'a AccountInfo<'b>, Player)>, ProgramError> {
+        let upline = match player_data.upline {
+            COption::Some(upline) => upline,
+            COption::None => return Ok(None),
+        };
+        let upline_player_program_account = next_account_info(account_iter)?;
 
-        if player_program_account_data.upline != COption::None {
-            let upline_player_program_account = next_account_info(account_iter)?;
+        let upline_player_program_account_data =
+            Self::unpack_game_player(program_id, program_account, upline_player_program_account)?;
 
-            if upline_player_program_account.owner != program_id {
-                msg!("Upline program account owner is not current program");
-                return Err(ProgramError::IncorrectProgramId);
-            }
+        if *upline_player_program_account.key != upline {
+            msg!("Upline account passed was not current player upline");
+            return Err(GameError::InvalidUpline.into());
+        }
 
-            let mut upline_player_program_account_data =
-                Player::unpack_unchecked(&upline_player_program_account.try_borrow_data()?)?;
+        Ok(Some((
+            upline_player_program_account,
+            upline_player_program_account_data,
+        )))
+    }
 
-            if !upline_player_program_account_data.is_initialized {
-                msg!("Upline player program account is not initialized");
-                return Err(GameError::NotInitialize.into());
-            }
+    // Upline receive 10% of the reward, return (player reward, upline reward)
+    fn split_reward(reward_amount: u64, has_upline: bool) -> (u64, u64) {
+        if has_upline {
+            (reward_amount * 90 / 100, reward_amount * 10 / 100)
+        } else {
+            (reward_amount, 0)
+        }
+    }
 
-            if *upline_player_program_account.key != player_program_account_data.upline.unwrap() {
-                msg!("Upline account passed was not current player upline");
-                return Err(GameError::InvalidUpline.into());
-            }
+    // Credit the reward in the mint recorded during initialization (or lamports) to the player and the upline
+    // Return (player reward, upline reward)
+    fn credit_reward(
+        program_account_data: &mut GameInfo,
+        player_program_account: &AccountInfo,
+        mut player_program_account_data: Player,
+        upline_player_program_account: Option<(&AccountInfo, Player)>,
+        reward_amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        let (player_reward, upline_reward) =
+            Self::split_reward(reward_amount, upline_player_program_account.is_some());
 
-            let upline_reward = reward_amount * 10 / 100;
-            let player_reward = reward_amount * 90 / 100;
+        player_program_account_data.reward_to_claim =
+            u64::checked_add(player_program_account_data.reward_to_claim, player_reward)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        player_program_account_data.total_earned =
+            u64::checked_add(player_program_account_data.total_earned, player_reward)
+                .ok_or(GameError::RewardAmountOverflow)?;
+        program_account_data.total_awarded =
+            u64::checked_add(program_account_data.total_awarded, player_reward)
+                .ok_or(GameError::RewardAmountOverflow)?;
 
-            player_program_account_data.reward_to_claim =
-                u64::checked_add(player_program_account_data.reward_to_claim, player_reward)
-                    .ok_or(GameError::RewardAmountOverflow)?;
+        msg!("Player reward {}", player_reward);
+        Player::pack(
+            player_program_account_data,
+            &mut player_program_account.try_borrow_mut_data()?,
+        )?;
 
+        if let Some((upline_player_program_account, mut upline_player_program_account_data)) =
+            upline_player_program_account
+        {
+            // The commission of a frozen upline is withheld and counted as forfeited
+            if upline_player_program_account_data.frozen {
+                msg!(
+                    "Upline is frozen, commission {} is forfeited",
+                    upline_reward
+                );
+                program_account_data.total_forfeited =
+                    u64::checked_add(program_account_data.total_forfeited, upline_reward)
+                        .ok_or(GameError::RewardAmountOverflow)?;
+                return Ok((player_reward, 0));
+            }
             upline_player_program_account_data.reward_to_claim = u64::checked_add(
                 upline_player_program_account_data.reward_to_claim,
                 upline_reward,
             )
             .ok_or(GameError::RewardAmountOverflow)?;
-
-            msg!("Player reward {}", player_reward);
-            Player::pack(
-                player_program_account_data,
-                &mut player_program_account.try_borrow_mut_data()?,
-            )?;
+            upline_player_program_account_data.total_earned = u64::checked_add(
+                upline_player_program_account_data.total_earned,
+                upline_reward,
+            )
+            .ok_or(GameError::RewardAmountOverflow)?;
+            upline_player_program_account_data.total_referral_earned = u64::checked_add(
+                upline_player_program_account_data.total_referral_earned,
+                upline_reward,
+            )
+            .ok_or(GameError::RewardAmountOverflow)?;
+            program_account_data.total_awarded =
+                u64::checked_add(program_account_data.total_awarded, upline_reward)
+                    .ok_or(GameError::RewardAmountOverflow)?;
 
             msg!("Upline reward {}", upline_reward);
             Player::pack(
                 upline_player_program_account_data,
                 &mut upline_player_program_account.try_borrow_mut_data()?,
             )?;
-        } else {
-            player_program_account_data.reward_to_claim =
-                u64::checked_add(player_program_account_data.reward_to_claim, reward_amount)
-                    .ok_or(GameError::RewardAmountOverflow)?;
-
-            Player::pack(
-                player_program_account_data,
-                &mut player_program_account.try_borrow_mut_data()?,
-            )?;
         }
 
-        // msg!("Add reward {}", reward_amount);
-        Ok(())
+        Ok((player_reward, upline_reward))
     }
 
     // 0 - [signer]   - The admin (holder) account
     // 1 - [writable] - Program account
     // 2 - [writable] - An token account created by the admin, and pre-funded
-    // 3 - []         - The token program
+    // 3 - []         - The token program, SPL TOKEN or Token-2022
+    // 4 - []         - The mint of the token account
     pub fn process_init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         msg!("process_init");
         // Make the account info array iterable
@@ -278,33 +4394,43 @@ impl Processor {
         }
 
         let token_account = next_account_info(account_iter)?;
-        // Make sure token_account belongs to TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA (SPL TOKEN)
-        if *token_account.owner != spl_token::id() {
-            msg!("Token account owner is not SPL TOKEN program");
+        let token_program = next_account_info(account_iter)?;
+        // Make sure the token_program is the SPL TOKEN or Token-2022 on-chain program
+        if !token::is_token_program(token_program.key) {
+            msg!("Token program is not SPL TOKEN or Token-2022 program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Make sure token_account belongs to the token program
+        if token_account.owner != token_program.key {
+            msg!("Token account owner is not the token program");
             return Err(ProgramError::IllegalOwner);
         }
 
-        // How to check whether the token_account is an account for token program ? Eg: rent space ? Or, should I check it ?
-        // Todo: Transfer token_account ownership to current program PDA
-        // Derive program address
-        // Destructure pubkey as pda, put _ to avoid compiler complain unused nonce
-        let (pda, _nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
+        let mint_account = next_account_info(account_iter)?;
+        if mint_account.owner != token_program.key {
+            msg!("Mint account owner is not the token program");
+            return Err(ProgramError::IllegalOwner);
+        }
 
-        let token_program = next_account_info(account_iter)?;
-        // Make sure the token_program is the SPL_TOKEN on-chain program
-        if !spl_token::check_id(token_program.key) {
-            msg!("Token program is not SPL TOKEN program");
-            return Err(ProgramError::IncorrectProgramId);
+        let token_account_data = token::unpack_account(&token_account.try_borrow_data()?)?;
+        if token_account_data.mint != *mint_account.key {
+            msg!("Mint account do not match with the token account mint");
+            return Err(ProgramError::InvalidAccountData);
         }
+        let mint_account_data = token::unpack_mint(&mint_account.try_borrow_data()?)?;
+
+        // Transfer token_account ownership to current program PDA
+        // Destructure pubkey as pda, put _ to avoid compiler complain unused nonce
+        let (pda, _nonce) = Pubkey::find_program_address(&[PDA_SEED.as_bytes()], program_id);
 
         // Transfer token_account ownership (not the program owner, the owner here is the owner in token_account data) to the pda
-        let set_authority_instruction = spl_token::instruction::set_authority(
+        let set_authority_instruction = token::set_authority(
             token_program.key,
             token_account.key,
             Some(&pda),
             spl_token::instruction::AuthorityType::AccountOwner,
             admin_account.key,
-            &[&admin_account.key],
         )?;
 
         // Invoke token_account ownership transfer
@@ -312,13 +4438,21 @@ impl Processor {
         // All account involved in the instruction need to be passed when invoke
         invoke(
             &set_authority_instruction,
-            &[token_account.clone(), admin_account.clone()],
+            &[
+                token_account.clone(),
+                admin_account.clone(),
+                token_program.clone(),
+            ],
         )?;
 
         // Update program account data
         program_account_data.admin = *admin_account.key;
         program_account_data.is_initialized = true;
         program_account_data.spl_token_account = *token_account.key;
+        program_account_data.token_program = *token_program.key;
+        program_account_data.mint = *mint_account.key;
+        program_account_data.decimals = mint_account_data.decimals;
+        program_account_data.payout_mode = PayoutMode::Token;
 
         // Pack / serialize the updated program account data
         GameInfo::pack(
@@ -326,14 +4460,143 @@ impl Processor {
             &mut program_account.try_borrow_mut_data()?,
         )?;
 
+        GameEvent::GameInitialized {
+            program_account: *program_account.key,
+            admin: *admin_account.key,
+            payout_mode: PayoutMode::Token,
+            mint: Some(*mint_account.key),
+        }
+        .emit();
+
+        Ok(())
+    }
+
+    // 0 - [signer, writable] - The player (holder) account, pay for the referral code creation
+    // 1 - []         - Program account
+    // 2 - []         - The player program account
+    // 3 - [writable] - The referral code PDA of ["code", program account, code]
+    // 4 - []         - The system program
+    pub fn process_claim_referral_code(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        code: Vec<u8>,
+    ) -> ProgramResult {
+        msg!("process_claim_referral_code");
+        let account_iter = &mut accounts.iter();
+        let player_holder_account = next_account_info(account_iter)?;
+        if !player_holder_account.is_signer {
+            msg!("Player holder account must be signed");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let program_account = next_account_info(account_iter)?;
+        if program_account.owner != program_id {
+            msg!("Program account owner is not the current program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let player_program_account = next_account_info(account_iter)?;
+        let player_data =
+            Self::unpack_game_player(program_id, program_account.key, player_program_account)?;
+        if player_data.owner != *player_holder_account.key {
+            msg!("Only the player can claim a referral code");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let referral_code_account = next_account_info(account_iter)?;
+        let (referral_code, nonce) =
+            Self::find_referral_code_address(program_id, program_account.key, &code);
+        if *referral_code_account.key != referral_code {
+            msg!("Referral code account is not the PDA of the code");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if !referral_code_account.data_is_empty() {
+            msg!("Referral code already claimed");
+            return Err(GameError::ReferralCodeAlreadyExists.into());
+        }
+
+        let system_program_account = next_account_info(account_iter)?;
+        Self::create_pda_account(
+            player_holder_account,
+            referral_code_account,
+            system_program_account,
+            ReferralCode::LEN,
+            program_id,
+            &[
+                REFERRAL_CODE_SEED.as_bytes(),
+                program_account.key.as_ref(),
+                &code,
+                &[nonce],
+            ],
+        )?;
+
+        ReferralCode::pack(
+            ReferralCode {
+                is_initialized: true,
+                player: *player_program_account.key,
+            },
+            &mut referral_code_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::ReferralCodeClaimed {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            code,
+        }
+        .emit();
+
         Ok(())
     }
 
+    fn find_referral_code_address(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        code: &[u8],
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                REFERRAL_CODE_SEED.as_bytes(),
+                program_account.as_ref(),
+                code,
+            ],
+            program_id,
+        )
+    }
+
+    // Return the player program account owning the referral code
+    fn resolve_referral_code(
+        program_id: &Pubkey,
+        program_account: &Pubkey,
+        referral_code_account: &AccountInfo,
+        code: &[u8],
+    ) -> Result<Pubkey, ProgramError> {
+        let (referral_code, _nonce) =
+            Self::find_referral_code_address(program_id, program_account, code);
+        if *referral_code_account.key != referral_code {
+            msg!("Referral code account is not the PDA of the code");
+            return Err(GameError::InvalidPdaAccount.into());
+        }
+        if referral_code_account.owner != program_id {
+            msg!("Referral code not claimed");
+            return Err(GameError::ReferralCodeNotFound.into());
+        }
+        let referral_code_data = ReferralCode::unpack(&referral_code_account.try_borrow_data()?)?;
+        Ok(referral_code_data.player)
+    }
+
     // 0 - [signer]   - The player (holder) account
     // 1 - [writable] - The player account for the program
-    // 2 - []         - The program account
-    // 3 - []         - The upline player account for the program
-    pub fn process_register(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    // 2 - [writable] - The program account
+    // 3 - [writable] - The upline player account for the program
+    // With a referral code
+    // 3 - []         - The referral code PDA of ["code", program account, code]
+    // 4 - [writable] - The upline player account owning the referral code
+    pub fn process_register(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        referral_code: Option<Vec<u8>>,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
         msg!("process_register");
         let account_iter = &mut accounts.iter();
         let player_holder_account = next_account_info(account_iter)?;
@@ -363,22 +4626,130 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        let has_upline = accounts.len() == 4;
+        let mut program_account_data =
+            GameInfo::unpack_unchecked(&program_account.try_borrow_data()?)?;
+        if !program_account_data.is_initialized {
+            msg!("Program account not initialized");
+            return Err(GameError::NotInitialize.into());
+        }
+        program_account_data.player_count = u64::checked_add(program_account_data.player_count, 1)
+            .ok_or(GameError::StatisticOverflow)?;
+
+        match program_account_data.registration_gate {
+            RegistrationGate::Open => {}
+            RegistrationGate::AdminSignature => {
+                // Admin account come before the upline accounts
+                let admin_holder_account = next_account_info(account_iter)?;
+                if !admin_holder_account.is_signer
+                    || *admin_holder_account.key != program_account_data.admin
+                {
+                    msg!("Register must be co-signed by the admin");
+                    return Err(GameError::RegistrationNotAllowed.into());
+                }
+            }
+            RegistrationGate::Allowlist => {
+                let leaf = merkle::hash_leaf(&[player_holder_account.key.as_ref()]);
+                if !merkle::verify(&allowlist_proof, &program_account_data.allowlist_root, leaf) {
+                    msg!("Player holder account is not in the allowlist");
+                    return Err(GameError::RegistrationNotAllowed.into());
+                }
+            }
+        }
+
+        // Fee accounts come before the upline accounts
+        let registration_fee = program_account_data.registration_fee;
+        if registration_fee > 0 {
+            Self::collect_registration_fee(
+                program_id,
+                program_account,
+                &program_account_data,
+                player_holder_account,
+                account_iter,
+            )?;
+            program_account_data.total_registration_fees = u64::checked_add(
+                program_account_data.total_registration_fees,
+                registration_fee,
+            )
+            .ok_or(GameError::StatisticOverflow)?;
+        }
+        let mut upline_fee = 0;
 
-        if has_upline {
-            let upline_player_program_account = next_account_info(account_iter)?;
-            // Check upline owner = current program
-            if upline_player_program_account.owner != program_id {
-                msg!("Upline player program account owner is not the current program");
-                return Err(ProgramError::IncorrectProgramId);
+        let upline_player_program_account = match referral_code {
+            Some(referral_code) => {
+                let referral_code_account = next_account_info(account_iter)?;
+                let upline = Self::resolve_referral_code(
+                    program_id,
+                    program_account.key,
+                    referral_code_account,
+                    &referral_code,
+                )?;
+                let upline_player_program_account = next_account_info(account_iter)?;
+                if *upline_player_program_account.key != upline {
+                    msg!("Upline player account does not match the referral code");
+                    return Err(GameError::InvalidUpline.into());
+                }
+                Some(upline_player_program_account)
             }
+            None => account_iter.next(),
+        };
 
+        if let Some(upline_player_program_account) = upline_player_program_account {
             // Check upline is not self-recursive
             if upline_player_program_account.key == player_program_account.key {
                 msg!("Upline cannot be same account as current player");
                 return Err(GameError::SelfRecursiveUpline.into());
             }
 
+            // Upline must be registered in the same program account
+            let mut upline_player_data = Self::unpack_game_player(
+                program_id,
+                program_account.key,
+                upline_player_program_account,
+            )?;
+            if let COption::Some(max_direct_referrals) = program_account_data.max_direct_referrals {
+                if upline_player_data.direct_referrals >= max_direct_referrals {
+                    msg!(
+                        "Upline already has {} direct referrals",
+                        max_direct_referrals
+                    );
+                    return Err(GameError::DirectReferralLimitReached.into());
+                }
+            }
+            upline_player_data.direct_referrals =
+                u64::checked_add(upline_player_data.direct_referrals, 1)
+                    .ok_or(GameError::StatisticOverflow)?;
+            upline_player_data.downline_count =
+                u64::checked_add(upline_player_data.downline_count, 1)
+                    .ok_or(GameError::StatisticOverflow)?;
+
+            // Upline receive a portion of the registration fee, the rest stay in the vault
+            // A frozen upline receive nothing
+            if !upline_player_data.frozen {
+                upline_fee = u64::checked_mul(
+                    registration_fee,
+                    program_account_data.registration_fee_upline_percent as u64,
+                )
+                .ok_or(GameError::RewardAmountOverflow)?
+                    / 100;
+            }
+            upline_player_data.reward_to_claim =
+                u64::checked_add(upline_player_data.reward_to_claim, upline_fee)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+            upline_player_data.total_earned =
+                u64::checked_add(upline_player_data.total_earned, upline_fee)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+            upline_player_data.total_referral_earned =
+                u64::checked_add(upline_player_data.total_referral_earned, upline_fee)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+            program_account_data.total_awarded =
+                u64::checked_add(program_account_data.total_awarded, upline_fee)
+                    .ok_or(GameError::RewardAmountOverflow)?;
+
+            Player::pack(
+                upline_player_data,
+                &mut upline_player_program_account.try_borrow_mut_data()?,
+            )?;
+
             player_data.upline = COption::Some(*upline_player_program_account.key);
         } else {
             player_data.upline = COption::None;
@@ -387,12 +4758,122 @@ impl Processor {
         player_data.is_initialized = true;
         player_data.owner = *player_holder_account.key;
         player_data.reward_to_claim = 0;
+        player_data.program_account = *program_account.key;
+        player_data.registered_at = Clock::get()?.unix_timestamp;
+        // Lock the claim destination, so a relayed claim can't be redirected
+        player_data.payout_token_account = match program_account_data.payout_mode {
+            PayoutMode::Token => token::get_associated_token_address(
+                player_holder_account.key,
+                &program_account_data.mint,
+                &program_account_data.token_program,
+            ),
+            PayoutMode::Lamports => Pubkey::default(),
+        };
+        let upline = player_data.upline.into();
 
         Player::pack(
             player_data,
             &mut player_program_account.try_borrow_mut_data()?,
         )?;
 
+        GameInfo::pack(
+            program_account_data,
+            &mut program_account.try_borrow_mut_data()?,
+        )?;
+
+        GameEvent::PlayerRegistered {
+            program_account: *program_account.key,
+            player_program_account: *player_program_account.key,
+            owner: *player_holder_account.key,
+            upline,
+        }
+        .emit();
+
+        if registration_fee > 0 {
+            GameEvent::RegistrationFeePaid {
+                program_account: *program_account.key,
+                player_program_account: *player_program_account.key,
+                upline,
+                amount: registration_fee,
+                upline_amount: upline_fee,
+            }
+            .emit();
+        }
+
         Ok(())
     }
+
+    // Transfer the registration fee from the player into the vault, the remaining accounts start from the fee source
+    fn collect_registration_fee<'b>(
+        program_id: &Pubkey,
+        program_account: &AccountInfo,
+        program_account_data: &GameInfo,
+        player_holder_account: &AccountInfo<'b>,
+        account_iter: &mut std::slice::Iter<'_, AccountInfo<'b>>,
+    ) -> ProgramResult {
+        let registration_fee = program_account_data.registration_fee;
+        if program_account_data.payout_mode == PayoutMode::Lamports {
+            let lamport_vault_account = next_account_info(account_iter)?;
+            let system_program_account = next_account_info(account_iter)?;
+
+            let (lamport_vault, _nonce) =
+                Self::find_lamport_vault_address(program_id, program_account.key);
+            if *lamport_vault_account.key != lamport_vault {
+                msg!("Lamport vault is not the PDA of the program account");
+                return Err(GameError::InvalidPdaAccount.into());
+            }
+
+            msg!("Pay registration fee {} lamports", registration_fee);
+            return invoke(
+                &system_instruction::transfer(
+                    player_holder_account.key,
+                    lamport_vault_account.key,
+                    registration_fee,
+                ),
+                &[
+                    player_holder_account.clone(),
+                    lamport_vault_account.clone(),
+                    system_program_account.clone(),
+                ],
+            );
+        }
+
+        let player_token_account = next_account_info(account_iter)?;
+        let program_token_account = next_account_info(account_iter)?;
+        let mint_account = next_account_info(account_iter)?;
+        let token_program = next_account_info(account_iter)?;
+
+        if *program_token_account.key != program_account_data.spl_token_account {
+            msg!("Program token account do not match with current program token account");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if *token_program.key != program_account_data.token_program {
+            msg!("Token program do not match with current program token program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *mint_account.key != program_account_data.mint {
+            msg!("Mint account do not match with current program mint");
+            return Err(GameError::MintMismatch.into());
+        }
+
+        msg!("Pay registration fee {}", registration_fee);
+        invoke(
+            &token::transfer_checked(
+                token_program.key,
+                player_token_account.key,
+                mint_account.key,
+                program_token_account.key,
+                player_holder_account.key,
+                registration_fee,
+                program_account_data.decimals,
+            )?,
+            &[
+                player_token_account.clone(),
+                mint_account.clone(),
+                program_token_account.clone(),
+                player_holder_account.clone(),
+                token_program.clone(),
+            ],
+        )
+    }
 }
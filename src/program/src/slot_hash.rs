@@ -0,0 +1,61 @@
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError, sysvar};
+
+// Layout of the SlotHashes sysvar data, see solana_program::slot_hashes
+// 8 bytes entry count, then each entry is the slot followed by its bank hash, newest slot first
+const ENTRY_COUNT_LEN: usize = 8;
+const SLOT_LEN: usize = 8;
+const HASH_LEN: usize = 32;
+const ENTRY_LEN: usize = SLOT_LEN + HASH_LEN;
+
+pub enum SlotHash {
+    // The target slot is not produced yet
+    NotReady,
+    // The target slot is older than the history kept by the sysvar
+    Expired,
+    Found([u8; 32]),
+}
+
+// Find the hash of the target slot, or of the first slot produced after it if the target slot was skipped
+// The sysvar is too big to be deserialized on chain, so entries are read in place from the account data
+pub fn find_slot_hash(
+    slot_hashes_account: &AccountInfo,
+    target_slot: u64,
+) -> Result<SlotHash, ProgramError> {
+    if !sysvar::slot_hashes::check_id(slot_hashes_account.key) {
+        msg!("Account is not the SlotHashes sysvar");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let data = slot_hashes_account.try_borrow_data()?;
+    if data.len() < ENTRY_COUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut entry_count = [0; ENTRY_COUNT_LEN];
+    entry_count.copy_from_slice(&data[..ENTRY_COUNT_LEN]);
+    let entry_count = u64::from_le_bytes(entry_count) as usize;
+    let entries = data[ENTRY_COUNT_LEN..]
+        .chunks_exact(ENTRY_LEN)
+        .take(entry_count);
+
+    let mut found = None;
+    let mut oldest_slot = u64::MAX;
+    for entry in entries {
+        let mut slot = [0; SLOT_LEN];
+        slot.copy_from_slice(&entry[..SLOT_LEN]);
+        let slot = u64::from_le_bytes(slot);
+        // Entries are sorted newest first, so the last match is the closest to the target
+        if slot >= target_slot {
+            let mut hash = [0; HASH_LEN];
+            hash.copy_from_slice(&entry[SLOT_LEN..]);
+            found = Some(hash);
+        }
+        oldest_slot = slot;
+    }
+
+    Ok(match found {
+        None => SlotHash::NotReady,
+        // Slots right after the target may be evicted too, the match is not the closest one anymore
+        Some(_) if oldest_slot > target_slot => SlotHash::Expired,
+        Some(hash) => SlotHash::Found(hash),
+    })
+}
@@ -1,4 +1,8 @@
+// arrayref macros cast the offset when a chunk size is not a literal
+#![allow(clippy::ptr_offset_with_cast)]
+
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     msg,
     program_error::ProgramError,
@@ -7,6 +11,35 @@ use solana_program::{
     pubkey::Pubkey,
 };
 
+// Maximum number of operators can be added to a game
+pub const MAX_OPERATORS: usize = 5;
+const OPERATORS_LEN: usize = Operator::LEN * MAX_OPERATORS;
+
+// Maximum length of a referral code in bytes
+pub const MAX_REFERRAL_CODE_LEN: usize = 16;
+
+// Size of a distribution claimed bitmap PDA, one bit per distribution index
+pub const DISTRIBUTION_BITMAP_LEN: usize = 1024;
+pub const DISTRIBUTION_BITMAP_BITS: u64 = DISTRIBUTION_BITMAP_LEN as u64 * 8;
+// Size of a player voucher bitmap PDA, one bit per voucher nonce
+pub const VOUCHER_BITMAP_LEN: usize = 128;
+pub const VOUCHER_BITMAP_BITS: u64 = VOUCHER_BITMAP_LEN as u64 * 8;
+// Number of ranks kept on a season leaderboard, also the maximum number of prize tiers
+pub const SEASON_LEADERBOARD_LEN: usize = 10;
+const LEADERBOARD_LEN: usize = LeaderboardEntry::LEN * SEASON_LEADERBOARD_LEN;
+const PRIZES_LEN: usize = 8 * SEASON_LEADERBOARD_LEN;
+// Scale of GameInfo::reward_per_share, so a reward rate lower than the total staked still accrue
+pub const STAKE_REWARD_PRECISION: u128 = 1_000_000_000_000;
+// Size of a player quest bitmap PDA, one bit per quest id, also limit the number of quests of a game
+pub const QUEST_BITMAP_LEN: usize = 128;
+pub const MAX_QUESTS: u64 = QUEST_BITMAP_LEN as u64 * 8;
+// Maximum number of reward tiers of a loot table
+pub const MAX_LOOT_TIERS: usize = 8;
+const LOOT_TIERS_LEN: usize = LootTier::LEN * MAX_LOOT_TIERS;
+// Maximum number of days of the check-in schedule
+pub const MAX_CHECK_IN_DAYS: usize = 7;
+const CHECK_IN_SCHEDULE_LEN: usize = 8 * MAX_CHECK_IN_DAYS;
+
 #[derive(Debug)]
 pub struct GameInfo {
     pub is_initialized: bool, // 1
@@ -14,6 +47,118 @@ pub struct GameInfo {
     pub admin: Pubkey, // 32
     // Token account which has been pre-funded
     pub spl_token_account: Pubkey, // 32
+    // Accounts authorized by admin to add reward to player, empty slot has default pubkey
+    pub operators: [Operator; MAX_OPERATORS], // 52 * MAX_OPERATORS
+    // Token program owning spl_token_account, either SPL TOKEN or Token-2022
+    pub token_program: Pubkey, // 32
+    // Mint of spl_token_account
+    pub mint: Pubkey, // 32
+    // Decimals of the mint, required by transfer_checked
+    pub decimals: u8, // 1
+    // Whether reward is paid from the token account or the lamport vault
+    pub payout_mode: PayoutMode, // 1
+    // Lifetime statistics, amounts are in the mint recorded during initialization (or lamports)
+    pub player_count: u64,  // 8
+    pub total_awarded: u64, // 8
+    pub total_claimed: u64, // 8
+    // Maximum number of players registered with the same upline, no limit when None
+    pub max_direct_referrals: COption<u64>, // 4 + 8
+    // Number of published distributions, also the index of the next distribution PDA
+    pub distribution_count: u64, // 8
+    // Reward taken from or withheld from frozen players, kept in the vault
+    // Only in the mint recorded during initialization (or lamports), reward balances of other mints are not counted
+    pub total_forfeited: u64, // 8
+    // Who can register in the game
+    pub registration_gate: RegistrationGate, // 1
+    // Merkle root of the allowed player holder accounts, only used by RegistrationGate::Allowlist
+    pub allowlist_root: [u8; 32], // 32
+    // Fee paid by the player on registration, in the mint recorded during initialization (or lamports), 0 for free registration
+    pub registration_fee: u64, // 8
+    // Percentage of the registration fee credited to the upline reward, the rest stay in the vault
+    pub registration_fee_upline_percent: u8, // 1
+    // Total registration fee collected into the vault, including the upline portion
+    pub total_registration_fees: u64, // 8
+    // Number of created seasons, also the index of the next season PDA
+    pub season_count: u64, // 8
+    // Reward per second shared by all the staked tokens, in the mint recorded during initialization
+    pub stake_reward_rate: u64, // 8
+    // Tokens locked in the stake vault
+    pub total_staked: u64, // 8
+    // Accumulated reward per staked token, scaled by STAKE_REWARD_PRECISION
+    pub reward_per_share: u128, // 16
+    // Unix timestamp up to which reward_per_share has been accumulated
+    pub last_stake_update: i64, // 8
+    // Number of created quests, also the id of the next quest PDA
+    pub quest_count: u64, // 8
+    // Reward of each day of a check-in streak, the last one is repeated once the streak is longer
+    // Check-in is disabled while the schedule is empty
+    pub check_in_schedule_len: u8,                   // 1
+    pub check_in_schedule: [u64; MAX_CHECK_IN_DAYS], // 8 * MAX_CHECK_IN_DAYS
+    // Percentage of a reward transfer between players kept in the vault, 0 for free transfer
+    pub transfer_fee_percent: u8, // 1
+    // Total transfer fee kept in the vault
+    pub total_transfer_fees: u64, // 8
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PayoutMode {
+    // Reward paid in SPL token from spl_token_account
+    Token,
+    // Reward paid in lamports from the lamport vault PDA
+    Lamports,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RegistrationGate {
+    // Anyone can register
+    Open,
+    // Register must be co-signed by the admin
+    AdminSignature,
+    // Player holder account must be a leaf of the allowlist root, proven in the Register instruction data
+    Allowlist,
+}
+
+impl GameInfo {
+    // Return the slot index of the operator
+    pub fn find_operator(&self, key: &Pubkey) -> Option<usize> {
+        self.operators
+            .iter()
+            .position(|operator| operator.is_active() && operator.key == *key)
+    }
+
+    // Return the first empty slot index
+    pub fn find_empty_operator_slot(&self) -> Option<usize> {
+        self.operators
+            .iter()
+            .position(|operator| !operator.is_active())
+    }
+
+    // Accumulate the reward per share from the last update until now, nothing accrue while no token is staked
+    // Return None on overflow
+    pub fn update_reward_per_share(&mut self, now: i64) -> Option<()> {
+        if now <= self.last_stake_update {
+            return Some(());
+        }
+        if self.total_staked > 0 {
+            let elapsed = (now - self.last_stake_update) as u128;
+            let reward = elapsed.checked_mul(self.stake_reward_rate as u128)?;
+            let reward_per_share =
+                reward.checked_mul(STAKE_REWARD_PRECISION)? / self.total_staked as u128;
+            self.reward_per_share = self.reward_per_share.checked_add(reward_per_share)?;
+        }
+        self.last_stake_update = now;
+        Some(())
+    }
+
+    // Reward of the given day of a check-in streak, None while check-in is disabled
+    pub fn check_in_reward(&self, streak: u64) -> Option<u64> {
+        let len = self.check_in_schedule_len as usize;
+        if len == 0 {
+            return None;
+        }
+        let day = usize::try_from(streak).unwrap_or(usize::MAX).clamp(1, len);
+        Some(self.check_in_schedule[day - 1])
+    }
 }
 
 // Pack expect Sealed and IsInitialized
@@ -28,13 +173,105 @@ impl IsInitialized for GameInfo {
 }
 
 impl Pack for GameInfo {
-    const LEN: usize = 1 + 32 + 32;
+    const LEN: usize = 1
+        + 32
+        + 32
+        + OPERATORS_LEN
+        + 32
+        + 32
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 4
+        + 8
+        + 8
+        + 8
+        + 1
+        + 32
+        + 8
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 16
+        + 8
+        + 8
+        + 1
+        + CHECK_IN_SCHEDULE_LEN
+        + 1
+        + 8;
     // Unpack account data (byte buffer) to GameInfo
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         // Shadow src argument, and use array_ref! to make src slice-able
         let src = array_ref![src, 0, GameInfo::LEN];
         // Slice src based on struct property byte
-        let (is_initialized, owner, spl_token_account) = array_refs![&src, 1, 32, 32];
+        let (
+            is_initialized,
+            owner,
+            spl_token_account,
+            operators_src,
+            token_program,
+            mint,
+            decimals,
+            payout_mode,
+            player_count,
+            total_awarded,
+            total_claimed,
+            has_max_direct_referrals,
+            max_direct_referrals,
+            distribution_count,
+            total_forfeited,
+            registration_gate,
+            allowlist_root,
+            registration_fee,
+            registration_fee_upline_percent,
+            total_registration_fees,
+            season_count,
+            stake_reward_rate,
+            total_staked,
+            reward_per_share,
+            last_stake_update,
+            quest_count,
+            check_in_schedule_len,
+            check_in_schedule_src,
+            transfer_fee_percent,
+            total_transfer_fees,
+        ) = array_refs![
+            &src,
+            1,
+            32,
+            32,
+            OPERATORS_LEN,
+            32,
+            32,
+            1,
+            1,
+            8,
+            8,
+            8,
+            4,
+            8,
+            8,
+            8,
+            1,
+            32,
+            8,
+            1,
+            8,
+            8,
+            8,
+            8,
+            16,
+            8,
+            8,
+            1,
+            CHECK_IN_SCHEDULE_LEN,
+            1,
+            8
+        ];
         // Convert is_initialized from byte to bool
         let is_initialized = match is_initialized {
             // First element is 0
@@ -43,6 +280,44 @@ impl Pack for GameInfo {
             [1] => true,
             _ => return Err(ProgramError::InvalidAccountData),
         };
+        let payout_mode = match payout_mode {
+            [0] => PayoutMode::Token,
+            [1] => PayoutMode::Lamports,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mut operators = [Operator::default(); MAX_OPERATORS];
+        // Each chunk of the operators byte is one operator slot
+        for (operator, chunk) in operators
+            .iter_mut()
+            .zip(operators_src.chunks_exact(Operator::LEN))
+        {
+            *operator = Operator::unpack_from_slice(chunk)?;
+        }
+        let max_direct_referrals = match has_max_direct_referrals {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(u64::from_le_bytes(*max_direct_referrals)),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let registration_gate = match registration_gate {
+            [0] => RegistrationGate::Open,
+            [1] => RegistrationGate::AdminSignature,
+            [2] => RegistrationGate::Allowlist,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if check_in_schedule_len[0] as usize > MAX_CHECK_IN_DAYS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut check_in_schedule = [0; MAX_CHECK_IN_DAYS];
+        for (reward, chunk) in check_in_schedule
+            .iter_mut()
+            .zip(check_in_schedule_src.chunks_exact(8))
+        {
+            *reward = u64::from_le_bytes(
+                chunk
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            );
+        }
         // Return GameInfo struct, which unpacked from account data
         Ok(GameInfo {
             is_initialized,
@@ -50,6 +325,32 @@ impl Pack for GameInfo {
             admin: Pubkey::new_from_array(*owner),
             // Dereference spl_token_account to get the byte array in heap
             spl_token_account: Pubkey::new_from_array(*spl_token_account),
+            operators,
+            token_program: Pubkey::new_from_array(*token_program),
+            mint: Pubkey::new_from_array(*mint),
+            decimals: decimals[0],
+            payout_mode,
+            player_count: u64::from_le_bytes(*player_count),
+            total_awarded: u64::from_le_bytes(*total_awarded),
+            total_claimed: u64::from_le_bytes(*total_claimed),
+            max_direct_referrals,
+            distribution_count: u64::from_le_bytes(*distribution_count),
+            total_forfeited: u64::from_le_bytes(*total_forfeited),
+            registration_gate,
+            allowlist_root: *allowlist_root,
+            registration_fee: u64::from_le_bytes(*registration_fee),
+            registration_fee_upline_percent: registration_fee_upline_percent[0],
+            total_registration_fees: u64::from_le_bytes(*total_registration_fees),
+            season_count: u64::from_le_bytes(*season_count),
+            stake_reward_rate: u64::from_le_bytes(*stake_reward_rate),
+            total_staked: u64::from_le_bytes(*total_staked),
+            reward_per_share: u128::from_le_bytes(*reward_per_share),
+            last_stake_update: i64::from_le_bytes(*last_stake_update),
+            quest_count: u64::from_le_bytes(*quest_count),
+            check_in_schedule_len: check_in_schedule_len[0],
+            check_in_schedule,
+            transfer_fee_percent: transfer_fee_percent[0],
+            total_transfer_fees: u64::from_le_bytes(*total_transfer_fees),
         })
     }
 
@@ -59,13 +360,101 @@ impl Pack for GameInfo {
         let dst = array_mut_ref![dst, 0, GameInfo::LEN];
         // Slice dst into mutable byte chunks
         // Added _dst postfix to avoid shadowing when destructure from GameInfo struct
-        let (is_initialized_dst, admin_dst, spl_token_account_dst) =
-            mut_array_refs![dst, 1, 32, 32];
+        let (
+            is_initialized_dst,
+            admin_dst,
+            spl_token_account_dst,
+            operators_dst,
+            token_program_dst,
+            mint_dst,
+            decimals_dst,
+            payout_mode_dst,
+            player_count_dst,
+            total_awarded_dst,
+            total_claimed_dst,
+            has_max_direct_referrals_dst,
+            max_direct_referrals_dst,
+            distribution_count_dst,
+            total_forfeited_dst,
+            registration_gate_dst,
+            allowlist_root_dst,
+            registration_fee_dst,
+            registration_fee_upline_percent_dst,
+            total_registration_fees_dst,
+            season_count_dst,
+            stake_reward_rate_dst,
+            total_staked_dst,
+            reward_per_share_dst,
+            last_stake_update_dst,
+            quest_count_dst,
+            check_in_schedule_len_dst,
+            check_in_schedule_dst,
+            transfer_fee_percent_dst,
+            total_transfer_fees_dst,
+        ) = mut_array_refs![
+            dst,
+            1,
+            32,
+            32,
+            OPERATORS_LEN,
+            32,
+            32,
+            1,
+            1,
+            8,
+            8,
+            8,
+            4,
+            8,
+            8,
+            8,
+            1,
+            32,
+            8,
+            1,
+            8,
+            8,
+            8,
+            8,
+            16,
+            8,
+            8,
+            1,
+            CHECK_IN_SCHEDULE_LEN,
+            1,
+            8
+        ];
         // Destructure GameInfo struct
         let GameInfo {
             is_initialized,
             admin,
             spl_token_account,
+            operators,
+            token_program,
+            mint,
+            decimals,
+            payout_mode,
+            player_count,
+            total_awarded,
+            total_claimed,
+            max_direct_referrals,
+            distribution_count,
+            total_forfeited,
+            registration_gate,
+            allowlist_root,
+            registration_fee,
+            registration_fee_upline_percent,
+            total_registration_fees,
+            season_count,
+            stake_reward_rate,
+            total_staked,
+            reward_per_share,
+            last_stake_update,
+            quest_count,
+            check_in_schedule_len,
+            check_in_schedule,
+            transfer_fee_percent,
+            total_transfer_fees,
         } = self;
         // Since the sliced chunks are mutable, direct modify the chunks content will reflect in account data
         is_initialized_dst[0] = *is_initialized as u8;
@@ -73,6 +462,51 @@ impl Pack for GameInfo {
         admin_dst.copy_from_slice(admin.as_ref());
         // Convert spl_token_account from Pubkey struct to byte array, then copy it into spl_token_account_dst
         spl_token_account_dst.copy_from_slice(spl_token_account.as_ref());
+        for (operator, chunk) in operators
+            .iter()
+            .zip(operators_dst.chunks_exact_mut(Operator::LEN))
+        {
+            operator.pack_into_slice(chunk);
+        }
+        token_program_dst.copy_from_slice(token_program.as_ref());
+        mint_dst.copy_from_slice(mint.as_ref());
+        decimals_dst[0] = *decimals;
+        payout_mode_dst[0] = *payout_mode as u8;
+        player_count_dst.copy_from_slice(&player_count.to_le_bytes());
+        total_awarded_dst.copy_from_slice(&total_awarded.to_le_bytes());
+        total_claimed_dst.copy_from_slice(&total_claimed.to_le_bytes());
+        match max_direct_referrals {
+            COption::None => {
+                has_max_direct_referrals_dst.copy_from_slice(&[0, 0, 0, 0]);
+                max_direct_referrals_dst.copy_from_slice(&[0; 8]);
+            }
+            COption::Some(value) => {
+                has_max_direct_referrals_dst.copy_from_slice(&[1, 0, 0, 0]);
+                max_direct_referrals_dst.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        distribution_count_dst.copy_from_slice(&distribution_count.to_le_bytes());
+        total_forfeited_dst.copy_from_slice(&total_forfeited.to_le_bytes());
+        registration_gate_dst[0] = *registration_gate as u8;
+        allowlist_root_dst.copy_from_slice(allowlist_root);
+        registration_fee_dst.copy_from_slice(&registration_fee.to_le_bytes());
+        registration_fee_upline_percent_dst[0] = *registration_fee_upline_percent;
+        total_registration_fees_dst.copy_from_slice(&total_registration_fees.to_le_bytes());
+        season_count_dst.copy_from_slice(&season_count.to_le_bytes());
+        stake_reward_rate_dst.copy_from_slice(&stake_reward_rate.to_le_bytes());
+        total_staked_dst.copy_from_slice(&total_staked.to_le_bytes());
+        reward_per_share_dst.copy_from_slice(&reward_per_share.to_le_bytes());
+        last_stake_update_dst.copy_from_slice(&last_stake_update.to_le_bytes());
+        quest_count_dst.copy_from_slice(&quest_count.to_le_bytes());
+        check_in_schedule_len_dst[0] = *check_in_schedule_len;
+        for (reward, chunk) in check_in_schedule
+            .iter()
+            .zip(check_in_schedule_dst.chunks_exact_mut(8))
+        {
+            chunk.copy_from_slice(&reward.to_le_bytes());
+        }
+        transfer_fee_percent_dst[0] = *transfer_fee_percent;
+        total_transfer_fees_dst.copy_from_slice(&total_transfer_fees.to_le_bytes());
     }
 
     fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
@@ -96,13 +530,127 @@ impl Pack for GameInfo {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Operator {
+    // Account authorized to add reward, default pubkey when the slot is empty
+    pub key: Pubkey, // 32
+    // Maximum total reward the operator can add in the game mint, no limit when None
+    // Operators with a cap can't add reward in other mints
+    pub reward_cap: COption<u64>, // 4 + 8
+    // Total reward added by the operator so far in the game mint, including the wagers of the flips it is the house of
+    pub total_rewarded: u64, // 8
+}
+
+impl Operator {
+    pub fn is_active(&self) -> bool {
+        self.key != Pubkey::default()
+    }
+}
+
+impl Sealed for Operator {}
+
+impl Pack for Operator {
+    const LEN: usize = 32 + 4 + 8 + 8;
+    // Unpack operator slot (byte buffer) to Operator
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Operator::LEN];
+        let (key, has_reward_cap, reward_cap, total_rewarded) = array_refs![&src, 32, 4, 8, 8];
+        let reward_cap = match has_reward_cap {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(u64::from_le_bytes(*reward_cap)),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Operator {
+            key: Pubkey::new_from_array(*key),
+            reward_cap,
+            total_rewarded: u64::from_le_bytes(*total_rewarded),
+        })
+    }
+
+    // Pack Operator struct into operator slot (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Operator::LEN];
+        let (key_dst, has_reward_cap_dst, reward_cap_dst, total_rewarded_dst) =
+            mut_array_refs![dst, 32, 4, 8, 8];
+        let Operator {
+            key,
+            reward_cap,
+            total_rewarded,
+        } = self;
+        key_dst.copy_from_slice(key.as_ref());
+        match reward_cap {
+            COption::None => {
+                has_reward_cap_dst.copy_from_slice(&[0, 0, 0, 0]);
+                reward_cap_dst.copy_from_slice(&[0; 8]);
+            }
+            COption::Some(amount) => {
+                has_reward_cap_dst.copy_from_slice(&[1, 0, 0, 0]);
+                reward_cap_dst.copy_from_slice(&amount.to_le_bytes());
+            }
+        }
+        total_rewarded_dst.copy_from_slice(&total_rewarded.to_le_bytes());
+    }
+}
+
 #[derive(Debug)]
 pub struct Player {
-    pub is_initialized: bool,    // 1 byte
-    pub owner: Pubkey,           // 32 byte
+    pub is_initialized: bool, // 1 byte
+    pub owner: Pubkey,        // 32 byte
+    // Only a record of the player, the token or lamports stay in the vault until claimed
+    // Reward taken from it without a claim (wager, loot box price, transfer fee, forfeit) stay in the vault too
     pub reward_to_claim: u64,    // 8 byte, follow SPL token amount byte
     pub program_account: Pubkey, // 32 byte
     pub upline: COption<Pubkey>, // 4 + 32 byte  msg!("{:?}", size_of::<COption<Pubkey>>()) shows 36
+    // Lifetime statistics, reward_to_claim reset on claim but these never decrease
+    pub total_earned: u64,          // 8 byte, including referral reward
+    pub total_claimed: u64,         // 8 byte
+    pub total_referral_earned: u64, // 8 byte, reward received as upline
+    pub downline_count: u64,        // 8 byte, players registered with this player as upline
+    // Players currently having this player as upline, checked against the program account max_direct_referrals
+    // downline_count is the lifetime total instead
+    pub direct_referrals: u64, // 8 byte
+    // Unix timestamp of the registration, start of the grace period to change upline
+    pub registered_at: i64, // 8 byte
+    // Whether the player already used the one-time upline change
+    pub upline_changed: bool, // 1 byte
+    // Frozen by the admin, a frozen player cannot receive or claim reward
+    pub frozen: bool, // 1 byte
+    // Tokens locked by the player in the stake vault
+    pub staked_amount: u64, // 8 byte
+    // staked_amount * reward_per_share at the last update, the yield accrued before that is already credited
+    pub stake_reward_debt: u128, // 16 byte
+    // Consecutive UTC days the player checked in, ending at last_check_in_day
+    pub check_in_streak: u64, // 8 byte
+    // Days since the unix epoch of the last check-in
+    pub last_check_in_day: i64, // 8 byte
+    // Account allowed to sign Claim for the player, the reward still goes to the owner
+    pub claim_delegate: COption<Pubkey>, // 4 + 32 byte
+    // Token account receiving the claimed game token, locked to the owner associated token account at Register
+    // Default pubkey in a game paying reward in lamports
+    pub payout_token_account: Pubkey, // 32 byte
+    // Nonce of the last claim authorization relayed for the player
+    pub last_claim_nonce: u64, // 8 byte
+}
+
+impl Player {
+    // Yield accrued by the staked tokens since the last stake update, None on overflow
+    pub fn pending_stake_reward(&self, reward_per_share: u128) -> Option<u64> {
+        let accrued =
+            (self.staked_amount as u128).checked_mul(reward_per_share)? / STAKE_REWARD_PRECISION;
+        u64::try_from(accrued.checked_sub(self.stake_reward_debt)?).ok()
+    }
+
+    // Mark the yield accrued so far as settled, called after staked_amount changed
+    pub fn reset_stake_reward_debt(&mut self, reward_per_share: u128) -> Option<()> {
+        self.stake_reward_debt =
+            (self.staked_amount as u128).checked_mul(reward_per_share)? / STAKE_REWARD_PRECISION;
+        Some(())
+    }
+
+    // Owner and claim delegate can sign Claim
+    pub fn can_claim(&self, signer: &Pubkey) -> bool {
+        self.owner == *signer || self.claim_delegate == COption::Some(*signer)
+    }
 }
 
 impl Sealed for Player {}
@@ -114,14 +662,39 @@ impl IsInitialized for Player {
 }
 
 impl Pack for Player {
-    const LEN: usize = 1 + 32 + 8 + 32 + 4 + 32;
+    const LEN: usize =
+        1 + 32 + 8 + 32 + 4 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 8 + 16 + 8 + 8 + 4 + 32 + 32 + 8;
     // Unpack account data (byte buffer) to Player
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         // Shadow src argument, and use array_ref! to make src slice-able
         let src = array_ref![src, 0, Player::LEN];
         // Slice src based on struct property byte
-        let (is_initialized, owner, reward_to_claim, program_account, has_upline, upline) =
-            array_refs![&src, 1, 32, 8, 32, 4, 32];
+        let (
+            is_initialized,
+            owner,
+            reward_to_claim,
+            program_account,
+            has_upline,
+            upline,
+            total_earned,
+            total_claimed,
+            total_referral_earned,
+            downline_count,
+            direct_referrals,
+            registered_at,
+            upline_changed,
+            frozen,
+            staked_amount,
+            stake_reward_debt,
+            check_in_streak,
+            last_check_in_day,
+            has_claim_delegate,
+            claim_delegate,
+            payout_token_account,
+            last_claim_nonce,
+        ) = array_refs![
+            &src, 1, 32, 8, 32, 4, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 16, 8, 8, 4, 32, 32, 8
+        ];
         // Convert is_initialized from byte to bool
         let is_initialized = match is_initialized {
             // First element is 0
@@ -135,6 +708,21 @@ impl Pack for Player {
             [1, 0, 0, 0] => COption::Some(Pubkey::new_from_array(*upline)),
             _ => return Err(ProgramError::InvalidAccountData),
         };
+        let upline_changed = match upline_changed {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let frozen = match frozen {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let claim_delegate = match has_claim_delegate {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(Pubkey::new_from_array(*claim_delegate)),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
         // Return Player struct, which unpacked from account data
         Ok(Player {
             is_initialized,
@@ -143,6 +731,21 @@ impl Pack for Player {
             reward_to_claim: u64::from_le_bytes(*reward_to_claim),
             program_account: Pubkey::new_from_array(*program_account),
             upline,
+            total_earned: u64::from_le_bytes(*total_earned),
+            total_claimed: u64::from_le_bytes(*total_claimed),
+            total_referral_earned: u64::from_le_bytes(*total_referral_earned),
+            downline_count: u64::from_le_bytes(*downline_count),
+            direct_referrals: u64::from_le_bytes(*direct_referrals),
+            registered_at: i64::from_le_bytes(*registered_at),
+            upline_changed,
+            frozen,
+            staked_amount: u64::from_le_bytes(*staked_amount),
+            stake_reward_debt: u128::from_le_bytes(*stake_reward_debt),
+            check_in_streak: u64::from_le_bytes(*check_in_streak),
+            last_check_in_day: i64::from_le_bytes(*last_check_in_day),
+            claim_delegate,
+            payout_token_account: Pubkey::new_from_array(*payout_token_account),
+            last_claim_nonce: u64::from_le_bytes(*last_claim_nonce),
         })
     }
 
@@ -159,7 +762,25 @@ impl Pack for Player {
             program_account_dst,
             has_upline_dst,
             upline_dst,
-        ) = mut_array_refs![dst, 1, 32, 8, 32, 4, 32];
+            total_earned_dst,
+            total_claimed_dst,
+            total_referral_earned_dst,
+            downline_count_dst,
+            direct_referrals_dst,
+            registered_at_dst,
+            upline_changed_dst,
+            frozen_dst,
+            staked_amount_dst,
+            stake_reward_debt_dst,
+            check_in_streak_dst,
+            last_check_in_day_dst,
+            has_claim_delegate_dst,
+            claim_delegate_dst,
+            payout_token_account_dst,
+            last_claim_nonce_dst,
+        ) = mut_array_refs![
+            dst, 1, 32, 8, 32, 4, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 16, 8, 8, 4, 32, 32, 8
+        ];
         // Destructure Player struct
         let Player {
             is_initialized,
@@ -167,6 +788,21 @@ impl Pack for Player {
             reward_to_claim,
             program_account,
             upline,
+            total_earned,
+            total_claimed,
+            total_referral_earned,
+            downline_count,
+            direct_referrals,
+            registered_at,
+            upline_changed,
+            frozen,
+            staked_amount,
+            stake_reward_debt,
+            check_in_streak,
+            last_check_in_day,
+            claim_delegate,
+            payout_token_account,
+            last_claim_nonce,
         } = self;
         // Since the sliced chunks are mutable, direct modify the chunks content will reflect in account data
         is_initialized_dst[0] = *is_initialized as u8;
@@ -174,7 +810,7 @@ impl Pack for Player {
         owner_dst.copy_from_slice(owner.as_ref());
         // Convert reward_to_chain to byte array, then copy it into reward_to_claim_dst
         reward_to_claim_dst.copy_from_slice(&reward_to_claim.to_le_bytes());
-        program_account_dst.copy_from_slice(&program_account.as_ref());
+        program_account_dst.copy_from_slice(program_account.as_ref());
         match upline {
             COption::None => has_upline_dst.copy_from_slice(&[0, 0, 0, 0]),
             COption::Some(pubkey) => {
@@ -182,6 +818,30 @@ impl Pack for Player {
                 upline_dst.copy_from_slice(pubkey.as_ref());
             }
         }
+        total_earned_dst.copy_from_slice(&total_earned.to_le_bytes());
+        total_claimed_dst.copy_from_slice(&total_claimed.to_le_bytes());
+        total_referral_earned_dst.copy_from_slice(&total_referral_earned.to_le_bytes());
+        downline_count_dst.copy_from_slice(&downline_count.to_le_bytes());
+        direct_referrals_dst.copy_from_slice(&direct_referrals.to_le_bytes());
+        registered_at_dst.copy_from_slice(&registered_at.to_le_bytes());
+        upline_changed_dst[0] = *upline_changed as u8;
+        frozen_dst[0] = *frozen as u8;
+        staked_amount_dst.copy_from_slice(&staked_amount.to_le_bytes());
+        stake_reward_debt_dst.copy_from_slice(&stake_reward_debt.to_le_bytes());
+        check_in_streak_dst.copy_from_slice(&check_in_streak.to_le_bytes());
+        last_check_in_day_dst.copy_from_slice(&last_check_in_day.to_le_bytes());
+        match claim_delegate {
+            COption::None => {
+                has_claim_delegate_dst.copy_from_slice(&[0, 0, 0, 0]);
+                claim_delegate_dst.copy_from_slice(&[0; 32]);
+            }
+            COption::Some(pubkey) => {
+                has_claim_delegate_dst.copy_from_slice(&[1, 0, 0, 0]);
+                claim_delegate_dst.copy_from_slice(pubkey.as_ref());
+            }
+        }
+        payout_token_account_dst.copy_from_slice(payout_token_account.as_ref());
+        last_claim_nonce_dst.copy_from_slice(&last_claim_nonce.to_le_bytes());
     }
 
     fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
@@ -204,3 +864,1023 @@ impl Pack for Player {
         Self::unpack_from_slice(input)
     }
 }
+
+// Reward of a player in a mint other than the mint recorded during initialization
+// Stored in a PDA derived from the player program account and the mint
+#[derive(Debug)]
+pub struct RewardBalance {
+    pub is_initialized: bool, // 1
+    // The player program account owning the balance
+    pub player: Pubkey, // 32
+    // Mint of the reward vault
+    pub mint: Pubkey,         // 32
+    pub reward_to_claim: u64, // 8
+    // Lifetime statistics of the mint, same as the one in Player
+    pub total_earned: u64,          // 8
+    pub total_claimed: u64,         // 8
+    pub total_referral_earned: u64, // 8
+}
+
+impl Sealed for RewardBalance {}
+
+impl IsInitialized for RewardBalance {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RewardBalance {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8 + 8;
+    // Unpack account data (byte buffer) to RewardBalance
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, RewardBalance::LEN];
+        let (
+            is_initialized,
+            player,
+            mint,
+            reward_to_claim,
+            total_earned,
+            total_claimed,
+            total_referral_earned,
+        ) = array_refs![&src, 1, 32, 32, 8, 8, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(RewardBalance {
+            is_initialized,
+            player: Pubkey::new_from_array(*player),
+            mint: Pubkey::new_from_array(*mint),
+            reward_to_claim: u64::from_le_bytes(*reward_to_claim),
+            total_earned: u64::from_le_bytes(*total_earned),
+            total_claimed: u64::from_le_bytes(*total_claimed),
+            total_referral_earned: u64::from_le_bytes(*total_referral_earned),
+        })
+    }
+
+    // Pack RewardBalance struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, RewardBalance::LEN];
+        let (
+            is_initialized_dst,
+            player_dst,
+            mint_dst,
+            reward_to_claim_dst,
+            total_earned_dst,
+            total_claimed_dst,
+            total_referral_earned_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 8, 8, 8, 8];
+        let RewardBalance {
+            is_initialized,
+            player,
+            mint,
+            reward_to_claim,
+            total_earned,
+            total_claimed,
+            total_referral_earned,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        player_dst.copy_from_slice(player.as_ref());
+        mint_dst.copy_from_slice(mint.as_ref());
+        reward_to_claim_dst.copy_from_slice(&reward_to_claim.to_le_bytes());
+        total_earned_dst.copy_from_slice(&total_earned.to_le_bytes());
+        total_claimed_dst.copy_from_slice(&total_claimed.to_le_bytes());
+        total_referral_earned_dst.copy_from_slice(&total_referral_earned.to_le_bytes());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != RewardBalance::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Human readable referral code of a player
+// Stored in a PDA derived from the program account and the code, so a code is unique per game
+#[derive(Debug)]
+pub struct ReferralCode {
+    pub is_initialized: bool, // 1
+    // The player program account owning the code
+    pub player: Pubkey, // 32
+}
+
+impl Sealed for ReferralCode {}
+
+impl IsInitialized for ReferralCode {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReferralCode {
+    const LEN: usize = 1 + 32;
+    // Unpack account data (byte buffer) to ReferralCode
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, ReferralCode::LEN];
+        let (is_initialized, player) = array_refs![&src, 1, 32];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(ReferralCode {
+            is_initialized,
+            player: Pubkey::new_from_array(*player),
+        })
+    }
+
+    // Pack ReferralCode struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, ReferralCode::LEN];
+        let (is_initialized_dst, player_dst) = mut_array_refs![dst, 1, 32];
+        let ReferralCode {
+            is_initialized,
+            player,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        player_dst.copy_from_slice(player.as_ref());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != ReferralCode::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Bulk reward published as the Merkle root of (index, player program account, amount) leaves
+// Stored in a PDA derived from the program account and the distribution index
+#[derive(Debug)]
+pub struct Distribution {
+    pub is_initialized: bool, // 1
+    // The program account publishing the distribution
+    pub program_account: Pubkey, // 32
+    pub root: [u8; 32],          // 32
+    // Maximum total amount can be claimed from the distribution
+    pub total: u64, // 8
+    // Total amount claimed so far
+    pub claimed: u64, // 8
+}
+
+impl Sealed for Distribution {}
+
+impl IsInitialized for Distribution {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Distribution {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8;
+    // Unpack account data (byte buffer) to Distribution
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Distribution::LEN];
+        let (is_initialized, program_account, root, total, claimed) =
+            array_refs![&src, 1, 32, 32, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Distribution {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            root: *root,
+            total: u64::from_le_bytes(*total),
+            claimed: u64::from_le_bytes(*claimed),
+        })
+    }
+
+    // Pack Distribution struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Distribution::LEN];
+        let (is_initialized_dst, program_account_dst, root_dst, total_dst, claimed_dst) =
+            mut_array_refs![dst, 1, 32, 32, 8, 8];
+        let Distribution {
+            is_initialized,
+            program_account,
+            root,
+            total,
+            claimed,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        root_dst.copy_from_slice(root);
+        total_dst.copy_from_slice(&total.to_le_bytes());
+        claimed_dst.copy_from_slice(&claimed.to_le_bytes());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Distribution::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Competition round of a game, stored in a PDA derived from the program account and the season index
+#[derive(Debug)]
+pub struct Season {
+    pub is_initialized: bool, // 1
+    // The program account running the season
+    pub program_account: Pubkey, // 32
+    // Unix timestamp range accepting scores, start inclusive and end exclusive
+    pub start: i64, // 8
+    pub end: i64,   // 8
+    // Prizes are awarded once, after the end
+    pub finalized: bool, // 1
+    // Prize of each rank, in the mint recorded during initialization (or lamports)
+    pub prizes: [u64; SEASON_LEADERBOARD_LEN], // 8 * SEASON_LEADERBOARD_LEN
+    // Highest score first, empty entries at the end
+    pub leaderboard: [LeaderboardEntry; SEASON_LEADERBOARD_LEN], // 40 * SEASON_LEADERBOARD_LEN
+}
+
+impl Season {
+    // Record the new score of the player, return the rank if the player is on the leaderboard
+    // Score only grows, so the entry only moves up, and a tie keep the earlier entry ahead
+    pub fn update_leaderboard(&mut self, player: &Pubkey, score: u64) -> Option<usize> {
+        let mut rank = match self
+            .leaderboard
+            .iter()
+            .position(|entry| entry.player == *player)
+        {
+            Some(rank) => rank,
+            None => {
+                let last = SEASON_LEADERBOARD_LEN - 1;
+                if self.leaderboard[last].is_active() && self.leaderboard[last].score >= score {
+                    return None;
+                }
+                last
+            }
+        };
+        self.leaderboard[rank] = LeaderboardEntry {
+            player: *player,
+            score,
+        };
+        while rank > 0
+            && (!self.leaderboard[rank - 1].is_active() || self.leaderboard[rank - 1].score < score)
+        {
+            self.leaderboard.swap(rank - 1, rank);
+            rank -= 1;
+        }
+        Some(rank)
+    }
+}
+
+impl Sealed for Season {}
+
+impl IsInitialized for Season {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Season {
+    const LEN: usize = 1 + 32 + 8 + 8 + 1 + PRIZES_LEN + LEADERBOARD_LEN;
+    // Unpack account data (byte buffer) to Season
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Season::LEN];
+        let (is_initialized, program_account, start, end, finalized, prizes_src, leaderboard_src) =
+            array_refs![&src, 1, 32, 8, 8, 1, PRIZES_LEN, LEADERBOARD_LEN];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let finalized = match finalized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mut prizes = [0; SEASON_LEADERBOARD_LEN];
+        for (prize, chunk) in prizes.iter_mut().zip(prizes_src.chunks_exact(8)) {
+            *prize = u64::from_le_bytes(
+                chunk
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            );
+        }
+        let mut leaderboard = [LeaderboardEntry::default(); SEASON_LEADERBOARD_LEN];
+        // Each chunk of the leaderboard byte is one rank
+        for (entry, chunk) in leaderboard
+            .iter_mut()
+            .zip(leaderboard_src.chunks_exact(LeaderboardEntry::LEN))
+        {
+            *entry = LeaderboardEntry::unpack_from_slice(chunk)?;
+        }
+        Ok(Season {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            start: i64::from_le_bytes(*start),
+            end: i64::from_le_bytes(*end),
+            finalized,
+            prizes,
+            leaderboard,
+        })
+    }
+
+    // Pack Season struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Season::LEN];
+        let (
+            is_initialized_dst,
+            program_account_dst,
+            start_dst,
+            end_dst,
+            finalized_dst,
+            prizes_dst,
+            leaderboard_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 8, 1, PRIZES_LEN, LEADERBOARD_LEN];
+        let Season {
+            is_initialized,
+            program_account,
+            start,
+            end,
+            finalized,
+            prizes,
+            leaderboard,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        start_dst.copy_from_slice(&start.to_le_bytes());
+        end_dst.copy_from_slice(&end.to_le_bytes());
+        finalized_dst[0] = *finalized as u8;
+        for (prize, chunk) in prizes.iter().zip(prizes_dst.chunks_exact_mut(8)) {
+            chunk.copy_from_slice(&prize.to_le_bytes());
+        }
+        for (entry, chunk) in leaderboard
+            .iter()
+            .zip(leaderboard_dst.chunks_exact_mut(LeaderboardEntry::LEN))
+        {
+            entry.pack_into_slice(chunk);
+        }
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Season::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LeaderboardEntry {
+    // The player program account, default pubkey when the rank is empty
+    pub player: Pubkey, // 32
+    pub score: u64,     // 8
+}
+
+impl LeaderboardEntry {
+    pub fn is_active(&self) -> bool {
+        self.player != Pubkey::default()
+    }
+}
+
+impl Sealed for LeaderboardEntry {}
+
+impl Pack for LeaderboardEntry {
+    const LEN: usize = 32 + 8;
+    // Unpack leaderboard rank (byte buffer) to LeaderboardEntry
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, LeaderboardEntry::LEN];
+        let (player, score) = array_refs![&src, 32, 8];
+        Ok(LeaderboardEntry {
+            player: Pubkey::new_from_array(*player),
+            score: u64::from_le_bytes(*score),
+        })
+    }
+
+    // Pack LeaderboardEntry struct into leaderboard rank (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, LeaderboardEntry::LEN];
+        let (player_dst, score_dst) = mut_array_refs![dst, 32, 8];
+        player_dst.copy_from_slice(self.player.as_ref());
+        score_dst.copy_from_slice(&self.score.to_le_bytes());
+    }
+}
+
+// Score of a player in a season, stored in a PDA derived from the season and the player program account
+#[derive(Debug)]
+pub struct SeasonScore {
+    pub is_initialized: bool, // 1
+    pub season: Pubkey,       // 32
+    // The player program account
+    pub player: Pubkey, // 32
+    pub score: u64,     // 8
+}
+
+impl Sealed for SeasonScore {}
+
+impl IsInitialized for SeasonScore {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SeasonScore {
+    const LEN: usize = 1 + 32 + 32 + 8;
+    // Unpack account data (byte buffer) to SeasonScore
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, SeasonScore::LEN];
+        let (is_initialized, season, player, score) = array_refs![&src, 1, 32, 32, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(SeasonScore {
+            is_initialized,
+            season: Pubkey::new_from_array(*season),
+            player: Pubkey::new_from_array(*player),
+            score: u64::from_le_bytes(*score),
+        })
+    }
+
+    // Pack SeasonScore struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, SeasonScore::LEN];
+        let (is_initialized_dst, season_dst, player_dst, score_dst) =
+            mut_array_refs![dst, 1, 32, 32, 8];
+        let SeasonScore {
+            is_initialized,
+            season,
+            player,
+            score,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        season_dst.copy_from_slice(season.as_ref());
+        player_dst.copy_from_slice(player.as_ref());
+        score_dst.copy_from_slice(&score.to_le_bytes());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != SeasonScore::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Quest defined by the admin, completed for a player by the admin or an operator
+// Stored in a PDA derived from the program account and the quest id
+#[derive(Debug)]
+pub struct Quest {
+    pub is_initialized: bool, // 1
+    // The program account defining the quest
+    pub program_account: Pubkey, // 32
+    // Reward credited on completion, in the mint recorded during initialization (or lamports), including upline commission
+    pub reward: u64, // 8
+    // Maximum number of players can complete the quest, no limit when None
+    pub max_completions: COption<u64>, // 4 + 8
+    // Unix timestamp after which the quest can't be completed, never expire when None
+    pub expiry: COption<i64>, // 4 + 8
+    // Number of players completed the quest so far
+    pub completions: u64, // 8
+}
+
+impl Sealed for Quest {}
+
+impl IsInitialized for Quest {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Quest {
+    const LEN: usize = 1 + 32 + 8 + 4 + 8 + 4 + 8 + 8;
+    // Unpack account data (byte buffer) to Quest
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Quest::LEN];
+        let (
+            is_initialized,
+            program_account,
+            reward,
+            has_max_completions,
+            max_completions,
+            has_expiry,
+            expiry,
+            completions,
+        ) = array_refs![&src, 1, 32, 8, 4, 8, 4, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let max_completions = match has_max_completions {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(u64::from_le_bytes(*max_completions)),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let expiry = match has_expiry {
+            [0, 0, 0, 0] => COption::None,
+            [1, 0, 0, 0] => COption::Some(i64::from_le_bytes(*expiry)),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Quest {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            reward: u64::from_le_bytes(*reward),
+            max_completions,
+            expiry,
+            completions: u64::from_le_bytes(*completions),
+        })
+    }
+
+    // Pack Quest struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Quest::LEN];
+        let (
+            is_initialized_dst,
+            program_account_dst,
+            reward_dst,
+            has_max_completions_dst,
+            max_completions_dst,
+            has_expiry_dst,
+            expiry_dst,
+            completions_dst,
+        ) = mut_array_refs![dst, 1, 32, 8, 4, 8, 4, 8, 8];
+        let Quest {
+            is_initialized,
+            program_account,
+            reward,
+            max_completions,
+            expiry,
+            completions,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        reward_dst.copy_from_slice(&reward.to_le_bytes());
+        match max_completions {
+            COption::None => {
+                has_max_completions_dst.copy_from_slice(&[0, 0, 0, 0]);
+                max_completions_dst.copy_from_slice(&[0; 8]);
+            }
+            COption::Some(value) => {
+                has_max_completions_dst.copy_from_slice(&[1, 0, 0, 0]);
+                max_completions_dst.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        match expiry {
+            COption::None => {
+                has_expiry_dst.copy_from_slice(&[0, 0, 0, 0]);
+                expiry_dst.copy_from_slice(&[0; 8]);
+            }
+            COption::Some(value) => {
+                has_expiry_dst.copy_from_slice(&[1, 0, 0, 0]);
+                expiry_dst.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        completions_dst.copy_from_slice(&completions.to_le_bytes());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Quest::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Coin flip between a player and the house (admin or operator), settled on the player reward
+// Stored in a PDA derived from the player program account, reused once settled
+#[derive(Debug)]
+pub struct Flip {
+    pub is_initialized: bool,    // 1
+    pub program_account: Pubkey, // 32
+    pub player: Pubkey,          // 32, the player program account
+    pub status: FlipStatus,      // 1
+    // Taken from the player reward on commit
+    pub wager: u64,                  // 8
+    pub player_commitment: [u8; 32], // 32, hash of the player secret
+    // The admin or operator committed as the house
+    pub house: Pubkey,              // 32
+    pub house_commitment: [u8; 32], // 32, hash of the house secret
+    pub player_secret: [u8; 32],    // 32
+    pub house_secret: [u8; 32],     // 32
+    pub player_revealed: bool,      // 1
+    pub house_revealed: bool,       // 1
+    // Unix timestamp of the last commit, the timeout start from it
+    pub committed_at: i64, // 8
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlipStatus {
+    // No flip in progress
+    Settled,
+    // Waiting for the house commitment
+    PlayerCommitted,
+    // Waiting for both reveals
+    HouseCommitted,
+}
+
+impl Sealed for Flip {}
+
+impl IsInitialized for Flip {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Flip {
+    const LEN: usize = 1 + 32 + 32 + 1 + 8 + 32 + 32 + 32 + 32 + 32 + 1 + 1 + 8;
+    // Unpack account data (byte buffer) to Flip
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Flip::LEN];
+        let (
+            is_initialized,
+            program_account,
+            player,
+            status,
+            wager,
+            player_commitment,
+            house,
+            house_commitment,
+            player_secret,
+            house_secret,
+            player_revealed,
+            house_revealed,
+            committed_at,
+        ) = array_refs![&src, 1, 32, 32, 1, 8, 32, 32, 32, 32, 32, 1, 1, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let status = match status {
+            [0] => FlipStatus::Settled,
+            [1] => FlipStatus::PlayerCommitted,
+            [2] => FlipStatus::HouseCommitted,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let player_revealed = match player_revealed {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let house_revealed = match house_revealed {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(Flip {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            player: Pubkey::new_from_array(*player),
+            status,
+            wager: u64::from_le_bytes(*wager),
+            player_commitment: *player_commitment,
+            house: Pubkey::new_from_array(*house),
+            house_commitment: *house_commitment,
+            player_secret: *player_secret,
+            house_secret: *house_secret,
+            player_revealed,
+            house_revealed,
+            committed_at: i64::from_le_bytes(*committed_at),
+        })
+    }
+
+    // Pack Flip struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Flip::LEN];
+        let (
+            is_initialized_dst,
+            program_account_dst,
+            player_dst,
+            status_dst,
+            wager_dst,
+            player_commitment_dst,
+            house_dst,
+            house_commitment_dst,
+            player_secret_dst,
+            house_secret_dst,
+            player_revealed_dst,
+            house_revealed_dst,
+            committed_at_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 1, 8, 32, 32, 32, 32, 32, 1, 1, 8];
+        let Flip {
+            is_initialized,
+            program_account,
+            player,
+            status,
+            wager,
+            player_commitment,
+            house,
+            house_commitment,
+            player_secret,
+            house_secret,
+            player_revealed,
+            house_revealed,
+            committed_at,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        player_dst.copy_from_slice(player.as_ref());
+        status_dst[0] = *status as u8;
+        wager_dst.copy_from_slice(&wager.to_le_bytes());
+        player_commitment_dst.copy_from_slice(player_commitment);
+        house_dst.copy_from_slice(house.as_ref());
+        house_commitment_dst.copy_from_slice(house_commitment);
+        player_secret_dst.copy_from_slice(player_secret);
+        house_secret_dst.copy_from_slice(house_secret);
+        player_revealed_dst[0] = *player_revealed as u8;
+        house_revealed_dst[0] = *house_revealed as u8;
+        committed_at_dst.copy_from_slice(&committed_at.to_le_bytes());
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Flip::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+// Weighted reward tiers of the loot boxes of a game, set by the admin
+// Stored in a PDA derived from the program account
+#[derive(Debug)]
+pub struct LootTable {
+    pub is_initialized: bool,    // 1
+    pub program_account: Pubkey, // 32
+    // Taken from the player reward when a box is opened
+    pub price: u64, // 8
+    // Only the first tier_count tiers are used
+    pub tier_count: u8,                    // 1
+    pub tiers: [LootTier; MAX_LOOT_TIERS], // 12 * MAX_LOOT_TIERS
+}
+
+impl LootTable {
+    pub fn total_weight(&self) -> u64 {
+        self.tiers[..self.tier_count as usize]
+            .iter()
+            .map(|tier| tier.weight as u64)
+            .sum()
+    }
+}
+
+impl Sealed for LootTable {}
+
+impl IsInitialized for LootTable {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for LootTable {
+    const LEN: usize = 1 + 32 + 8 + 1 + LOOT_TIERS_LEN;
+    // Unpack account data (byte buffer) to LootTable
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, LootTable::LEN];
+        let (is_initialized, program_account, price, tier_count, tiers_src) =
+            array_refs![&src, 1, 32, 8, 1, LOOT_TIERS_LEN];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if tier_count[0] as usize > MAX_LOOT_TIERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut tiers = [LootTier::default(); MAX_LOOT_TIERS];
+        for (tier, chunk) in tiers.iter_mut().zip(tiers_src.chunks_exact(LootTier::LEN)) {
+            *tier = LootTier::unpack_from_slice(chunk)?;
+        }
+        Ok(LootTable {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            price: u64::from_le_bytes(*price),
+            tier_count: tier_count[0],
+            tiers,
+        })
+    }
+
+    // Pack LootTable struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, LootTable::LEN];
+        let (is_initialized_dst, program_account_dst, price_dst, tier_count_dst, tiers_dst) =
+            mut_array_refs![dst, 1, 32, 8, 1, LOOT_TIERS_LEN];
+        let LootTable {
+            is_initialized,
+            program_account,
+            price,
+            tier_count,
+            tiers,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        price_dst.copy_from_slice(&price.to_le_bytes());
+        tier_count_dst[0] = *tier_count;
+        for (tier, chunk) in tiers.iter().zip(tiers_dst.chunks_exact_mut(LootTier::LEN)) {
+            tier.pack_into_slice(chunk);
+        }
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != LootTable::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LootTier {
+    // Relative chance of the tier
+    pub weight: u32, // 4
+    // Credited to the player reward
+    pub reward: u64, // 8
+}
+
+impl Sealed for LootTier {}
+
+impl Pack for LootTier {
+    const LEN: usize = 4 + 8;
+    // Unpack loot table tier (byte buffer) to LootTier
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, LootTier::LEN];
+        let (weight, reward) = array_refs![&src, 4, 8];
+        Ok(LootTier {
+            weight: u32::from_le_bytes(*weight),
+            reward: u64::from_le_bytes(*reward),
+        })
+    }
+
+    // Pack LootTier struct into loot table tier (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, LootTier::LEN];
+        let (weight_dst, reward_dst) = mut_array_refs![dst, 4, 8];
+        weight_dst.copy_from_slice(&self.weight.to_le_bytes());
+        reward_dst.copy_from_slice(&self.reward.to_le_bytes());
+    }
+}
+
+// Loot box opened by a player, the tier is decided by the hash of a slot after the opening
+// Stored in a PDA derived from the player program account, reused once revealed
+#[derive(Debug)]
+pub struct LootBox {
+    pub is_initialized: bool,    // 1
+    pub program_account: Pubkey, // 32
+    pub player: Pubkey,          // 32, the player program account
+    // Waiting for reveal
+    pub pending: bool, // 1
+    // Taken from the player reward on open
+    pub price: u64, // 8
+    // Slot whose hash decides the tier, unknown to anyone when the box is opened
+    pub reveal_slot: u64, // 8
+    // Tiers of the loot table at the opening, the admin can change the table before the reveal
+    pub tier_count: u8,                    // 1
+    pub tiers: [LootTier; MAX_LOOT_TIERS], // 12 * MAX_LOOT_TIERS
+}
+
+impl LootBox {
+    // Pick a tier with a probability proportional to its weight, roll is a uniform random number
+    pub fn pick_tier(&self, roll: u64) -> Option<usize> {
+        let tiers = &self.tiers[..self.tier_count as usize];
+        let total_weight: u64 = tiers.iter().map(|tier| tier.weight as u64).sum();
+        if total_weight == 0 {
+            return None;
+        }
+        let mut point = roll % total_weight;
+        for (index, tier) in tiers.iter().enumerate() {
+            if point < tier.weight as u64 {
+                return Some(index);
+            }
+            point -= tier.weight as u64;
+        }
+        None
+    }
+}
+
+impl Sealed for LootBox {}
+
+impl IsInitialized for LootBox {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for LootBox {
+    const LEN: usize = 1 + 32 + 32 + 1 + 8 + 8 + 1 + LOOT_TIERS_LEN;
+    // Unpack account data (byte buffer) to LootBox
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, LootBox::LEN];
+        let (
+            is_initialized,
+            program_account,
+            player,
+            pending,
+            price,
+            reveal_slot,
+            tier_count,
+            tiers_src,
+        ) = array_refs![&src, 1, 32, 32, 1, 8, 8, 1, LOOT_TIERS_LEN];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let pending = match pending {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if tier_count[0] as usize > MAX_LOOT_TIERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut tiers = [LootTier::default(); MAX_LOOT_TIERS];
+        for (tier, chunk) in tiers.iter_mut().zip(tiers_src.chunks_exact(LootTier::LEN)) {
+            *tier = LootTier::unpack_from_slice(chunk)?;
+        }
+        Ok(LootBox {
+            is_initialized,
+            program_account: Pubkey::new_from_array(*program_account),
+            player: Pubkey::new_from_array(*player),
+            pending,
+            price: u64::from_le_bytes(*price),
+            reveal_slot: u64::from_le_bytes(*reveal_slot),
+            tier_count: tier_count[0],
+            tiers,
+        })
+    }
+
+    // Pack LootBox struct into account data (byte buffer)
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, LootBox::LEN];
+        let (
+            is_initialized_dst,
+            program_account_dst,
+            player_dst,
+            pending_dst,
+            price_dst,
+            reveal_slot_dst,
+            tier_count_dst,
+            tiers_dst,
+        ) = mut_array_refs![dst, 1, 32, 32, 1, 8, 8, 1, LOOT_TIERS_LEN];
+        let LootBox {
+            is_initialized,
+            program_account,
+            player,
+            pending,
+            price,
+            reveal_slot,
+            tier_count,
+            tiers,
+        } = self;
+        is_initialized_dst[0] = *is_initialized as u8;
+        program_account_dst.copy_from_slice(program_account.as_ref());
+        player_dst.copy_from_slice(player.as_ref());
+        pending_dst[0] = *pending as u8;
+        price_dst.copy_from_slice(&price.to_le_bytes());
+        reveal_slot_dst.copy_from_slice(&reveal_slot.to_le_bytes());
+        tier_count_dst[0] = *tier_count;
+        for (tier, chunk) in tiers.iter().zip(tiers_dst.chunks_exact_mut(LootTier::LEN)) {
+            tier.pack_into_slice(chunk);
+        }
+    }
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != LootBox::LEN {
+            msg!(
+                "Invalid account data storage, required size = {}",
+                Self::LEN
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+}
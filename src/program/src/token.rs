@@ -0,0 +1,152 @@
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+// spl-token 3.x instruction builders reject any program id other than the legacy token program.
+// Token-2022 keeps the same instruction and base state layout, so the instruction is built for the legacy program, then retarget to the chosen token program.
+pub mod spl_token_2022 {
+    solana_program::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+pub mod spl_associated_token_account {
+    solana_program::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+// Byte right after the base account / mint state, which Token-2022 use to tag the extended account
+const ACCOUNT_TYPE_INDEX: usize = spl_token::state::Account::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+// Either the legacy token program or Token-2022
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    spl_token::check_id(program_id) || spl_token_2022::check_id(program_id)
+}
+
+pub fn set_authority(
+    token_program_id: &Pubkey,
+    owned_pubkey: &Pubkey,
+    new_authority_pubkey: Option<&Pubkey>,
+    authority_type: spl_token::instruction::AuthorityType,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = spl_token::instruction::set_authority(
+        &spl_token::id(),
+        owned_pubkey,
+        new_authority_pubkey,
+        authority_type,
+        owner_pubkey,
+        &[owner_pubkey],
+    )?;
+    instruction.program_id = *token_program_id;
+    Ok(instruction)
+}
+
+pub fn initialize_account(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        account_pubkey,
+        mint_pubkey,
+        owner_pubkey,
+    )?;
+    instruction.program_id = *token_program_id;
+    Ok(instruction)
+}
+
+pub fn transfer_checked(
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = spl_token::instruction::transfer_checked(
+        &spl_token::id(),
+        source_pubkey,
+        mint_pubkey,
+        destination_pubkey,
+        authority_pubkey,
+        &[authority_pubkey],
+        amount,
+        decimals,
+    )?;
+    instruction.program_id = *token_program_id;
+    Ok(instruction)
+}
+
+// Derive the associated token account of the wallet, token program is part of the seed so Token-2022 mint get a different address
+pub fn get_associated_token_address(
+    wallet_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            wallet_pubkey.as_ref(),
+            token_program_id.as_ref(),
+            mint_pubkey.as_ref(),
+        ],
+        &spl_associated_token_account::id(),
+    )
+    .0
+}
+
+// Instruction data of Create is empty, the rent sysvar is only required by older version of the associated token account program
+pub fn create_associated_token_account(
+    funding_pubkey: &Pubkey,
+    wallet_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: spl_associated_token_account::id(),
+        accounts: vec![
+            AccountMeta::new(*funding_pubkey, true),
+            AccountMeta::new(
+                get_associated_token_address(wallet_pubkey, mint_pubkey, token_program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*wallet_pubkey, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: vec![],
+    }
+}
+
+// Unpack the base token account state, ignoring Token-2022 extensions after it
+pub fn unpack_account(data: &[u8]) -> Result<spl_token::state::Account, ProgramError> {
+    check_account_type(data, spl_token::state::Account::LEN, ACCOUNT_TYPE_ACCOUNT)?;
+    spl_token::state::Account::unpack(&data[..spl_token::state::Account::LEN])
+}
+
+// Unpack the base mint state, ignoring Token-2022 extensions after it
+pub fn unpack_mint(data: &[u8]) -> Result<spl_token::state::Mint, ProgramError> {
+    check_account_type(data, spl_token::state::Mint::LEN, ACCOUNT_TYPE_MINT)?;
+    spl_token::state::Mint::unpack(&data[..spl_token::state::Mint::LEN])
+}
+
+fn check_account_type(data: &[u8], base_len: usize, account_type: u8) -> Result<(), ProgramError> {
+    if data.len() == base_len {
+        return Ok(());
+    }
+    // Extended Token-2022 account, the mint is padded up to the account size before the type byte
+    if data.len() > ACCOUNT_TYPE_INDEX && data[ACCOUNT_TYPE_INDEX] == account_type {
+        return Ok(());
+    }
+    msg!("Invalid token account data, size = {}", data.len());
+    Err(ProgramError::InvalidAccountData)
+}
@@ -1,29 +1,55 @@
+// Instructions creating a PDA, or an associated token account, resize account data by CPI which is only
+// supported in the BPF VM. Their tests either preload the account with preload_program_owned_account,
+// or are gated behind the test-bpf feature with the pure logic covered by plain unit tests
 #![allow(unused_variables)]
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::single_match,
+    clippy::too_many_arguments
+)]
+use borsh::BorshSerialize;
 use learn_solana::{
     entrypoint::main,
-    state::{GameInfo, Player},
+    error::GameError,
+    event::{decode_events, GameEvent},
+    instruction::{ClaimAuthorization, Voucher},
+    merkle,
+    slot_hash::{find_slot_hash, SlotHash},
+    state::{
+        Distribution, Flip, FlipStatus, GameInfo, LootBox, LootTable, LootTier, PayoutMode, Player,
+        Quest, ReferralCode, RegistrationGate, RewardBalance, Season, SeasonScore,
+        DISTRIBUTION_BITMAP_BITS, DISTRIBUTION_BITMAP_LEN, QUEST_BITMAP_LEN,
+        SEASON_LEADERBOARD_LEN, STAKE_REWARD_PRECISION, VOUCHER_BITMAP_BITS, VOUCHER_BITMAP_LEN,
+    },
+    token::{
+        create_associated_token_account, get_associated_token_address, spl_associated_token_account,
+    },
 };
 use solana_program::{
+    account_info::AccountInfo,
     hash::Hash,
-    instruction::{AccountMeta, Instruction},
+    instruction::{AccountMeta, Instruction, InstructionError},
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
+    system_program, sysvar,
 };
 use solana_program_test::*;
 use solana_sdk::{
+    account::AccountSharedData,
     signature::{Keypair, Signer},
     system_instruction,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 use spl_token::{
     self,
-    instruction::{initialize_account, initialize_mint, mint_to},
+    instruction::{freeze_account, initialize_account, initialize_mint, mint_to},
+    state::AccountState,
 };
 
 #[tokio::test]
-async fn claim_reward() {
+async fn relay_claim() {
     let (
         mint_account_keypair,
         admin_account_keypair,
@@ -44,6 +70,7 @@ async fn claim_reward() {
         &admin_account_keypair,
         &program_account_keypair,
         &token_account_keypair,
+        &mint_account_keypair.pubkey(),
         &payer,
         program_id,
         recent_blockhash,
@@ -63,94 +90,180 @@ async fn claim_reward() {
         .process_transaction(create_player_account_transaction)
         .await
         .unwrap();
-
-    // Register player one
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_one_holder_keypair,
-        &player_one_account_keypair,
+    for (player_holder_keypair, player_account_keypair) in [
+        (&player_one_holder_keypair, &player_one_account_keypair),
+        (&player_two_holder_keypair, &player_two_account_keypair),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            None,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
+        &player_one_account_keypair,
         None,
         program_id,
-        recent_blockhash,
-    );
-    banks_client.process_transaction(transaction).await.unwrap();
-
-    // Register player two, with player one as upline
-    let transaction = build_register_player_transaction(
+        100,
         &payer,
-        &player_two_holder_keypair,
-        &player_two_account_keypair,
-        &program_account_keypair,
-        Some(&player_one_account_keypair),
-        program_id,
         recent_blockhash,
     );
     banks_client.process_transaction(transaction).await.unwrap();
 
-    // Test add reward to player two with admin
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
-        &program_account_keypair,
-        &player_two_account_keypair,
-        Some(&player_one_account_keypair),
-        program_id,
-        100,
-        &payer,
-        recent_blockhash,
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let player_one_associated_token_account = get_associated_token_address(
+        &player_one_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
     );
-    banks_client
-        .process_transaction(add_reward_transaction)
-        .await
-        .unwrap();
-
-    // Test add reward to player one
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
-        &program_account_keypair,
-        &player_one_account_keypair,
-        None,
-        program_id,
-        100,
-        &payer,
-        recent_blockhash,
+    let player_two_associated_token_account = get_associated_token_address(
+        &player_two_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
     );
-    banks_client
-        .process_transaction(add_reward_transaction)
+
+    // Payout token account is locked to the associated token account at Register
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
         .await
+        .unwrap()
         .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(
+        player_one_state.payout_token_account,
+        player_one_associated_token_account
+    );
 
-    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
-    // Test claim other player reward
-    let transaction = build_claim_reward_transaction(
+    let authorization = ClaimAuthorization {
+        program_account: program_account_keypair.pubkey(),
+        player: player_one_account_keypair.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+
+    // Test relay claim before the payout token account exists
+    let transaction = build_relay_claim_transaction(
         &player_one_holder_keypair,
         &program_account_keypair,
-        &player_two_account_keypair, // Claim player two reward using player one signature
+        &player_one_account_keypair,
         &token_account_keypair,
         pda,
-        &player_one_token_account_keypair,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        &ClaimAuthorization {
+            nonce: 2,
+            ..authorization.clone()
+        },
         program_id,
         &payer,
         recent_blockhash,
     );
     let result = banks_client.process_transaction(transaction).await;
-    match result {
-        Ok(()) => {}
-        Err(e) => {
-            assert_eq!(e.to_string().contains("missing required signature"), true);
-        }
-    };
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::InvalidPlayerTokenAccount as u32)
+        )
+    );
+    // End
 
-    // Test player one claim reward
-    let transaction = build_claim_reward_transaction(
+    // The relayer is a third account paying the fee, without any player signature
+    let relayer_keypair = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            system_instruction::transfer(&payer.pubkey(), &relayer_keypair.pubkey(), 1_000_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test relay claim to another token account, signed by another player, for another player, and expired
+    for (signer_keypair, player_token_account, authorization, error) in [
+        (
+            &player_one_holder_keypair,
+            &player_two_associated_token_account,
+            authorization.clone(),
+            GameError::InvalidClaimDestination,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_one_associated_token_account,
+            authorization.clone(),
+            GameError::InvalidClaimAuthorization,
+        ),
+        (
+            &player_one_holder_keypair,
+            &player_one_associated_token_account,
+            ClaimAuthorization {
+                player: player_two_account_keypair.pubkey(),
+                ..authorization.clone()
+            },
+            GameError::InvalidClaimAuthorization,
+        ),
+        (
+            &player_one_holder_keypair,
+            &player_one_associated_token_account,
+            ClaimAuthorization {
+                expiry: 0,
+                ..authorization.clone()
+            },
+            GameError::ClaimAuthorizationExpired,
+        ),
+    ] {
+        let transaction = build_relay_claim_transaction(
+            signer_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            &token_account_keypair,
+            pda,
+            player_token_account,
+            &mint_account_keypair.pubkey(),
+            &authorization,
+            program_id,
+            &payer,
+            recent_blockhash,
+        );
+        let result = banks_client.process_transaction(transaction).await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(1, InstructionError::Custom(error as u32))
+        );
+    }
+    // End
+
+    // Test relay claim
+    let transaction = build_relay_claim_transaction(
         &player_one_holder_keypair,
         &program_account_keypair,
         &player_one_account_keypair,
         &token_account_keypair,
         pda,
-        &player_one_token_account_keypair,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        &authorization,
         program_id,
-        &payer,
+        &relayer_keypair,
         recent_blockhash,
     );
     banks_client.process_transaction(transaction).await.unwrap();
@@ -158,31 +271,61 @@ async fn claim_reward() {
     let player_one_account = banks_client
         .get_account(player_one_account_keypair.pubkey())
         .await
+        .unwrap()
         .unwrap();
-    match player_one_account {
-        Some(account) => {
-            let player_one_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_one_state.reward_to_claim, 0);
-        }
-        _ => {} // Unreachable
-    };
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    assert_eq!(player_one_state.total_claimed, 100);
+    assert_eq!(player_one_state.last_claim_nonce, 1);
 
     let player_one_token_account = banks_client
-        .get_account(player_one_token_account_keypair.pubkey())
+        .get_account(player_one_associated_token_account)
         .await
+        .unwrap()
         .unwrap();
-    match player_one_token_account {
-        Some(account) => {
-            let player_one_token_account_state =
-                spl_token::state::Account::unpack(&account.data).unwrap();
-            assert_eq!(player_one_token_account_state.amount, 110); // 100 + 10% from player two
-        }
-        _ => {}
-    }
+    let player_one_token_account_state =
+        spl_token::state::Account::unpack(&player_one_token_account.data).unwrap();
+    assert_eq!(player_one_token_account_state.amount, 100);
+
+    // Test relay the same authorization again
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        50,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_relay_claim_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        &authorization,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::ClaimAuthorizationUsed as u32)
+        )
+    );
+    // End
 }
 
 #[tokio::test]
-async fn add_reward() {
+async fn claim_delegate() {
     let (
         mint_account_keypair,
         admin_account_keypair,
@@ -203,6 +346,7 @@ async fn add_reward() {
         &admin_account_keypair,
         &program_account_keypair,
         &token_account_keypair,
+        &mint_account_keypair.pubkey(),
         &payer,
         program_id,
         recent_blockhash,
@@ -222,193 +366,255 @@ async fn add_reward() {
         .process_transaction(create_player_account_transaction)
         .await
         .unwrap();
-    // Register player one
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_one_holder_keypair,
-        &player_one_account_keypair,
+    for (player_holder_keypair, player_account_keypair) in [
+        (&player_one_holder_keypair, &player_one_account_keypair),
+        (&player_two_holder_keypair, &player_two_account_keypair),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            None,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
+        &player_one_account_keypair,
         None,
         program_id,
+        100,
+        &payer,
         recent_blockhash,
     );
     banks_client.process_transaction(transaction).await.unwrap();
 
-    // Register player two, with player one as upline
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_two_holder_keypair,
-        &player_two_account_keypair,
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let player_one_associated_token_account = get_associated_token_address(
+        &player_one_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    let player_two_associated_token_account = get_associated_token_address(
+        &player_two_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+
+    // Test delegate claim before the player associated token account exists
+    let transaction = build_set_claim_delegate_transaction(
+        &player_one_holder_keypair,
         &program_account_keypair,
-        Some(&player_one_account_keypair),
+        &player_one_account_keypair,
+        Some(&player_two_holder_keypair.pubkey()),
         program_id,
+        &payer,
         recent_blockhash,
     );
     banks_client.process_transaction(transaction).await.unwrap();
 
-    // Test add reward with non-admin account
-    let fake_admin_account_keypair = Keypair::new();
-    let add_reward_transaction = build_add_reward_transaction(
-        &fake_admin_account_keypair,
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
         &program_account_keypair,
         &player_one_account_keypair,
-        None,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
         program_id,
-        100,
         &payer,
         recent_blockhash,
     );
-    let result = banks_client
-        .process_transaction(add_reward_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(
-                error.to_string().contains("missing required signature"),
-                true
-            );
-        }
-    };
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPlayerTokenAccount as u32)
+        )
+    );
     // End
 
-    // Test add reward with invalid player account
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
+    // Account data can't be resized by CPI in native mode, see claim_reward_create_associated_token_account
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            // Fund the players to pay the fee of their own transactions
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test set the delegate of another player
+    let transaction = build_set_claim_delegate_transaction(
+        &player_two_holder_keypair,
         &program_account_keypair,
-        &Keypair::new(),
-        None,
+        &player_one_account_keypair,
+        Some(&player_two_holder_keypair.pubkey()),
         program_id,
-        100,
         &payer,
         recent_blockhash,
     );
-    let result = banks_client
-        .process_transaction(add_reward_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
-    };
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
     // End
 
-    // Test add reward with non-exists upline account
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
+    // Test delegate claim to the delegate token account
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
         &program_account_keypair,
-        &player_two_account_keypair,
-        Some(&Keypair::new()),
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_two_associated_token_account,
+        &mint_account_keypair.pubkey(),
         program_id,
-        100,
         &payer,
         recent_blockhash,
     );
-    let result = banks_client
-        .process_transaction(add_reward_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
-    };
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPlayerTokenAccount as u32)
+        )
+    );
     // End
 
-    // Test add reward to invalid upline account
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
-        &program_account_keypair,
-        &player_two_account_keypair,
-        Some(&player_two_account_keypair),
+    // Test claim by a signer which is neither the player nor its delegate
+    let transaction = build_claim_reward_transaction(
+        &Keypair::new(),
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
         program_id,
-        100,
         &payer,
         recent_blockhash,
     );
-    let result = banks_client
-        .process_transaction(add_reward_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("custom program error"), true);
-        }
-    };
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
     // End
 
-    // Test add reward to player two with admin
-    let add_reward_transaction = build_add_reward_transaction(
-        &admin_account_keypair,
+    // Test delegate claim, paid to the player associated token account
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
         &program_account_keypair,
-        &player_two_account_keypair,
-        Some(&player_one_account_keypair),
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
         program_id,
-        100,
-        &payer,
+        &player_two_holder_keypair,
         recent_blockhash,
     );
-    banks_client
-        .process_transaction(add_reward_transaction)
-        .await
-        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
 
     let player_one_account = banks_client
         .get_account(player_one_account_keypair.pubkey())
         .await
+        .unwrap()
         .unwrap();
-    match player_one_account {
-        Some(account) => {
-            let player_one_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_one_state.reward_to_claim, 10); // 10% from player two
-        }
-        _ => {} // Unreachable
-    };
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    assert_eq!(player_one_state.total_claimed, 100);
+    assert_eq!(
+        player_one_state.claim_delegate,
+        COption::Some(player_two_holder_keypair.pubkey())
+    );
 
-    let player_two_account = banks_client
-        .get_account(player_two_account_keypair.pubkey())
+    let player_one_token_account = banks_client
+        .get_account(player_one_associated_token_account)
         .await
+        .unwrap()
         .unwrap();
-    match player_two_account {
-        Some(account) => {
-            let player_two_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_two_state.reward_to_claim, 90);
-        }
-        _ => {} // Unreachable
-    };
+    let player_one_token_account_state =
+        spl_token::state::Account::unpack(&player_one_token_account.data).unwrap();
+    assert_eq!(player_one_token_account_state.amount, 100);
 
-    // Test add reward to player one
-    let add_reward_transaction = build_add_reward_transaction(
+    // Test delegate claim after the delegate is removed
+    let transaction = build_set_claim_delegate_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_add_reward_transaction(
         &admin_account_keypair,
         &program_account_keypair,
         &player_one_account_keypair,
         None,
         program_id,
-        100,
+        50,
         &payer,
         recent_blockhash,
     );
-    banks_client
-        .process_transaction(add_reward_transaction)
-        .await
-        .unwrap();
+    banks_client.process_transaction(transaction).await.unwrap();
 
-    let player_one_account = banks_client
-        .get_account(player_one_account_keypair.pubkey())
-        .await
-        .unwrap();
-    match player_one_account {
-        Some(account) => {
-            let player_one_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_one_state.reward_to_claim, 110); // 100 + 10% from player two
-        }
-        _ => {} // Unreachable
-    };
+    // Player one pay the fee, the same claim already failed before the associated token account exists
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
 }
 
 #[tokio::test]
-async fn register_player() {
+async fn transfer_reward() {
     let (
         mint_account_keypair,
         admin_account_keypair,
@@ -429,6 +635,7 @@ async fn register_player() {
         &admin_account_keypair,
         &program_account_keypair,
         &token_account_keypair,
+        &mint_account_keypair.pubkey(),
         &payer,
         program_id,
         recent_blockhash,
@@ -448,243 +655,9362 @@ async fn register_player() {
         .process_transaction(create_player_account_transaction)
         .await
         .unwrap();
-
-    // Test register player with invalid program id
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_one_holder_keypair,
-        &Keypair::new(),
+    for (player_holder_keypair, player_account_keypair) in [
+        (&player_one_holder_keypair, &player_one_account_keypair),
+        (&player_two_holder_keypair, &player_two_account_keypair),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            None,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
+        &player_one_account_keypair,
         None,
         program_id,
+        1000,
+        &payer,
         recent_blockhash,
     );
-    let result = banks_client.process_transaction(transaction).await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
-    };
-    // End
+    banks_client.process_transaction(transaction).await.unwrap();
 
-    // Test register player with invalid program account
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_one_holder_keypair,
+    // Test transfer signed by another player, more than the reward, and to the sender itself
+    let transaction = build_transfer_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
         &player_one_account_keypair,
-        &Keypair::new(),
-        None,
+        &player_two_account_keypair,
+        100,
         program_id,
+        &payer,
         recent_blockhash,
     );
     let result = banks_client.process_transaction(transaction).await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
-    };
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    for (recipient_account_keypair, amount, error) in [
+        (
+            &player_two_account_keypair,
+            1001,
+            GameError::InsufficientReward,
+        ),
+        (&player_one_account_keypair, 100, GameError::InvalidTransfer),
+    ] {
+        let transaction = build_transfer_reward_transaction(
+            &player_one_holder_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            recipient_account_keypair,
+            amount,
+            program_id,
+            &payer,
+            recent_blockhash,
+        );
+        let result = banks_client.process_transaction(transaction).await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+        );
+    }
     // End
 
-    // Test register player with invalid upline account
-    let transaction = build_register_player_transaction(
-        &payer,
+    // Test set transfer fee by non admin, and greater than 100 percent
+    let transaction = build_set_transfer_fee_transaction(
         &player_one_holder_keypair,
-        &player_one_account_keypair,
         &program_account_keypair,
-        Some(&Keypair::new()),
+        10,
         program_id,
+        &payer,
         recent_blockhash,
     );
     let result = banks_client.process_transaction(transaction).await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
-    };
-    // End
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
 
-    // Test register self as upline
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_one_holder_keypair,
-        &player_one_account_keypair,
+    let transaction = build_set_transfer_fee_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
-        Some(&player_one_account_keypair),
+        101,
         program_id,
+        &payer,
         recent_blockhash,
     );
     let result = banks_client.process_transaction(transaction).await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("custom program error"), true);
-            // Why decode custom error not working?
-        }
-    };
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPercentage as u32)
+        )
+    );
     // End
 
-    // Test register player
-    // Register player one
-    let transaction = build_register_player_transaction(
-        &payer,
+    // Test transfer without fee, then with a 10% fee kept in the vault
+    let transaction = build_transfer_reward_transaction(
         &player_one_holder_keypair,
+        &program_account_keypair,
         &player_one_account_keypair,
+        &player_two_account_keypair,
+        300,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_set_transfer_fee_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
-        None,
+        10,
         program_id,
+        &payer,
         recent_blockhash,
     );
-    let result = banks_client.process_transaction(transaction).await;
-    let player_one_account = banks_client
-        .get_account(player_one_account_keypair.pubkey())
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_transfer_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        200,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for (player_account_keypair, reward_to_claim) in [
+        (&player_one_account_keypair, 500),
+        (&player_two_account_keypair, 480),
+    ] {
+        let player_account = banks_client
+            .get_account(player_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, reward_to_claim);
+    }
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
         .await
+        .unwrap()
         .unwrap();
-    match player_one_account {
-        Some(account) => {
-            let player_one_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_one_state.is_initialized, true);
-            assert_eq!(player_one_state.owner, player_one_holder_keypair.pubkey());
-            assert_eq!(player_one_state.reward_to_claim, 0);
-            assert_eq!(player_one_state.upline, COption::None);
-        }
-        _ => {
-            panic!("Player one account not found");
-        }
-    };
+    let program_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_state.total_transfer_fees, 20);
+    // End
+}
 
-    // Register player two, with player one as upline
-    let transaction = build_register_player_transaction(
-        &payer,
-        &player_two_holder_keypair,
-        &player_two_account_keypair,
+#[tokio::test]
+async fn check_in() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
         &program_account_keypair,
-        Some(&player_one_account_keypair),
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
         program_id,
         recent_blockhash,
     );
-    let result = banks_client.process_transaction(transaction).await;
-
-    let player_two_account = banks_client
-        .get_account(player_two_account_keypair.pubkey())
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
         .await
         .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    for (player_holder_keypair, player_account_keypair, upline_account_keypair) in [
+        (
+            &player_one_holder_keypair,
+            &player_one_account_keypair,
+            None,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_two_account_keypair,
+            Some(&player_one_account_keypair),
+        ),
+    ] {
+        let transaction = build_register_player_transaction(
+            &context.payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            upline_account_keypair,
+            program_id,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    // Player pay the fee to resend the same check-in
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &player_one_holder_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test check in before the schedule is set
+    let transaction = build_check_in_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::CheckInDisabled as u32)
+        )
+    );
+    // End
+
+    // Test set schedule by non admin, and longer than the maximum number of days
+    let transaction = build_set_check_in_schedule_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &[10, 20, 30],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let transaction = build_set_check_in_schedule_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &[10; 8],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidCheckInSchedule as u32)
+        )
+    );
+    // End
+
+    let transaction = build_set_check_in_schedule_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &[10, 20, 30],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test streak grow on consecutive days, keep the last reward, and restart after a missed day
+    // Clock change does not change the transaction, so each check-in has a new fee payer
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    let first_day = clock.unix_timestamp.div_euclid(24 * 60 * 60) + 1;
+    let mut reward = 0;
+    for (day, streak, day_reward, hour) in [
+        (0, 1, 10, 23),
+        (1, 2, 20, 0),
+        (2, 3, 30, 12),
+        (3, 4, 30, 12),
+        (5, 1, 10, 12),
+    ] {
+        clock.unix_timestamp = (first_day + day) * 24 * 60 * 60 + hour * 60 * 60;
+        context.set_sysvar(&clock);
+        let fee_payer_keypair = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(
+                &context.payer.pubkey(),
+                &fee_payer_keypair.pubkey(),
+                1_000_000_000,
+            )],
+            Some(&context.payer.pubkey()),
+        );
+        transaction.partial_sign(&[&context.payer], recent_blockhash);
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        let transaction = build_check_in_transaction(
+            &player_one_holder_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            None,
+            program_id,
+            &fee_payer_keypair,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+
+        reward += day_reward;
+        let player_account = context
+            .banks_client
+            .get_account(player_one_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.check_in_streak, streak);
+        assert_eq!(player_state.reward_to_claim, reward);
+    }
+    // End
+
+    // Test check in twice the same day
+    let transaction = build_check_in_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::AlreadyCheckedIn as u32)
+        )
+    );
+    // End
+
+    // Test upline receive 10% of the check-in reward
+    let transaction = build_check_in_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for (player_account_keypair, reward_to_claim) in [
+        (&player_one_account_keypair, reward + 1),
+        (&player_two_account_keypair, 9),
+    ] {
+        let player_account = context
+            .banks_client
+            .get_account(player_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, reward_to_claim);
+    }
+    // End
+}
+
+#[tokio::test]
+async fn invalid_loot_box() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let tiers = [LootTier {
+        weight: 1,
+        reward: 100,
+    }];
+    // Test set loot table by non admin
+    let transaction = build_set_loot_table_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        100,
+        &tiers,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test set loot table with a zero price, no tier and a zero total weight
+    for (price, tiers) in [
+        (0, tiers.to_vec()),
+        (100, vec![]),
+        (
+            100,
+            vec![LootTier {
+                weight: 0,
+                reward: 100,
+            }],
+        ),
+    ] {
+        let transaction = build_set_loot_table_transaction(
+            &admin_account_keypair,
+            &program_account_keypair,
+            price,
+            &tiers,
+            program_id,
+            &payer,
+            recent_blockhash,
+        );
+        let result = banks_client.process_transaction(transaction).await;
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(GameError::InvalidLootTable as u32)
+            )
+        );
+    }
+    // End
+
+    // Test open and reveal box without loot table
+    let transaction = build_open_box_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+
+    let transaction = build_reveal_box_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+    // End
+}
+
+#[test]
+fn loot_tier_selection() {
+    let mut loot_box = LootBox::unpack_unchecked(&[0; LootBox::LEN]).unwrap();
+    assert_eq!(loot_box.pick_tier(0), None);
+
+    loot_box.tier_count = 3;
+    for (tier, weight) in loot_box.tiers.iter_mut().zip([1, 0, 3]) {
+        tier.weight = weight;
+    }
+    // Unused tiers are ignored
+    loot_box.tiers[3].weight = 100;
+
+    // Roll 0 fall in the first tier, 1 to 3 in the third, a tier without weight is never picked
+    assert_eq!(loot_box.pick_tier(0), Some(0));
+    assert_eq!(loot_box.pick_tier(1), Some(2));
+    assert_eq!(loot_box.pick_tier(3), Some(2));
+    assert_eq!(loot_box.pick_tier(4), Some(0));
+    assert_eq!(loot_box.pick_tier(u64::MAX), Some(2));
+}
+
+#[test]
+fn slot_hash_lookup() {
+    // Newest slot first, slot 12 was skipped
+    let entries = [(14_u64, [14_u8; 32]), (13, [13; 32]), (11, [11; 32])];
+    let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+    for (slot, hash) in entries {
+        data.extend_from_slice(&slot.to_le_bytes());
+        data.extend_from_slice(&hash);
+    }
+    let key = sysvar::slot_hashes::id();
+    let owner = sysvar::id();
+    let mut lamports = 0;
+    let slot_hashes_account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    assert!(matches!(
+        find_slot_hash(&slot_hashes_account, 15),
+        Ok(SlotHash::NotReady)
+    ));
+    assert!(matches!(
+        find_slot_hash(&slot_hashes_account, 13),
+        Ok(SlotHash::Found(hash)) if hash == [13; 32]
+    ));
+    // A skipped slot resolve to the first slot produced after it
+    assert!(matches!(
+        find_slot_hash(&slot_hashes_account, 12),
+        Ok(SlotHash::Found(hash)) if hash == [13; 32]
+    ));
+    assert!(matches!(
+        find_slot_hash(&slot_hashes_account, 11),
+        Ok(SlotHash::Found(hash)) if hash == [11; 32]
+    ));
+    // Older than the history
+    assert!(matches!(
+        find_slot_hash(&slot_hashes_account, 10),
+        Ok(SlotHash::Expired)
+    ));
+}
+
+#[tokio::test]
+async fn loot_box() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &context.payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        1000,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Player and admin pay the fee to resend the same instruction
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &admin_account_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Loot table and revealed loot box of player one, normally created in BPF only
+    preload_program_owned_account(
+        &mut context,
+        &find_loot_table_address(&program_account_keypair.pubkey(), &program_id),
+        &[0; LootTable::LEN],
+        &program_id,
+    );
+    let mut loot_box_data = vec![0; LootBox::LEN];
+    LootBox::pack(
+        LootBox {
+            is_initialized: true,
+            program_account: program_account_keypair.pubkey(),
+            player: player_one_account_keypair.pubkey(),
+            pending: false,
+            price: 0,
+            reveal_slot: 0,
+            tier_count: 0,
+            tiers: Default::default(),
+        },
+        &mut loot_box_data,
+    )
+    .unwrap();
+    preload_program_owned_account(
+        &mut context,
+        &find_loot_box_address(&player_one_account_keypair.pubkey(), &program_id),
+        &loot_box_data,
+        &program_id,
+    );
+
+    // Tier without weight is never picked, so the reward is known whatever the slot hash
+    let transaction = build_set_loot_table_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        100,
+        &[
+            LootTier {
+                weight: 0,
+                reward: 1000,
+            },
+            LootTier {
+                weight: 5,
+                reward: 300,
+            },
+        ],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test open box, then open again and reveal before the reveal slot
+    let transaction = build_open_box_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let player_account = context
+        .banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_state = Player::unpack(&player_account.data).unwrap();
+    assert_eq!(player_state.reward_to_claim, 900);
+
+    let transaction = build_open_box_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::LootBoxInProgress as u32)
+        )
+    );
+
+    let transaction = build_reveal_box_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::LootBoxNotReady as u32)
+        )
+    );
+    // End
+
+    // Test the loot table changed after the opening, the box keeps the tiers of the opening
+    let transaction = build_set_loot_table_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        100,
+        &[LootTier {
+            weight: 1,
+            reward: 0,
+        }],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    // End
+
+    // Test reveal box once the reveal slot hash is recorded, then reveal again
+    let loot_box_account = context
+        .banks_client
+        .get_account(find_loot_box_address(
+            &player_one_account_keypair.pubkey(),
+            &program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let loot_box_state = LootBox::unpack(&loot_box_account.data).unwrap();
+    assert!(loot_box_state.pending);
+    // The hash of a slot is recorded in SlotHashes by the bank of the next slot
+    context
+        .warp_to_slot(loot_box_state.reveal_slot + 1)
+        .unwrap();
+    let transaction = build_reveal_box_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let player_account = context
+        .banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_state = Player::unpack(&player_account.data).unwrap();
+    assert_eq!(player_state.reward_to_claim, 1200);
+
+    let transaction = build_reveal_box_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &admin_account_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::LootBoxNotOpened as u32)
+        )
+    );
+    // End
+
+    // Test open box again with a price greater than the player reward
+    let transaction = build_set_loot_table_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        2000,
+        &[LootTier {
+            weight: 1,
+            reward: 0,
+        }],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_open_box_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &admin_account_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InsufficientReward as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn open_box() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &context.payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        1000,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Admin pay for the loot table creation, player pay for the loot box creation
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &admin_account_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test first loot table of the game and first box of the player create their accounts
+    let transaction = build_set_loot_table_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        100,
+        &[LootTier {
+            weight: 1,
+            reward: 300,
+        }],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_open_box_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let loot_table_account = context
+        .banks_client
+        .get_account(find_loot_table_address(
+            &program_account_keypair.pubkey(),
+            &program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let loot_table_state = LootTable::unpack(&loot_table_account.data).unwrap();
+    assert_eq!(loot_table_state.price, 100);
+    assert_eq!(loot_table_state.tier_count, 1);
+    let loot_box_account = context
+        .banks_client
+        .get_account(find_loot_box_address(
+            &player_one_account_keypair.pubkey(),
+            &program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let loot_box_state = LootBox::unpack(&loot_box_account.data).unwrap();
+    assert!(loot_box_state.pending);
+    assert_eq!(loot_box_state.price, 100);
+    assert_eq!(loot_box_state.tiers[0].reward, 300);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_flip() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test commit flip with a wager greater than the player reward
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        100,
+        [1; 32],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidWager as u32)
+        )
+    );
+    // End
+
+    // Test house commit by non operator
+    let transaction = build_house_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        [1; 32],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test reveal and resolve a flip not created
+    let transaction = build_reveal_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        [1; 32],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+
+    let transaction = build_resolve_flip_timeout_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+    // End
+}
+
+#[tokio::test]
+async fn flip() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &context.payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        1000,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Player and admin pay the fee to resend the same resolve
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &admin_account_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Settled flip of player one, normally created by the first CommitFlip in BPF only
+    let mut flip_data = vec![0; Flip::LEN];
+    Flip::pack(
+        Flip {
+            is_initialized: true,
+            program_account: program_account_keypair.pubkey(),
+            player: player_one_account_keypair.pubkey(),
+            status: FlipStatus::Settled,
+            wager: 0,
+            player_commitment: [0; 32],
+            house: Pubkey::default(),
+            house_commitment: [0; 32],
+            player_secret: [0; 32],
+            house_secret: [0; 32],
+            player_revealed: false,
+            house_revealed: false,
+            committed_at: 0,
+        },
+        &mut flip_data,
+    )
+    .unwrap();
+    preload_program_owned_account(
+        &mut context,
+        &find_flip_address(&player_one_account_keypair.pubkey(), &program_id),
+        &flip_data,
+        &program_id,
+    );
+
+    // House secret giving the expected winner with the player secret
+    let find_house_secret = |player_secret: &[u8; 32], player_win: bool| {
+        (0..=u8::MAX)
+            .map(|i| [i; 32])
+            .find(|house_secret| {
+                let outcome = solana_program::hash::hashv(&[player_secret, house_secret]);
+                (outcome.to_bytes()[0] & 1 == 0) == player_win
+            })
+            .unwrap()
+    };
+    let commitment = |secret: &[u8; 32]| solana_program::hash::hashv(&[secret]).to_bytes();
+
+    // Test flip settled by both reveals, the player win then lose
+    let mut reward = 1000;
+    for (player_secret, player_win) in [([1; 32], true), ([2; 32], false)] {
+        let house_secret = find_house_secret(&player_secret, player_win);
+        let transaction = build_commit_flip_transaction(
+            &player_one_holder_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            100,
+            commitment(&player_secret),
+            program_id,
+            &context.payer,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        let transaction = build_house_commit_flip_transaction(
+            &admin_account_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            commitment(&house_secret),
+            program_id,
+            &context.payer,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        for (revealer_keypair, secret) in [
+            (&player_one_holder_keypair, player_secret),
+            (&admin_account_keypair, house_secret),
+        ] {
+            let transaction = build_reveal_flip_transaction(
+                revealer_keypair,
+                &program_account_keypair,
+                &player_one_account_keypair,
+                secret,
+                program_id,
+                &context.payer,
+                recent_blockhash,
+            );
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap();
+        }
+
+        reward = if player_win {
+            reward + 100
+        } else {
+            reward - 100
+        };
+        let player_account = context
+            .banks_client
+            .get_account(player_one_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, reward);
+    }
+    // End
+
+    // Test commit flip while a flip is in progress, and reveal a secret not matching the commitment
+    let player_secret = [3; 32];
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        100,
+        commitment(&player_secret),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        200,
+        commitment(&player_secret),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::FlipInProgress as u32)
+        )
+    );
+
+    let transaction = build_house_commit_flip_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        commitment(&[4; 32]),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_reveal_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        [4; 32],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidCommitment as u32)
+        )
+    );
+    // End
+
+    // Test player win by timeout when the house withhold its secret
+    let transaction = build_reveal_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        player_secret,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_resolve_flip_timeout_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &admin_account_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::FlipTimeoutNotReached as u32)
+        )
+    );
+
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    clock.unix_timestamp += 60 * 60;
+    context.set_sysvar(&clock);
+    let transaction = build_resolve_flip_timeout_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    // End
+
+    // Test wager refunded by timeout when the house never commit
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        300,
+        commitment(&[5; 32]),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    clock.unix_timestamp += 60 * 60;
+    context.set_sysvar(&clock);
+    let transaction = build_resolve_flip_timeout_transaction(
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let player_account = context
+        .banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_state = Player::unpack(&player_account.data).unwrap();
+    assert_eq!(player_state.reward_to_claim, reward + 100);
+    // End
+
+    // Test operator as house, the wager is charged to its reward cap and given back when the house win
+    let operator_keypair = Keypair::new();
+    let transaction = build_add_operator_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &operator_keypair.pubkey(),
+        Some(150),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for (player_secret, player_win, total_rewarded) in [([6; 32], false, 0), ([7; 32], true, 100)] {
+        let house_secret = find_house_secret(&player_secret, player_win);
+        let transaction = build_commit_flip_transaction(
+            &player_one_holder_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            100,
+            commitment(&player_secret),
+            program_id,
+            &context.payer,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        let transaction = build_house_commit_flip_transaction(
+            &operator_keypair,
+            &program_account_keypair,
+            &player_one_account_keypair,
+            commitment(&house_secret),
+            program_id,
+            &context.payer,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        for (revealer_keypair, secret) in [
+            (&player_one_holder_keypair, player_secret),
+            (&operator_keypair, house_secret),
+        ] {
+            let transaction = build_reveal_flip_transaction(
+                revealer_keypair,
+                &program_account_keypair,
+                &player_one_account_keypair,
+                secret,
+                program_id,
+                &context.payer,
+                recent_blockhash,
+            );
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap();
+        }
+
+        let program_account = context
+            .banks_client
+            .get_account(program_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+        assert_eq!(
+            program_account_state.operators[0].total_rewarded,
+            total_rewarded
+        );
+    }
+    // End
+
+    // Test operator can not be the house of a flip it could lose above its reward cap
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        100,
+        commitment(&[8; 32]),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_house_commit_flip_transaction(
+        &operator_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        commitment(&[9; 32]),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::OperatorRewardCapExceeded as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn commit_flip() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &context.payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        1000,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Player pay for the flip creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &player_one_holder_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test first flip of the player create the flip
+    let transaction = build_commit_flip_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        100,
+        [1; 32],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let flip_account = context
+        .banks_client
+        .get_account(find_flip_address(
+            &player_one_account_keypair.pubkey(),
+            &program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let flip_state = Flip::unpack(&flip_account.data).unwrap();
+    assert_eq!(flip_state.player, player_one_account_keypair.pubkey());
+    assert_eq!(flip_state.status, FlipStatus::PlayerCommitted);
+    assert_eq!(flip_state.wager, 100);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_quest() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test create quest by non admin
+    let transaction = build_create_quest_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        0,
+        100,
+        None,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test create quest with invalid expiry option
+    let mut transaction = build_create_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        100,
+        None,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let create_quest_data = &mut transaction.message.instructions[0].data;
+    *create_quest_data.last_mut().unwrap() = 2;
+    transaction.partial_sign(&[&payer, &admin_account_keypair], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    // End
+
+    // Test complete a quest not created
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+    // End
+}
+
+#[tokio::test]
+async fn quest() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    // Quest 0 without limit, quest 1 completed once at most, quest 2 already expired
+    // Quests and quest bitmaps, normally created by CreateQuest and CompleteQuest in BPF only
+    for (quest_id, max_completions, expiry) in [
+        (0_u64, COption::None, COption::None),
+        (1, COption::Some(1), COption::None),
+        (2, COption::None, COption::Some(0)),
+    ] {
+        let (quest, _nonce) = Pubkey::find_program_address(
+            &[
+                "quest".as_bytes(),
+                program_account_keypair.pubkey().as_ref(),
+                &quest_id.to_le_bytes(),
+            ],
+            &program_id,
+        );
+        let mut quest_data = vec![0; Quest::LEN];
+        Quest::pack(
+            Quest {
+                is_initialized: true,
+                program_account: program_account_keypair.pubkey(),
+                reward: 100,
+                max_completions,
+                expiry,
+                completions: 0,
+            },
+            &mut quest_data,
+        )
+        .unwrap();
+        preload_program_owned_account(&mut context, &quest, &quest_data, &program_id);
+    }
+    for player_account_keypair in [&player_one_account_keypair, &player_two_account_keypair] {
+        let (quest_bitmap, _nonce) = Pubkey::find_program_address(
+            &[
+                "quests".as_bytes(),
+                player_account_keypair.pubkey().as_ref(),
+            ],
+            &program_id,
+        );
+        preload_program_owned_account(
+            &mut context,
+            &quest_bitmap,
+            &[0; QUEST_BITMAP_LEN],
+            &program_id,
+        );
+    }
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Admin pay the fee to resend the same completion
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test complete quest credit the reward with upline commission
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for (player_account_keypair, reward) in [
+        (&player_one_account_keypair, 10),
+        (&player_two_account_keypair, 90),
+    ] {
+        let player_account = banks_client
+            .get_account(player_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, reward);
+    }
+    // End
+
+    // Test complete the same quest twice
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        &admin_account_keypair,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::QuestAlreadyCompleted as u32)
+        )
+    );
+    // End
+
+    // Test complete a quest over its maximum completions
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        1,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        1,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::QuestExhausted as u32)
+        )
+    );
+    // End
+
+    // Test complete an expired quest
+    let transaction = build_complete_quest_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        2,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::QuestExpired as u32)
+        )
+    );
+    // End
+
+    // Test complete quest by non operator
+    let transaction = build_complete_quest_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        0,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn create_quest() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+
+    // Admin pay for the quest creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test create quests, quest 1 completed once at most, quest 2 already expired
+    for (quest_id, max_completions, expiry) in
+        [(0, None, None), (1, Some(1), None), (2, None, Some(0))]
+    {
+        let transaction = build_create_quest_transaction(
+            &admin_account_keypair,
+            &program_account_keypair,
+            quest_id,
+            100,
+            max_completions,
+            expiry,
+            program_id,
+            &payer,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    for (quest_id, max_completions, expiry) in [
+        (0_u64, COption::None, COption::None),
+        (1, COption::Some(1), COption::None),
+        (2, COption::None, COption::Some(0)),
+    ] {
+        let (quest, _nonce) = Pubkey::find_program_address(
+            &[
+                "quest".as_bytes(),
+                program_account_keypair.pubkey().as_ref(),
+                &quest_id.to_le_bytes(),
+            ],
+            &program_id,
+        );
+        let quest_account = banks_client.get_account(quest).await.unwrap().unwrap();
+        let quest_state = Quest::unpack(&quest_account.data).unwrap();
+        assert_eq!(quest_state.reward, 100);
+        assert_eq!(quest_state.max_completions, max_completions);
+        assert_eq!(quest_state.expiry, expiry);
+    }
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.quest_count, 3);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_stake() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test create stake vault and set stake reward rate by non admin
+    let transaction = build_init_stake_vault_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let transaction = build_set_stake_reward_rate_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        10,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test stake before the stake vault is created
+    let transaction = build_stake_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &player_one_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        100,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::StakeVaultNotFound as u32)
+        )
+    );
+    // End
+
+    // Test unstake from the player program account of another holder
+    let transaction = build_unstake_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        0,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+}
+
+#[test]
+fn stake_reward_per_share() {
+    let mut game = GameInfo::unpack_unchecked(&[0; GameInfo::LEN]).unwrap();
+    game.stake_reward_rate = 10;
+    game.last_stake_update = 100;
+
+    // Nothing accrue while no token is staked
+    game.update_reward_per_share(150).unwrap();
+    assert_eq!(game.reward_per_share, 0);
+    assert_eq!(game.last_stake_update, 150);
+
+    // 50 seconds at 10 per second shared by 1000 staked token
+    game.total_staked = 1000;
+    game.update_reward_per_share(200).unwrap();
+    assert_eq!(game.reward_per_share, 500 * STAKE_REWARD_PRECISION / 1000);
+
+    // Time going backward accrue nothing
+    game.update_reward_per_share(190).unwrap();
+    assert_eq!(game.last_stake_update, 200);
+    assert_eq!(game.reward_per_share, 500 * STAKE_REWARD_PRECISION / 1000);
+
+    // Overflow
+    game.stake_reward_rate = u64::MAX;
+    game.total_staked = 1;
+    assert!(game.update_reward_per_share(i64::MAX).is_none());
+}
+
+#[tokio::test]
+async fn stake() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    for (player_holder_keypair, player_account_keypair, player_token_account_keypair) in [
+        (
+            &player_one_holder_keypair,
+            &player_one_account_keypair,
+            &player_one_token_account_keypair,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_two_account_keypair,
+            &player_two_token_account_keypair,
+        ),
+    ] {
+        let transaction = build_register_player_transaction(
+            &context.payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            None,
+            program_id,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        let transaction = build_mint_transaction(
+            &context.payer,
+            &mint_account_keypair,
+            player_token_account_keypair,
+            &admin_account_keypair,
+            1000,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    // Stake vault, normally created by InitStakeVault in BPF only
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    preload_token_account(
+        &mut context,
+        &find_stake_vault_address(&program_account_keypair.pubkey(), &program_id),
+        &mint_account_keypair.pubkey(),
+        &pda,
+    );
+
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    let start = clock.unix_timestamp;
+    let transaction = build_set_stake_reward_rate_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        10,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test yield shared by the staked tokens, player one stake 100 alone for 10 seconds, then with 300 of player two for 20 seconds
+    let transaction = build_stake_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &player_one_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        100,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    clock.unix_timestamp = start + 10;
+    context.set_sysvar(&clock);
+    let transaction = build_stake_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        300,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    clock.unix_timestamp = start + 30;
+    context.set_sysvar(&clock);
+    let transaction = build_unstake_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &player_one_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        100,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let transaction = build_unstake_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        0,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    for (player_account_keypair, player_token_account_keypair, reward, staked_amount, balance) in [
+        (
+            &player_one_account_keypair,
+            &player_one_token_account_keypair,
+            150,
+            0,
+            1000,
+        ),
+        (
+            &player_two_account_keypair,
+            &player_two_token_account_keypair,
+            150,
+            300,
+            700,
+        ),
+    ] {
+        let player_account = context
+            .banks_client
+            .get_account(player_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, reward);
+        assert_eq!(player_state.staked_amount, staked_amount);
+
+        let player_token_account = context
+            .banks_client
+            .get_account(player_token_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_token_account_state =
+            spl_token::state::Account::unpack(&player_token_account.data).unwrap();
+        assert_eq!(player_token_account_state.amount, balance);
+    }
+
+    let program_account = context
+        .banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_state.total_staked, 300);
+    assert_eq!(program_state.total_awarded, 300);
+    // End
+
+    // Test unstake more than staked
+    let transaction = build_unstake_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        301,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InsufficientStake as u32)
+        )
+    );
+    // End
+
+    // Test unstake to a token account of another holder
+    let transaction = build_unstake_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &player_one_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        100,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPlayerTokenAccount as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn init_stake_vault() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+
+    // Admin pay for the stake vault creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test create stake vault
+    let transaction = build_init_stake_vault_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let stake_vault_account = context
+        .banks_client
+        .get_account(find_stake_vault_address(
+            &program_account_keypair.pubkey(),
+            &program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let stake_vault_state = spl_token::state::Account::unpack(&stake_vault_account.data).unwrap();
+    assert_eq!(stake_vault_state.mint, mint_account_keypair.pubkey());
+    assert_eq!(stake_vault_state.owner, pda);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_season() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test create season by non admin
+    let transaction = build_create_season_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        0,
+        0,
+        100,
+        &[100],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test create season ending before start
+    let transaction = build_create_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        100,
+        100,
+        &[100],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidSeason as u32)
+        )
+    );
+    // End
+
+    // Test create season with more prizes than leaderboard ranks
+    let transaction = build_create_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        0,
+        100,
+        &[100; 11],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidSeason as u32)
+        )
+    );
+    // End
+
+    // Test submit score and finalize a season not created
+    let transaction = build_submit_score_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &player_one_account_keypair,
+        10,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+
+    let transaction = build_finalize_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &[player_one_account_keypair.pubkey()],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+    // End
+}
+
+#[test]
+fn season_leaderboard() {
+    let mut season = Season::unpack_unchecked(&[0; Season::LEN]).unwrap();
+    let players: Vec<Pubkey> = (0..=SEASON_LEADERBOARD_LEN)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+
+    // Insert in an empty leaderboard
+    assert_eq!(season.update_leaderboard(&players[0], 10), Some(0));
+    assert_eq!(season.update_leaderboard(&players[1], 30), Some(0));
+    assert_eq!(season.update_leaderboard(&players[2], 20), Some(1));
+    assert_eq!(season.leaderboard[0].player, players[1]);
+    assert_eq!(season.leaderboard[1].player, players[2]);
+    assert_eq!(season.leaderboard[2].player, players[0]);
+    assert!(!season.leaderboard[3].is_active());
+
+    // Reorder when the score of a player grows
+    assert_eq!(season.update_leaderboard(&players[0], 25), Some(1));
+    assert_eq!(season.leaderboard[1].player, players[0]);
+    assert_eq!(season.leaderboard[2].player, players[2]);
+
+    // A tie keep the earlier entry ahead
+    assert_eq!(season.update_leaderboard(&players[2], 25), Some(2));
+    assert_eq!(season.leaderboard[1].player, players[0]);
+
+    // Fill the leaderboard, then a lower score or a tie with the last rank is not recorded
+    for (index, player) in players[3..SEASON_LEADERBOARD_LEN].iter().enumerate() {
+        season.update_leaderboard(player, 5 + index as u64);
+    }
+    let last = &season.leaderboard[SEASON_LEADERBOARD_LEN - 1];
+    assert_eq!(last.score, 5);
+    assert_eq!(
+        season.update_leaderboard(&players[SEASON_LEADERBOARD_LEN], 5),
+        None
+    );
+
+    // A higher score evict the last rank, and stay behind the entry it ties with
+    assert_eq!(
+        season.update_leaderboard(&players[SEASON_LEADERBOARD_LEN], 6),
+        Some(SEASON_LEADERBOARD_LEN - 1)
+    );
+    assert_eq!(
+        season.leaderboard[SEASON_LEADERBOARD_LEN - 2].player,
+        players[4]
+    );
+    assert!(season
+        .leaderboard
+        .iter()
+        .all(|entry| entry.player != players[3]));
+}
+
+#[tokio::test]
+async fn season() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    for (player_holder_keypair, player_account_keypair) in [
+        (&player_one_holder_keypair, &player_one_account_keypair),
+        (&player_two_holder_keypair, &player_two_account_keypair),
+    ] {
+        let transaction = build_register_player_transaction(
+            &context.payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            None,
+            program_id,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    // Admin pay the fee to resend the same finalize
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &admin_account_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let mut clock = context
+        .banks_client
+        .get_sysvar::<solana_program::clock::Clock>()
+        .await
+        .unwrap();
+    let start = clock.unix_timestamp;
+    let end = start + 1000;
+    let (season, _nonce) = Pubkey::find_program_address(
+        &[
+            "season".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &0_u64.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    // Season and season scores, normally created by CreateSeason and SubmitScore in BPF only
+    let mut prizes = [0; SEASON_LEADERBOARD_LEN];
+    prizes[..2].copy_from_slice(&[300, 100]);
+    let mut season_data = vec![0; Season::LEN];
+    Season::pack(
+        Season {
+            is_initialized: true,
+            program_account: program_account_keypair.pubkey(),
+            start,
+            end,
+            finalized: false,
+            prizes,
+            leaderboard: Default::default(),
+        },
+        &mut season_data,
+    )
+    .unwrap();
+    preload_program_owned_account(&mut context, &season, &season_data, &program_id);
+    for player_account_keypair in [&player_one_account_keypair, &player_two_account_keypair] {
+        let (season_score, _nonce) = Pubkey::find_program_address(
+            &[
+                "score".as_bytes(),
+                season.as_ref(),
+                player_account_keypair.pubkey().as_ref(),
+            ],
+            &program_id,
+        );
+        preload_program_owned_account(
+            &mut context,
+            &season_score,
+            &[0; SeasonScore::LEN],
+            &program_id,
+        );
+    }
+
+    // Test leaderboard ordered by the season score
+    for (player_account_keypair, points) in [
+        (&player_one_account_keypair, 10),
+        (&player_two_account_keypair, 20),
+        (&player_one_account_keypair, 15),
+    ] {
+        let transaction = build_submit_score_transaction(
+            &admin_account_keypair,
+            &program_account_keypair,
+            0,
+            player_account_keypair,
+            points,
+            program_id,
+            &context.payer,
+            recent_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    let season_account = context
+        .banks_client
+        .get_account(season)
+        .await
+        .unwrap()
+        .unwrap();
+    let season_state = learn_solana::state::Season::unpack(&season_account.data).unwrap();
+    assert_eq!(
+        season_state.leaderboard[0].player,
+        player_one_account_keypair.pubkey()
+    );
+    assert_eq!(season_state.leaderboard[0].score, 25);
+    assert_eq!(
+        season_state.leaderboard[1].player,
+        player_two_account_keypair.pubkey()
+    );
+    assert_eq!(season_state.leaderboard[1].score, 20);
+    assert!(!season_state.leaderboard[2].is_active());
+    // End
+
+    // Test finalize season before the end
+    let transaction = build_finalize_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &[
+            player_one_account_keypair.pubkey(),
+            player_two_account_keypair.pubkey(),
+        ],
+        program_id,
+        &admin_account_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::SeasonNotEnded as u32)
+        )
+    );
+    // End
+
+    clock.unix_timestamp = end;
+    context.set_sysvar(&clock);
+
+    // Test submit score after the end
+    let transaction = build_submit_score_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &player_two_account_keypair,
+        10,
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::SeasonNotActive as u32)
+        )
+    );
+    // End
+
+    // Test finalize season award the prizes, the prize of a frozen player is forfeited
+    let transaction = build_freeze_player_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        false,
+        &[],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_finalize_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &[
+            player_one_account_keypair.pubkey(),
+            player_two_account_keypair.pubkey(),
+        ],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    for (player_account_keypair, prize) in [
+        (&player_one_account_keypair, 300),
+        (&player_two_account_keypair, 0),
+    ] {
+        let player_account = context
+            .banks_client
+            .get_account(player_account_keypair.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&player_account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, prize);
+    }
+    let program_account = context
+        .banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_forfeited, 100);
+    // End
+
+    // Test finalize season twice
+    let transaction = build_finalize_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        &[
+            player_one_account_keypair.pubkey(),
+            player_two_account_keypair.pubkey(),
+        ],
+        program_id,
+        &player_one_holder_keypair,
+        recent_blockhash,
+    );
+    let result = context.banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::SeasonAlreadyFinalized as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn create_season() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &context.payer,
+        program_id,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+
+    // Admin pay for the season creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.partial_sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Test create season
+    let start = 1000;
+    let end = start + 1000;
+    let transaction = build_create_season_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        start,
+        end,
+        &[300, 100],
+        program_id,
+        &context.payer,
+        recent_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (season, _nonce) = Pubkey::find_program_address(
+        &[
+            "season".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &0_u64.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let season_account = context
+        .banks_client
+        .get_account(season)
+        .await
+        .unwrap()
+        .unwrap();
+    let season_state = Season::unpack(&season_account.data).unwrap();
+    assert_eq!(season_state.start, start);
+    assert_eq!(season_state.end, end);
+    assert_eq!(season_state.prizes[..2], [300, 100]);
+    assert!(!season_state.leaderboard[0].is_active());
+
+    let program_account = context
+        .banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.season_count, 1);
+    // End
+}
+
+#[tokio::test]
+async fn registration_fee() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    banks_client
+        .process_transaction(build_mint_transaction(
+            &payer,
+            &mint_account_keypair,
+            &player_two_token_account_keypair,
+            &admin_account_keypair,
+            5000,
+            recent_blockhash,
+        ))
+        .await
+        .unwrap();
+
+    // Test set registration fee by non admin
+    let transaction = build_set_registration_fee_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        1000,
+        20,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test set upline percentage over 100
+    let transaction = build_set_registration_fee_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        1000,
+        101,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPercentage as u32)
+        )
+    );
+    // End
+
+    // Test register with registration fee, upline receive 20% of the fee
+    let transaction = build_set_registration_fee_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        1000,
+        20,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_register_player_with_fee_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client
+        .get_account(player_two_token_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_token_account_state = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(player_two_token_account_state.amount, 4000);
+    let account = banks_client
+        .get_account(token_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_token_account_state = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(program_token_account_state.amount, 1000000001000);
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 200);
+    assert_eq!(player_one_state.total_referral_earned, 200);
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_registration_fees, 1000);
+    // End
+}
+
+#[tokio::test]
+async fn registration_gate() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Test set registration gate by non admin
+    let transaction = build_set_registration_gate_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        RegistrationGate::AdminSignature,
+        [0; 32],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test register with admin co-signature
+    let transaction = build_set_registration_gate_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        RegistrationGate::AdminSignature,
+        [0; 32],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_register_player_with_gate_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        Some(&player_two_holder_keypair),
+        &[],
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::RegistrationNotAllowed as u32)
+        )
+    );
+
+    let transaction = build_register_player_with_gate_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        Some(&admin_account_keypair),
+        &[],
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    // End
+
+    // Test register with allowlist proof
+    let player_two_leaf = merkle::hash_leaf(&[player_two_holder_keypair.pubkey().as_ref()]);
+    let other_leaf = merkle::hash_leaf(&[Pubkey::new_unique().as_ref()]);
+    let root = merkle::hash_node(&player_two_leaf, &other_leaf);
+    let transaction = build_set_registration_gate_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        RegistrationGate::Allowlist,
+        root,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(
+        program_account_state.registration_gate,
+        RegistrationGate::Allowlist
+    );
+    assert_eq!(program_account_state.allowlist_root, root);
+
+    let transaction = build_register_player_with_gate_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        None,
+        &[player_two_leaf],
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::RegistrationNotAllowed as u32)
+        )
+    );
+
+    let transaction = build_register_player_with_gate_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        None,
+        &[other_leaf],
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.is_initialized, true);
+    // End
+}
+
+#[tokio::test]
+async fn freeze_player() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    // Reward of player one in another mint, normally created by AddReward in BPF only
+    let other_mint = Pubkey::new_unique();
+    let (reward_balance, _nonce) = Pubkey::find_program_address(
+        &[
+            b"balance",
+            player_one_account_keypair.pubkey().as_ref(),
+            other_mint.as_ref(),
+        ],
+        &program_id,
+    );
+    let mut reward_balance_data = vec![0; RewardBalance::LEN];
+    RewardBalance::pack(
+        RewardBalance {
+            is_initialized: true,
+            player: player_one_account_keypair.pubkey(),
+            mint: other_mint,
+            reward_to_claim: 70,
+            total_earned: 70,
+            total_claimed: 0,
+            total_referral_earned: 0,
+        },
+        &mut reward_balance_data,
+    )
+    .unwrap();
+    preload_program_owned_account(
+        &mut context,
+        &reward_balance,
+        &reward_balance_data,
+        &program_id,
+    );
+    // Same reward balance data at an address which is not the PDA of the player and mint
+    let fake_reward_balance = Pubkey::new_unique();
+    preload_program_owned_account(
+        &mut context,
+        &fake_reward_balance,
+        &reward_balance_data,
+        &program_id,
+    );
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test freeze player by non admin
+    let transaction = build_freeze_player_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        false,
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test frozen player cannot receive or claim reward
+    let transaction = build_freeze_player_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        false,
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        50,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::PlayerFrozen as u32)
+        )
+    );
+
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &get_associated_token_address(
+            &player_one_holder_keypair.pubkey(),
+            &mint_account_keypair.pubkey(),
+            &spl_token::id(),
+        ),
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::PlayerFrozen as u32)
+        )
+    );
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.frozen, true);
+    assert_eq!(player_one_state.reward_to_claim, 100);
+    // End
+
+    // Test thawed player receive reward again
+    let transaction = build_thaw_player_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        60,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    // End
+
+    // Test forfeit a reward balance account which is not the PDA of the player and mint
+    let transaction = build_freeze_player_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        true,
+        &[fake_reward_balance],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPdaAccount as u32)
+        )
+    );
+    // End
+
+    // Test freeze player and forfeit the unclaimed reward
+    let transaction = build_freeze_player_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        true,
+        &[reward_balance],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.frozen, true);
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_forfeited, 160);
+    let account = banks_client
+        .get_account(reward_balance)
+        .await
+        .unwrap()
+        .unwrap();
+    let reward_balance_state = RewardBalance::unpack(&account.data).unwrap();
+    assert_eq!(reward_balance_state.reward_to_claim, 0);
+    // End
+
+    // Test frozen upline receive no registration fee share
+    let transaction = build_set_registration_fee_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        1000,
+        20,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    banks_client
+        .process_transaction(build_mint_transaction(
+            &payer,
+            &mint_account_keypair,
+            &player_two_token_account_keypair,
+            &admin_account_keypair,
+            1000,
+            recent_blockhash,
+        ))
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_with_fee_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        &player_two_token_account_keypair.pubkey(),
+        &token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    assert_eq!(player_one_state.total_referral_earned, 0);
+    assert_eq!(player_one_state.direct_referrals, 1);
+    // End
+
+    // Test frozen upline receive no commission, the commission is forfeited
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.reward_to_claim, 90);
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_forfeited, 170);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_distribution() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let leaf = merkle::hash_leaf(&[
+        &0_u64.to_le_bytes(),
+        player_one_account_keypair.pubkey().as_ref(),
+        &100_u64.to_le_bytes(),
+    ]);
+
+    // Test publish distribution by non admin
+    let transaction = build_publish_distribution_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        0,
+        leaf,
+        100,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test claim from unpublished distribution
+    let transaction = build_claim_from_distribution_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        0,
+        0,
+        100,
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+    // End
+
+    // Test claim with too long proof
+    let transaction = build_claim_from_distribution_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        0,
+        0,
+        100,
+        &[leaf; 33],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    // End
+}
+
+#[tokio::test]
+async fn distribution() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    let player_two_leaf = merkle::hash_leaf(&[
+        &0_u64.to_le_bytes(),
+        player_two_account_keypair.pubkey().as_ref(),
+        &1000_u64.to_le_bytes(),
+    ]);
+    let player_one_leaf = merkle::hash_leaf(&[
+        &1_u64.to_le_bytes(),
+        player_one_account_keypair.pubkey().as_ref(),
+        &500_u64.to_le_bytes(),
+    ]);
+    let root = merkle::hash_node(&player_two_leaf, &player_one_leaf);
+
+    // Distributions and their claimed bitmaps, normally created in BPF only
+    for (distribution_index, total) in [(0_u64, 1500), (1, 400)] {
+        let (distribution, _nonce) = Pubkey::find_program_address(
+            &[
+                "distribution".as_bytes(),
+                program_account_keypair.pubkey().as_ref(),
+                &distribution_index.to_le_bytes(),
+            ],
+            &program_id,
+        );
+        let mut distribution_data = vec![0; Distribution::LEN];
+        Distribution::pack(
+            Distribution {
+                is_initialized: true,
+                program_account: program_account_keypair.pubkey(),
+                root,
+                total,
+                claimed: 0,
+            },
+            &mut distribution_data,
+        )
+        .unwrap();
+        preload_program_owned_account(&mut context, &distribution, &distribution_data, &program_id);
+        let (bitmap, _nonce) = Pubkey::find_program_address(
+            &[
+                "claimed".as_bytes(),
+                distribution.as_ref(),
+                &0_u64.to_le_bytes(),
+            ],
+            &program_id,
+        );
+        preload_program_owned_account(
+            &mut context,
+            &bitmap,
+            &[0; DISTRIBUTION_BITMAP_LEN],
+            &program_id,
+        );
+    }
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    for (player_holder_keypair, player_account_keypair, upline_account_keypair) in [
+        (
+            &player_one_holder_keypair,
+            &player_one_account_keypair,
+            None,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_two_account_keypair,
+            Some(&player_one_account_keypair),
+        ),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            upline_account_keypair,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    // Player two pay the fee to resend the same claim
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &player_two_holder_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test claim with wrong amount
+    let transaction = build_claim_from_distribution_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        0,
+        0,
+        2000,
+        &[player_one_leaf],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidMerkleProof as u32)
+        )
+    );
+    // End
+
+    // Test claim with upline commission
+    let transaction = build_claim_from_distribution_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        0,
+        0,
+        1000,
+        &[player_one_leaf],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.reward_to_claim, 900);
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 100);
+    // End
+
+    // Test claim the same index again, player two pay the fee to resend the same claim
+    let transaction = build_claim_from_distribution_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        0,
+        0,
+        1000,
+        &[player_one_leaf],
+        program_id,
+        &player_two_holder_keypair,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::DistributionAlreadyClaimed as u32)
+        )
+    );
+    // End
+
+    // Test claim exceeding the distribution total
+    let transaction = build_claim_from_distribution_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        1,
+        1,
+        500,
+        &[player_two_leaf],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::DistributionExhausted as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn publish_distribution() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+
+    // Admin pay for the distribution creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test publish distribution
+    let transaction = build_publish_distribution_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        0,
+        [1; 32],
+        1500,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.distribution_count, 1);
+    // End
+}
+
+#[tokio::test]
+async fn redeem_voucher() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    // Nonces below VOUCHER_BITMAP_BITS share the first bitmap
+    preload_program_owned_account(
+        &mut context,
+        &find_voucher_bitmap_address(&player_two_account_keypair.pubkey(), 0, &program_id),
+        &[0; VOUCHER_BITMAP_LEN],
+        &program_id,
+    );
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    for (player_holder_keypair, player_account_keypair, upline_account_keypair) in [
+        (
+            &player_one_holder_keypair,
+            &player_one_account_keypair,
+            None,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_two_account_keypair,
+            Some(&player_one_account_keypair),
+        ),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            upline_account_keypair,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    // Player two pay the fee to resend the same voucher
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &player_two_holder_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let voucher = Voucher {
+        program_account: program_account_keypair.pubkey(),
+        player: player_two_account_keypair.pubkey(),
+        amount: 100,
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+
+    // Test redeem voucher without the Ed25519 instruction
+    let mut transaction = Transaction::new_with_payer(
+        &[Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(player_two_holder_keypair.pubkey(), true),
+                AccountMeta::new(program_account_keypair.pubkey(), false),
+                AccountMeta::new(player_two_account_keypair.pubkey(), false),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                AccountMeta::new(
+                    find_voucher_bitmap_address(
+                        &player_two_account_keypair.pubkey(),
+                        voucher.nonce,
+                        &program_id,
+                    ),
+                    false,
+                ),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(player_one_account_keypair.pubkey(), false),
+            ],
+            data: vec![12_u8], // Tag = 12
+        }],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &player_two_holder_keypair], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    // End
+
+    // Test redeem voucher with a signature of another message
+    let mut transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &voucher,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    // Increase the amount in the signed message, which is the last 24 bytes before nonce and expiry
+    let ed25519_data = &mut transaction.message.instructions[0].data;
+    let amount_offset = ed25519_data.len() - 24;
+    ed25519_data[amount_offset] = 0xff;
+    let mut transaction = Transaction::new_unsigned(transaction.message);
+    transaction.partial_sign(&[&payer, &player_two_holder_keypair], recent_blockhash);
+    let result = banks_client.process_transaction(transaction).await;
+    // Rejected by the Ed25519 precompile before the program is invoked
+    assert!(result.is_err());
+    // End
+
+    // Test redeem voucher signed by non-admin account
+    let transaction = build_redeem_voucher_transaction(
+        &player_one_holder_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &voucher,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::InvalidVoucher as u32)
+        )
+    );
+    // End
+
+    // Test redeem voucher issued for another player
+    let transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        &voucher,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::InvalidVoucher as u32)
+        )
+    );
+    // End
+
+    // Test redeem expired voucher
+    let transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &Voucher {
+            expiry: 0,
+            ..voucher.clone()
+        },
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::VoucherExpired as u32)
+        )
+    );
+    // End
+
+    // Test redeem voucher
+    let transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &voucher,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.reward_to_claim, 90);
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 10);
+    // End
+
+    // Test redeem the same voucher again
+    let transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &voucher,
+        program_id,
+        &player_two_holder_keypair,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(GameError::VoucherAlreadyRedeemed as u32)
+        )
+    );
+    // End
+
+    // Test redeem a voucher with a lower nonce after a higher one
+    let transaction = build_redeem_voucher_transaction(
+        &admin_account_keypair,
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &Voucher {
+            nonce: 0,
+            ..voucher.clone()
+        },
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.reward_to_claim, 180);
+    // End
+}
+
+#[tokio::test]
+async fn invalid_referral_code() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test claim referral code for another player
+    let transaction = build_claim_referral_code_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        b"alice",
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test claim empty referral code
+    let transaction = build_claim_referral_code_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        b"",
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidReferralCode as u32)
+        )
+    );
+    // End
+
+    // Test register with unclaimed referral code
+    let transaction = build_register_player_with_code_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        b"alice",
+        &player_one_account_keypair.pubkey(),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::ReferralCodeNotFound as u32)
+        )
+    );
+    // End
+}
+
+#[tokio::test]
+async fn referral_code() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    // Referral code of player one, normally created by ClaimReferralCode in BPF only
+    let (referral_code, _nonce) = Pubkey::find_program_address(
+        &[
+            "code".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            b"alice",
+        ],
+        &program_id,
+    );
+    let mut referral_code_data = vec![0; ReferralCode::LEN];
+    ReferralCode::pack(
+        ReferralCode {
+            is_initialized: true,
+            player: player_one_account_keypair.pubkey(),
+        },
+        &mut referral_code_data,
+    )
+    .unwrap();
+    preload_program_owned_account(
+        &mut context,
+        &referral_code,
+        &referral_code_data,
+        &program_id,
+    );
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test claim referral code longer than 16 bytes
+    let transaction = build_claim_referral_code_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        b"a_very_long_code_",
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidReferralCode as u32)
+        )
+    );
+    // End
+
+    // Test register with unknown referral code
+    let transaction = build_register_player_with_code_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        b"bob",
+        &player_one_account_keypair.pubkey(),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::ReferralCodeNotFound as u32)
+        )
+    );
+    // End
+
+    // Test register player two with the referral code of player one
+    let transaction = build_register_player_with_code_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        b"alice",
+        &player_one_account_keypair.pubkey(),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(
+        player_two_state.upline,
+        COption::Some(player_one_account_keypair.pubkey())
+    );
+    // End
+
+    // Test claim referral code already taken
+    let transaction = build_claim_referral_code_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        b"alice",
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::ReferralCodeAlreadyExists as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn claim_referral_code() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Player pay for the referral code creation
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &player_one_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test claim referral code
+    let transaction = build_claim_referral_code_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        b"alice",
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (referral_code, _nonce) = Pubkey::find_program_address(
+        &[
+            "code".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            b"alice",
+        ],
+        &program_id,
+    );
+    let referral_code_account = banks_client
+        .get_account(referral_code)
+        .await
+        .unwrap()
+        .unwrap();
+    let referral_code_state = ReferralCode::unpack(&referral_code_account.data).unwrap();
+    assert_eq!(
+        referral_code_state.player,
+        player_one_account_keypair.pubkey()
+    );
+    // End
+}
+
+#[tokio::test]
+async fn set_upline() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let player_three_holder_keypair = Keypair::new();
+    let player_three_account_keypair = Keypair::new();
+    for (player_account_keypair, other_player_account_keypair) in [
+        (&player_one_account_keypair, &player_two_account_keypair),
+        (&player_three_account_keypair, &Keypair::new()),
+    ] {
+        let create_player_account_transaction = build_create_player_account_transaction(
+            player_account_keypair,
+            other_player_account_keypair,
+            &payer,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client
+            .process_transaction(create_player_account_transaction)
+            .await
+            .unwrap();
+    }
+
+    // Register player one, player two under player one, and player three under player two
+    for (player_holder_keypair, player_account_keypair, upline_account_keypair) in [
+        (
+            &player_one_holder_keypair,
+            &player_one_account_keypair,
+            None,
+        ),
+        (
+            &player_two_holder_keypair,
+            &player_two_account_keypair,
+            Some(&player_one_account_keypair),
+        ),
+        (
+            &player_three_holder_keypair,
+            &player_three_account_keypair,
+            Some(&player_two_account_keypair),
+        ),
+    ] {
+        let transaction = build_register_player_transaction(
+            &payer,
+            player_holder_keypair,
+            player_account_keypair,
+            &program_account_keypair,
+            upline_account_keypair,
+            program_id,
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    // Test set upline of another player
+    let transaction = build_set_upline_transaction(
+        &player_three_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(player_one_account_keypair.pubkey()),
+        None,
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    // Test set upline to a downline of the player
+    let transaction = build_set_upline_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        Some(player_three_account_keypair.pubkey()),
+        &[player_two_account_keypair.pubkey()],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::UplineCycle as u32)
+        )
+    );
+    // End
+
+    // Test player two remove upline within the grace period
+    let transaction = build_set_upline_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(player_one_account_keypair.pubkey()),
+        None,
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(player_two_state.upline, COption::None);
+    assert_eq!(player_two_state.upline_changed, true);
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.direct_referrals, 0);
+    assert_eq!(player_one_state.downline_count, 1);
+    // End
+
+    // Test player two change upline again
+    let transaction = build_set_upline_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        None,
+        Some(player_one_account_keypair.pubkey()),
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::UplineChangeNotAllowed as u32)
+        )
+    );
+    // End
+
+    // Test admin set player one as upline of player two
+    let transaction = build_set_upline_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        None,
+        Some(player_one_account_keypair.pubkey()),
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_state = Player::unpack(&player_two_account.data).unwrap();
+    assert_eq!(
+        player_two_state.upline,
+        COption::Some(player_one_account_keypair.pubkey())
+    );
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.direct_referrals, 1);
+    assert_eq!(player_one_state.downline_count, 2);
+    // End
+
+    // Test admin set the same upline again, the referral counters are unchanged
+    let transaction = build_set_upline_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(player_one_account_keypair.pubkey()),
+        Some(player_one_account_keypair.pubkey()),
+        &[],
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.direct_referrals, 1);
+    assert_eq!(player_one_state.downline_count, 2);
+    // End
+}
+
+#[tokio::test]
+async fn max_direct_referrals() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let player_three_holder_keypair = Keypair::new();
+    let player_three_account_keypair = Keypair::new();
+    let player_four_holder_keypair = Keypair::new();
+    let player_four_account_keypair = Keypair::new();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_three_account_keypair,
+        &player_four_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Test set max direct referrals with non-admin account
+    let transaction = build_set_max_direct_referrals_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        Some(1),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert!(result.is_err());
+    // End
+
+    // Test set max direct referrals
+    let transaction = build_set_max_direct_referrals_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        Some(1),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.max_direct_referrals, COption::Some(1));
+    // End
+
+    // Register player one, then player two with player one as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test register player three with player one as upline, over the limit
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_three_holder_keypair,
+        &player_three_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::DirectReferralLimitReached as u32)
+        )
+    );
+    // End
+
+    // Test register player four after the limit is removed
+    let transaction = build_set_max_direct_referrals_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_four_holder_keypair,
+        &player_four_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.direct_referrals, 2);
+    assert_eq!(player_one_state.downline_count, 2);
+    // End
+}
+
+#[test]
+fn decode_events_from_log_messages() {
+    let program_id = Pubkey::new_unique();
+    let reward_added = GameEvent::RewardAdded {
+        program_account: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        player_program_account: Pubkey::new_unique(),
+        upline: Some(Pubkey::new_unique()),
+        mint: None,
+        player_amount: 90,
+        upline_amount: 10,
+    };
+    let reward_claimed = GameEvent::RewardClaimed {
+        program_account: Pubkey::new_unique(),
+        player_program_account: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        mint: Some(Pubkey::new_unique()),
+        amount: 90,
+    };
+    let log_messages = vec![
+        format!("Program {} invoke [1]", program_id),
+        "Program log: Instruction tag 2".to_string(),
+        format!(
+            "Program data: {}",
+            base64::encode(reward_added.try_to_vec().unwrap())
+        ),
+        format!(
+            "Program {} consumed 2000 of 200000 compute units",
+            program_id
+        ),
+        format!("Program {} success", program_id),
+        format!("Program {} invoke [1]", program_id),
+        format!("Program {} invoke [2]", spl_token::id()),
+        // Logged by the CPI callee, not an event of the program
+        "Program data: AQID".to_string(),
+        format!("Program {} success", spl_token::id()),
+        format!(
+            "Program data: {}",
+            base64::encode(reward_claimed.try_to_vec().unwrap())
+        ),
+        format!("Program {} success", program_id),
+        // Logged by other program
+        format!("Program {} invoke [1]", Pubkey::new_unique()),
+        "Program data: AQID".to_string(),
+        format!(
+            "Program {} failed: custom program error: 0x0",
+            Pubkey::new_unique()
+        ),
+    ];
+
+    let events = decode_events(&program_id, &log_messages).unwrap();
+    assert_eq!(events, vec![reward_added, reward_claimed]);
+
+    // Program data which is not an event
+    let log_messages = vec![
+        format!("Program {} invoke [1]", program_id),
+        "Program data: AQID".to_string(),
+        format!("Program {} success", program_id),
+    ];
+    assert!(decode_events(&program_id, &log_messages).is_err());
+}
+
+#[tokio::test]
+async fn lamport_payout() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+
+    // Admin pay for the lamport vault creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &admin_account_keypair.pubkey(),
+            1_000_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_init_lamports_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_state.is_initialized, true);
+    assert_eq!(&program_state.admin, &admin_account_keypair.pubkey());
+    assert_eq!(program_state.payout_mode, PayoutMode::Lamports);
+
+    // Test initialize token payout after lamport payout
+    let transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::AlreadyInitialize as u32)
+        )
+    );
+    // End
+
+    let transaction = build_deposit_transaction(
+        &payer,
+        &program_account_keypair,
+        program_id,
+        2_000_000_000,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        1_000_000_000,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        None,
+        program_id,
+        5_000_000_000,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    // Test claim more than the lamport vault balance
+    let transaction = build_claim_lamports_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InsufficientVaultBalance as u32)
+        )
+    );
+    // End
+
+    // Test player one claim reward
+    let transaction = build_claim_lamports_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_balance = banks_client
+        .get_balance(player_one_holder_keypair.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(player_one_balance, 1_000_000_000);
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 0);
+    assert_eq!(player_one_state.total_claimed, 1_000_000_000);
+    // Lamports are paid to the player holder account, there is no payout token account
+    assert_eq!(player_one_state.payout_token_account, Pubkey::default());
+
+    let (lamport_vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "lamport_vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let lamport_vault_balance = banks_client.get_balance(lamport_vault).await.unwrap();
+    assert_eq!(
+        lamport_vault_balance,
+        Rent::default().minimum_balance(0) + 1_000_000_000
+    );
+
+    // Another game paying in lamports, its admin register a player and reward it without any deposit
+    let other_admin_keypair = Keypair::new();
+    let other_program_account_keypair = Keypair::new();
+    let other_player_account_keypair = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &other_program_account_keypair.pubkey(),
+                Rent::default().minimum_balance(GameInfo::LEN),
+                GameInfo::LEN.try_into().unwrap(),
+                &program_id,
+            ),
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &other_player_account_keypair.pubkey(),
+                Rent::default().minimum_balance(Player::LEN),
+                Player::LEN.try_into().unwrap(),
+                &program_id,
+            ),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &other_admin_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(
+        &[
+            &payer,
+            &other_program_account_keypair,
+            &other_player_account_keypair,
+        ],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_init_lamports_transaction(
+        &other_admin_keypair,
+        &other_program_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &other_player_account_keypair,
+        &other_program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_add_reward_transaction(
+        &other_admin_keypair,
+        &other_program_account_keypair,
+        &other_player_account_keypair,
+        None,
+        program_id,
+        1_000_000_000,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test claim the reward of the other game from the lamport vault of this game
+    let transaction = build_claim_lamports_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &other_player_account_keypair,
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::PlayerGameMismatch as u32)
+        )
+    );
+    let lamport_vault_balance = banks_client.get_balance(lamport_vault).await.unwrap();
+    assert_eq!(
+        lamport_vault_balance,
+        Rent::default().minimum_balance(0) + 1_000_000_000
+    );
+    // End
+}
+
+#[tokio::test]
+async fn multiple_reward_vaults() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut context,
+    ) = setup_with_context().await;
+    // Reward vault and reward balances of the second mint, normally created by RegisterVault and AddReward in BPF only
+    let second_mint_keypair = Keypair::new();
+    let (vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            second_mint_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    preload_token_account(&mut context, &vault, &second_mint_keypair.pubkey(), &pda);
+    for player_account in [
+        player_one_account_keypair.pubkey(),
+        player_two_account_keypair.pubkey(),
+    ] {
+        let (reward_balance, _nonce) = Pubkey::find_program_address(
+            &[
+                "balance".as_bytes(),
+                player_account.as_ref(),
+                second_mint_keypair.pubkey().as_ref(),
+            ],
+            &program_id,
+        );
+        preload_program_owned_account(
+            &mut context,
+            &reward_balance,
+            &[0; RewardBalance::LEN],
+            &program_id,
+        );
+    }
+    let mut banks_client = context.banks_client;
+    let payer = context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Second mint and the associated token account of player two, which would be created by CPI when claiming
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &second_mint_keypair.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN.try_into().unwrap(),
+                &spl_token::id(),
+            ),
+            initialize_mint(
+                &spl_token::id(),
+                &second_mint_keypair.pubkey(),
+                &admin_account_keypair.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                &second_mint_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &second_mint_keypair], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test register the reward vault which already exists
+    let transaction = build_register_vault_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::VaultAlreadyExists as u32)
+        )
+    );
+    // End
+
+    // Fund the reward vault
+    let mut transaction = Transaction::new_with_payer(
+        &[mint_to(
+            &spl_token::id(),
+            &second_mint_keypair.pubkey(),
+            &vault,
+            &admin_account_keypair.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &admin_account_keypair], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test add reward to player two in the second mint, upline get 10%
+    let transaction = build_add_mint_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &second_mint_keypair.pubkey(),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for (player_account, amount) in [
+        (player_one_account_keypair.pubkey(), 10),
+        (player_two_account_keypair.pubkey(), 90),
+    ] {
+        let (reward_balance, _nonce) = Pubkey::find_program_address(
+            &[
+                "balance".as_bytes(),
+                player_account.as_ref(),
+                second_mint_keypair.pubkey().as_ref(),
+            ],
+            &program_id,
+        );
+        let account = banks_client
+            .get_account(reward_balance)
+            .await
+            .unwrap()
+            .unwrap();
+        let reward_balance_state =
+            learn_solana::state::RewardBalance::unpack(&account.data).unwrap();
+        assert_eq!(reward_balance_state.player, player_account);
+        assert_eq!(reward_balance_state.mint, second_mint_keypair.pubkey());
+        assert_eq!(reward_balance_state.reward_to_claim, amount);
+        assert_eq!(reward_balance_state.total_earned, amount);
+
+        // Reward in the mint recorded during initialization is untouched
+        let account = banks_client
+            .get_account(player_account)
+            .await
+            .unwrap()
+            .unwrap();
+        let player_state = Player::unpack(&account.data).unwrap();
+        assert_eq!(player_state.reward_to_claim, 0);
+    }
+
+    // Test add reward in the second mint by an operator capped in the game mint
+    let operator_keypair = Keypair::new();
+    let transaction = build_add_operator_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &operator_keypair.pubkey(),
+        Some(1000),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_add_mint_reward_transaction(
+        &operator_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        &second_mint_keypair.pubkey(),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::OperatorRewardCapExceeded as u32)
+        )
+    );
+    // End
+
+    // Test claim reward of the mint recorded during initialization
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &token_account_keypair,
+        pda,
+        &get_associated_token_address(
+            &player_two_holder_keypair.pubkey(),
+            &mint_account_keypair.pubkey(),
+            &spl_token::id(),
+        ),
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::UnclaimableAmount as u32)
+        )
+    );
+    // End
+
+    // Test player two claim reward of the second mint
+    let transaction = build_claim_mint_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_two_associated_token_account = get_associated_token_address(
+        &player_two_holder_keypair.pubkey(),
+        &second_mint_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    let account = banks_client
+        .get_account(player_two_associated_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let player_two_token_account_state = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(player_two_token_account_state.amount, 90);
+
+    // Test claim again after the reward balance reset, player two pay the fee so it's not the same transaction
+    let transaction = build_claim_mint_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &player_two_holder_keypair,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::UnclaimableAmount as u32)
+        )
+    );
+    // End
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn create_reward_vault() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+
+    // Second mint, admin pay for the reward vault
+    let second_mint_keypair = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &second_mint_keypair.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN.try_into().unwrap(),
+                &spl_token::id(),
+            ),
+            initialize_mint(
+                &spl_token::id(),
+                &second_mint_keypair.pubkey(),
+                &admin_account_keypair.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &admin_account_keypair.pubkey(),
+                1_000_000_000,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &second_mint_keypair], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test register reward vault for the second mint
+    let transaction = build_register_vault_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            second_mint_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let vault_account = banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault_state = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+    assert_eq!(vault_state.mint, second_mint_keypair.pubkey());
+    assert_eq!(vault_state.owner, pda);
+
+    // End
+}
+
+#[tokio::test]
+async fn register_vault() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let second_mint_keypair = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &second_mint_keypair.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN.try_into().unwrap(),
+                &spl_token::id(),
+            ),
+            initialize_mint(
+                &spl_token::id(),
+                &second_mint_keypair.pubkey(),
+                &admin_account_keypair.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &second_mint_keypair], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test add reward in mint without reward vault
+    let transaction = build_add_mint_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::VaultNotFound as u32)
+        )
+    );
+    // End
+
+    // Test claim reward in mint without reward balance
+    let transaction = build_claim_mint_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::UnclaimableAmount as u32)
+        )
+    );
+    // End
+
+    // Test register reward vault for the mint recorded during initialization
+    let transaction = build_register_vault_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::VaultAlreadyExists as u32)
+        )
+    );
+    // End
+
+    // Test register reward vault by non admin
+    let transaction = build_register_vault_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &second_mint_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+}
+
+#[tokio::test]
+async fn operator_add_reward() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Register player one
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let operator_keypair = Keypair::new();
+
+    // Test add operator with non-admin account
+    let transaction = build_add_operator_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &operator_keypair.pubkey(),
+        Some(150),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert!(result.is_err());
+    // End
+
+    // Test add operator with reward cap
+    let transaction = build_add_operator_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &operator_keypair.pubkey(),
+        Some(150),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_state = GameInfo::unpack(&program_account.data).unwrap();
+    let slot = program_state
+        .find_operator(&operator_keypair.pubkey())
+        .unwrap();
+    assert_eq!(program_state.operators[slot].reward_cap, COption::Some(150));
+
+    // Test operator add reward within cap
+    let add_reward_transaction = build_add_reward_transaction(
+        &operator_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    // Test operator add reward exceeding cap
+    let add_reward_transaction = build_add_reward_transaction(
+        &operator_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        51,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::OperatorRewardCapExceeded as u32)
+        )
+    );
+    // End
+
+    // Test remove operator, then operator can no longer add reward
+    let transaction = build_remove_operator_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &operator_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let add_reward_transaction = build_add_reward_transaction(
+        &operator_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        10,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    // End
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.reward_to_claim, 100);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn claim_reward_create_associated_token_account() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    // Player one pay for the associated token account creation
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &player_one_holder_keypair.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Account::LEN),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let player_one_associated_token_account = get_associated_token_address(
+        &player_one_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_token_account = banks_client
+        .get_account(player_one_associated_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_token_account_state =
+        spl_token::state::Account::unpack(&player_one_token_account.data).unwrap();
+    assert_eq!(player_one_token_account_state.amount, 100);
+}
+
+#[tokio::test]
+async fn claim_reward() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Register player one
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Register player two, with player one as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test add reward to player two with admin
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    // Test add reward to player one
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let player_one_associated_token_account = get_associated_token_address(
+        &player_one_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    let player_two_associated_token_account = get_associated_token_address(
+        &player_two_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    // Test claim other player reward
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair, // Claim player two reward using player one signature
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    match result {
+        Ok(()) => {}
+        Err(e) => {
+            assert_eq!(e.to_string().contains("missing required signature"), true);
+        }
+    };
+
+    // Test claim reward to token account which is not the associated token account
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_token_account_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::InvalidPlayerTokenAccount as u32)
+        )
+    );
+    // End
+
+    // Test claim reward with mint which is not the program mint
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &Keypair::new().pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::MintMismatch as u32)
+        )
+    );
+    // End
+
+    // Test claim reward to frozen token account
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_associated_token_account(
+                &payer.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &spl_token::id(),
+            ),
+            freeze_account(
+                &spl_token::id(),
+                &player_two_associated_token_account,
+                &mint_account_keypair.pubkey(),
+                &admin_account_keypair.pubkey(),
+                &[],
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer, &admin_account_keypair], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_two_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(GameError::TokenAccountFrozen as u32)
+        )
+    );
+    // End
+
+    // Account data can't be resized by CPI in native mode, see claim_reward_create_associated_token_account
+    let mut transaction = Transaction::new_with_payer(
+        &[create_associated_token_account(
+            &payer.pubkey(),
+            &player_one_holder_keypair.pubkey(),
+            &mint_account_keypair.pubkey(),
+            &spl_token::id(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test player one claim reward
+    let transaction = build_claim_reward_transaction(
+        &player_one_holder_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_one_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match player_one_account {
+        Some(account) => {
+            let player_one_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_one_state.reward_to_claim, 0);
+            assert_eq!(player_one_state.total_claimed, 110);
+        }
+        _ => {} // Unreachable
+    };
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_claimed, 110);
+
+    let player_one_token_account = banks_client
+        .get_account(player_one_associated_token_account)
+        .await
+        .unwrap();
+    match player_one_token_account {
+        Some(account) => {
+            let player_one_token_account_state =
+                spl_token::state::Account::unpack(&account.data).unwrap();
+            assert_eq!(player_one_token_account_state.amount, 110); // 100 + 10% from player two
+        }
+        _ => {
+            panic!("Player one associated token account not found");
+        }
+    }
+}
+
+#[tokio::test]
+async fn player_game_mismatch() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Player two holder create another game on the same mint, and register player two in it
+    let other_program_account_keypair = Keypair::new();
+    let other_token_account_keypair = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &other_program_account_keypair.pubkey(),
+                Rent::default().minimum_balance(GameInfo::LEN),
+                GameInfo::LEN.try_into().unwrap(),
+                &program_id,
+            ),
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &other_token_account_keypair.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN.try_into().unwrap(),
+                &spl_token::id(),
+            ),
+            initialize_account(
+                &spl_token::id(),
+                &other_token_account_keypair.pubkey(),
+                &mint_account_keypair.pubkey(),
+                &player_two_holder_keypair.pubkey(),
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.partial_sign(
+        &[
+            &payer,
+            &other_program_account_keypair,
+            &other_token_account_keypair,
+        ],
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_init_instruction_transaction(
+        &player_two_holder_keypair,
+        &other_program_account_keypair,
+        &other_token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &other_program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Reward added by the admin of the other game, without any token in its vault
+    let transaction = build_add_reward_transaction(
+        &player_two_holder_keypair,
+        &other_program_account_keypair,
+        &player_two_account_keypair,
+        None,
+        program_id,
+        1000,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let player_game_mismatch = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(GameError::PlayerGameMismatch as u32),
+    );
+
+    // Test claim the reward of the other game from the vault of this game
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let player_two_associated_token_account = get_associated_token_address(
+        &player_two_holder_keypair.pubkey(),
+        &mint_account_keypair.pubkey(),
+        &spl_token::id(),
+    );
+    let transaction = build_claim_reward_transaction(
+        &player_two_holder_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        &token_account_keypair,
+        pda,
+        &player_two_associated_token_account,
+        &mint_account_keypair.pubkey(),
+        program_id,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(result.unwrap_err().unwrap(), player_game_mismatch);
+    // End
+
+    // Test add reward to the player of the other game
+    let transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(result.unwrap_err().unwrap(), player_game_mismatch);
+    // End
+
+    // Test register with the player of the other game as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        Some(&player_two_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    assert_eq!(result.unwrap_err().unwrap(), player_game_mismatch);
+    // End
+
+    let token_account = banks_client
+        .get_account(token_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let token_account_state = spl_token::state::Account::unpack(&token_account.data).unwrap();
+    assert_eq!(token_account_state.amount, 1000000000000);
+}
+
+#[tokio::test]
+async fn add_reward() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+    // Register player one
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Register player two, with player one as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Test add reward with non-admin account
+    let fake_admin_account_keypair = Keypair::new();
+    let add_reward_transaction = build_add_reward_transaction(
+        &fake_admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(
+                error.to_string().contains("missing required signature"),
+                true
+            );
+        }
+    };
+    // End
+
+    // Test add reward with invalid player account
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &Keypair::new(),
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    };
+    // End
+
+    // Test add reward with non-exists upline account
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&Keypair::new()),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    };
+    // End
+
+    // Test add reward to invalid upline account
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_two_account_keypair),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(add_reward_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("custom program error"), true);
+        }
+    };
+    // End
+
+    // Test add reward to player two with admin
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_two_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match player_one_account {
+        Some(account) => {
+            let player_one_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_one_state.reward_to_claim, 10); // 10% from player two
+        }
+        _ => {} // Unreachable
+    };
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match player_two_account {
+        Some(account) => {
+            let player_two_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_two_state.reward_to_claim, 90);
+        }
+        _ => {} // Unreachable
+    };
+
+    // Test add reward to player one
+    let add_reward_transaction = build_add_reward_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &player_one_account_keypair,
+        None,
+        program_id,
+        100,
+        &payer,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(add_reward_transaction)
+        .await
+        .unwrap();
+
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match player_one_account {
+        Some(account) => {
+            let player_one_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_one_state.reward_to_claim, 110); // 100 + 10% from player two
+            assert_eq!(player_one_state.total_earned, 110);
+            assert_eq!(player_one_state.total_referral_earned, 10);
+        }
+        _ => {} // Unreachable
+    };
+
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.total_awarded, 200);
+    assert_eq!(program_account_state.total_claimed, 0);
+}
+
+#[tokio::test]
+async fn register_player() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    let create_player_account_transaction = build_create_player_account_transaction(
+        &player_one_account_keypair,
+        &player_two_account_keypair,
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(create_player_account_transaction)
+        .await
+        .unwrap();
+
+    // Test register player with invalid program id
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &Keypair::new(),
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    };
+    // End
+
+    // Test register player with invalid program account
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &Keypair::new(),
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    };
+    // End
+
+    // Test register player with invalid upline account
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        Some(&Keypair::new()),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    };
+    // End
+
+    // Test register self as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("custom program error"), true);
+            // Why decode custom error not working?
+        }
+    };
+    // End
+
+    // Test register player
+    // Register player one
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_one_holder_keypair,
+        &player_one_account_keypair,
+        &program_account_keypair,
+        None,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match player_one_account {
+        Some(account) => {
+            let player_one_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_one_state.is_initialized, true);
+            assert_eq!(player_one_state.owner, player_one_holder_keypair.pubkey());
+            assert_eq!(player_one_state.reward_to_claim, 0);
+            assert_eq!(player_one_state.upline, COption::None);
+        }
+        _ => {
+            panic!("Player one account not found");
+        }
+    };
+
+    // Register player two, with player one as upline
+    let transaction = build_register_player_transaction(
+        &payer,
+        &player_two_holder_keypair,
+        &player_two_account_keypair,
+        &program_account_keypair,
+        Some(&player_one_account_keypair),
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client.process_transaction(transaction).await;
+
+    let player_two_account = banks_client
+        .get_account(player_two_account_keypair.pubkey())
+        .await
+        .unwrap();
+
+    match player_two_account {
+        Some(account) => {
+            let player_two_state = Player::unpack(&account.data).unwrap();
+            assert_eq!(player_two_state.is_initialized, true);
+            assert_eq!(player_two_state.owner, player_two_holder_keypair.pubkey());
+            assert_eq!(player_two_state.reward_to_claim, 0);
+            assert_eq!(
+                player_two_state.upline,
+                COption::Some(player_one_account_keypair.pubkey())
+            );
+        }
+        _ => {
+            panic!("Player two account not found");
+        }
+    };
+
+    // Player one count player two as downline, and both are counted by the program account
+    let player_one_account = banks_client
+        .get_account(player_one_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let player_one_state = Player::unpack(&player_one_account.data).unwrap();
+    assert_eq!(player_one_state.downline_count, 1);
+    assert_eq!(player_one_state.direct_referrals, 1);
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let program_account_state = GameInfo::unpack(&program_account.data).unwrap();
+    assert_eq!(program_account_state.player_count, 2);
+}
+
+#[tokio::test]
+async fn init_instruction() {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        mut banks_client,
+        payer,
+        recent_blockhash,
+    ) = setup().await;
+
+    // Test init with invalid program_account
+    let invalid_program_account_keypair = Keypair::new();
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &invalid_program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(init_instruction_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(error.to_string().contains("incorrect program id"), true);
+        }
+    }
+    // End
+
+    // Test init with invalid token account
+    let invalid_token_account_keypair = Keypair::new();
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &invalid_token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(init_instruction_transaction)
+        .await;
+    match result {
+        Ok(()) => {}
+        Err(error) => {
+            assert_eq!(
+                error.to_string().contains("Provided owner is not allowed"),
+                true
+            );
+        }
+    }
+    // End
+
+    // Test init with mint which is not the token account mint
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &Keypair::new().pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    let result = banks_client
+        .process_transaction(init_instruction_transaction)
+        .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+    // End
+
+    // Test init instruction
+    let init_instruction_transaction = build_init_instruction_transaction(
+        &admin_account_keypair,
+        &program_account_keypair,
+        &token_account_keypair,
+        &mint_account_keypair.pubkey(),
+        &payer,
+        program_id,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(init_instruction_transaction)
+        .await
+        .unwrap();
+    // It should successfully execute init instruction
+    let program_account = banks_client
+        .get_account(program_account_keypair.pubkey())
+        .await
+        .unwrap();
+    match program_account {
+        Some(account) => {
+            let program_state = GameInfo::unpack(&account.data).unwrap();
+            assert_eq!(program_state.is_initialized, true);
+            assert_eq!(&program_state.admin, &admin_account_keypair.pubkey());
+            assert_eq!(
+                &program_state.spl_token_account,
+                &token_account_keypair.pubkey()
+            );
+            assert_eq!(&program_state.token_program, &spl_token::id());
+            assert_eq!(&program_state.mint, &mint_account_keypair.pubkey());
+            assert_eq!(program_state.decimals, 9);
+            assert_eq!(program_state.payout_mode, PayoutMode::Token);
+        }
+        _ => {
+            panic!("Program account not found");
+        }
+    };
+}
+
+fn build_relay_claim_transaction(
+    authorization_signer_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    token_account_keypair: &Keypair,
+    pda: Pubkey,
+    player_token_account: &Pubkey,
+    mint: &Pubkey,
+    authorization: &ClaimAuthorization,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let relay_claim_instruction = [
+        build_ed25519_instruction(
+            authorization_signer_keypair,
+            &authorization.try_to_vec().unwrap(),
+        ),
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(program_account_keypair.pubkey(), false),
+                AccountMeta::new(player_account_keypair.pubkey(), false),
+                AccountMeta::new(token_account_keypair.pubkey(), false),
+                AccountMeta::new_readonly(pda, false),
+                AccountMeta::new(*player_token_account, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            ],
+            data: vec![40_u8], // Tag = 40
+        },
+    ];
+    // Only the relayer sign the transaction, the player signature is in the Ed25519 instruction
+    let mut transaction =
+        Transaction::new_with_payer(&relay_claim_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer], recent_blockhash);
+    transaction
+}
+
+fn build_set_claim_delegate_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    delegate: Option<&Pubkey>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_claim_delegate_data = vec![39_u8]; // Tag = 39
+    match delegate {
+        Some(delegate) => {
+            set_claim_delegate_data.push(1);
+            set_claim_delegate_data.extend_from_slice(delegate.as_ref());
+        }
+        None => set_claim_delegate_data.push(0),
+    }
+    let set_claim_delegate_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+        ],
+        data: set_claim_delegate_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_claim_delegate_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_transfer_reward_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    recipient_account_keypair: &Keypair,
+    amount: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut transfer_reward_data = vec![38_u8]; // Tag = 38
+    transfer_reward_data.extend_from_slice(&amount.to_le_bytes());
+    let transfer_reward_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(recipient_account_keypair.pubkey(), false),
+        ],
+        data: transfer_reward_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&transfer_reward_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_transfer_fee_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    fee_percent: u8,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let set_transfer_fee_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: vec![37_u8, fee_percent], // Tag = 37
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_transfer_fee_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_check_in_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    upline_account_keypair: Option<&Keypair>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
+    }
+    let check_in_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: vec![36_u8], // Tag = 36
+    }];
+    let mut transaction = Transaction::new_with_payer(&check_in_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_check_in_schedule_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    schedule: &[u64],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_check_in_schedule_data = vec![35_u8]; // Tag = 35
+    set_check_in_schedule_data.extend_from_slice(&schedule.to_vec().try_to_vec().unwrap());
+    let set_check_in_schedule_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: set_check_in_schedule_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_check_in_schedule_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn find_loot_table_address(program_account: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &["loot_table".as_bytes(), program_account.as_ref()],
+        program_id,
+    )
+    .0
+}
+
+fn find_loot_box_address(player_account: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&["box".as_bytes(), player_account.as_ref()], program_id).0
+}
+
+fn build_reveal_box_transaction(
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let reveal_box_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_loot_box_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+        data: vec![34_u8], // Tag = 34
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&reveal_box_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer], recent_blockhash);
+    transaction
+}
+
+fn build_open_box_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let open_box_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(
+                find_loot_table_address(&program_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new(
+                find_loot_box_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: vec![33_u8], // Tag = 33
+    }];
+    let mut transaction = Transaction::new_with_payer(&open_box_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_loot_table_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    price: u64,
+    tiers: &[LootTier],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_loot_table_data = vec![32_u8]; // Tag = 32
+    set_loot_table_data.extend_from_slice(&price.to_le_bytes());
+    set_loot_table_data.extend_from_slice(&tiers.to_vec().try_to_vec().unwrap());
+    let set_loot_table_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_loot_table_address(&program_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: set_loot_table_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_loot_table_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn find_flip_address(player_account: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&["flip".as_bytes(), player_account.as_ref()], program_id).0
+}
+
+fn build_resolve_flip_timeout_transaction(
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let resolve_flip_timeout_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_flip_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+        ],
+        data: vec![31_u8], // Tag = 31
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&resolve_flip_timeout_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer], recent_blockhash);
+    transaction
+}
+
+fn build_reveal_flip_transaction(
+    revealer_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    secret: [u8; 32],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut reveal_flip_data = vec![30_u8]; // Tag = 30
+    reveal_flip_data.extend_from_slice(&secret);
+    let reveal_flip_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(revealer_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_flip_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+        ],
+        data: reveal_flip_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&reveal_flip_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, revealer_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_house_commit_flip_transaction(
+    house_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    commitment: [u8; 32],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut house_commit_flip_data = vec![29_u8]; // Tag = 29
+    house_commit_flip_data.extend_from_slice(&commitment);
+    let house_commit_flip_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(house_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_flip_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+        ],
+        data: house_commit_flip_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&house_commit_flip_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, house_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_commit_flip_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    wager: u64,
+    commitment: [u8; 32],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut commit_flip_data = vec![28_u8]; // Tag = 28
+    commit_flip_data.extend_from_slice(&wager.to_le_bytes());
+    commit_flip_data.extend_from_slice(&commitment);
+    let commit_flip_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_flip_address(&player_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: commit_flip_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&commit_flip_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_complete_quest_transaction(
+    authority_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    quest_id: u64,
+    player_account_keypair: &Keypair,
+    upline_account_keypair: Option<&Keypair>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut complete_quest_data = vec![27_u8]; // Tag = 27
+    complete_quest_data.extend_from_slice(&quest_id.to_le_bytes());
+    let (quest, _nonce) = Pubkey::find_program_address(
+        &[
+            "quest".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &quest_id.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let (quest_bitmap, _nonce) = Pubkey::find_program_address(
+        &[
+            "quests".as_bytes(),
+            player_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(authority_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(quest, false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+        AccountMeta::new(quest_bitmap, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
+    }
+    let complete_quest_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: complete_quest_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&complete_quest_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, authority_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_create_quest_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    quest_id: u64,
+    reward: u64,
+    max_completions: Option<u64>,
+    expiry: Option<i64>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut create_quest_data = vec![26_u8]; // Tag = 26
+    create_quest_data.extend_from_slice(&reward.to_le_bytes());
+    create_quest_data.extend_from_slice(&max_completions.try_to_vec().unwrap());
+    create_quest_data.extend_from_slice(&expiry.try_to_vec().unwrap());
+    let (quest, _nonce) = Pubkey::find_program_address(
+        &[
+            "quest".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &quest_id.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let create_quest_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(quest, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: create_quest_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&create_quest_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn find_stake_vault_address(program_account: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&["stake".as_bytes(), program_account.as_ref()], program_id).0
+}
+
+fn build_unstake_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    player_token_account: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut unstake_data = vec![25_u8]; // Tag = 25
+    unstake_data.extend_from_slice(&amount.to_le_bytes());
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let unstake_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(*player_token_account, false),
+            AccountMeta::new(
+                find_stake_vault_address(&program_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: unstake_data,
+    }];
+    let mut transaction = Transaction::new_with_payer(&unstake_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_stake_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    player_token_account: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut stake_data = vec![24_u8]; // Tag = 24
+    stake_data.extend_from_slice(&amount.to_le_bytes());
+    let stake_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(*player_token_account, false),
+            AccountMeta::new(
+                find_stake_vault_address(&program_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: stake_data,
+    }];
+    let mut transaction = Transaction::new_with_payer(&stake_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_stake_reward_rate_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    stake_reward_rate: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_stake_reward_rate_data = vec![23_u8]; // Tag = 23
+    set_stake_reward_rate_data.extend_from_slice(&stake_reward_rate.to_le_bytes());
+    let set_stake_reward_rate_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: set_stake_reward_rate_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_stake_reward_rate_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_init_stake_vault_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    mint: &Pubkey,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let init_stake_vault_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_stake_vault_address(&program_account_keypair.pubkey(), &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: vec![22_u8], // Tag = 22
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&init_stake_vault_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn find_season_address(program_account: &Pubkey, season_index: u64, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            "season".as_bytes(),
+            program_account.as_ref(),
+            &season_index.to_le_bytes(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+fn build_finalize_season_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    season_index: u64,
+    winners: &[Pubkey],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(
+            find_season_address(&program_account_keypair.pubkey(), season_index, &program_id),
+            false,
+        ),
+    ];
+    for winner in winners {
+        accounts.push(AccountMeta::new(*winner, false));
+    }
+    let finalize_season_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: vec![21_u8], // Tag = 21
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&finalize_season_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_submit_score_transaction(
+    authority_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    season_index: u64,
+    player_account_keypair: &Keypair,
+    points: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut submit_score_data = vec![20_u8]; // Tag = 20
+    submit_score_data.extend_from_slice(&points.to_le_bytes());
+    let season = find_season_address(&program_account_keypair.pubkey(), season_index, &program_id);
+    let (season_score, _nonce) = Pubkey::find_program_address(
+        &[
+            "score".as_bytes(),
+            season.as_ref(),
+            player_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let submit_score_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(authority_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(season, false),
+            AccountMeta::new_readonly(player_account_keypair.pubkey(), false),
+            AccountMeta::new(season_score, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: submit_score_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&submit_score_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, authority_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_create_season_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    season_index: u64,
+    start: i64,
+    end: i64,
+    prizes: &[u64],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut create_season_data = vec![19_u8]; // Tag = 19
+    create_season_data.extend_from_slice(&start.to_le_bytes());
+    create_season_data.extend_from_slice(&end.to_le_bytes());
+    create_season_data.extend_from_slice(&prizes.to_vec().try_to_vec().unwrap());
+    let create_season_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(
+                find_season_address(&program_account_keypair.pubkey(), season_index, &program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: create_season_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&create_season_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_register_player_with_fee_transaction(
+    payer: &Keypair,
+    player_holder_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_token_account: &Pubkey,
+    program_token_account: &Pubkey,
+    mint: &Pubkey,
+    upline_account_keypair: Option<&Keypair>,
+    program_id: Pubkey,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(*player_token_account, false),
+        AccountMeta::new(*program_token_account, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
+    }
+    let register_player_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: vec![1_u8], // Tag 1
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&register_player_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_registration_fee_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    registration_fee: u64,
+    upline_percent: u8,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_registration_fee_data = vec![18_u8]; // Tag = 18
+    set_registration_fee_data.extend_from_slice(&registration_fee.to_le_bytes());
+    set_registration_fee_data.push(upline_percent);
+    let set_registration_fee_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: set_registration_fee_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_registration_fee_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_register_player_with_gate_transaction(
+    payer: &Keypair,
+    player_holder_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    admin_account_keypair: Option<&Keypair>,
+    allowlist_proof: &[[u8; 32]],
+    program_id: Pubkey,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut register_player_data = vec![1_u8, 0]; // Tag 1, referral code = None
+    register_player_data.extend_from_slice(&allowlist_proof.to_vec().try_to_vec().unwrap());
+    let mut accounts = vec![
+        AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+    ];
+    let mut signers = vec![payer, player_holder_keypair];
+    if let Some(admin_account_keypair) = admin_account_keypair {
+        accounts.push(AccountMeta::new_readonly(
+            admin_account_keypair.pubkey(),
+            true,
+        ));
+        signers.push(admin_account_keypair);
+    }
+    let register_player_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: register_player_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&register_player_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&signers, recent_blockhash);
+    transaction
+}
+
+fn build_set_registration_gate_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    registration_gate: RegistrationGate,
+    allowlist_root: [u8; 32],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_registration_gate_data = vec![17_u8, registration_gate as u8]; // Tag = 17
+    if registration_gate == RegistrationGate::Allowlist {
+        set_registration_gate_data.extend_from_slice(&allowlist_root);
+    }
+    let set_registration_gate_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: set_registration_gate_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_registration_gate_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
 
-    match player_two_account {
-        Some(account) => {
-            let player_two_state = Player::unpack(&account.data).unwrap();
-            assert_eq!(player_two_state.is_initialized, true);
-            assert_eq!(player_two_state.owner, player_two_holder_keypair.pubkey());
-            assert_eq!(player_two_state.reward_to_claim, 0);
-            assert_eq!(
-                player_two_state.upline,
-                COption::Some(player_one_account_keypair.pubkey())
-            );
+fn build_thaw_player_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let thaw_player_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+        ],
+        data: vec![16_u8], // Tag = 16
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&thaw_player_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_freeze_player_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    forfeit: bool,
+    reward_balances: &[Pubkey],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+    ];
+    for reward_balance in reward_balances {
+        accounts.push(AccountMeta::new(*reward_balance, false));
+    }
+    let freeze_player_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: vec![15_u8, forfeit as u8], // Tag = 15
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&freeze_player_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_claim_from_distribution_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    upline_account_keypair: Option<&Keypair>,
+    distribution_index: u64,
+    index: u64,
+    amount: u64,
+    proof: &[[u8; 32]],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut claim_from_distribution_data = vec![14_u8]; // Tag = 14
+    claim_from_distribution_data.extend_from_slice(&index.to_le_bytes());
+    claim_from_distribution_data.extend_from_slice(&amount.to_le_bytes());
+    claim_from_distribution_data.extend_from_slice(&proof.to_vec().try_to_vec().unwrap());
+    let (distribution, _nonce) = Pubkey::find_program_address(
+        &[
+            "distribution".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &distribution_index.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let (bitmap, _nonce) = Pubkey::find_program_address(
+        &[
+            "claimed".as_bytes(),
+            distribution.as_ref(),
+            &(index / DISTRIBUTION_BITMAP_BITS).to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(player_holder_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+        AccountMeta::new(distribution, false),
+        AccountMeta::new(bitmap, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
+    }
+    let claim_from_distribution_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: claim_from_distribution_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&claim_from_distribution_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_publish_distribution_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    distribution_index: u64,
+    root: [u8; 32],
+    total: u64,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut publish_distribution_data = vec![13_u8]; // Tag = 13
+    publish_distribution_data.extend_from_slice(&root);
+    publish_distribution_data.extend_from_slice(&total.to_le_bytes());
+    let (distribution, _nonce) = Pubkey::find_program_address(
+        &[
+            "distribution".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            &distribution_index.to_le_bytes(),
+        ],
+        &program_id,
+    );
+    let publish_distribution_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(distribution, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: publish_distribution_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&publish_distribution_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+// Same layout as solana_sdk::ed25519_instruction::new_ed25519_instruction, which require an ed25519_dalek keypair
+fn build_ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+    let public_key_offset: u16 = 16;
+    let signature_offset = public_key_offset + 32;
+    let message_data_offset = signature_offset + 64;
+    let mut data = vec![1_u8, 0]; // 1 signature, padding
+    for offset in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_data_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signer.sign_message(message).as_ref());
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: solana_program::ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+fn find_voucher_bitmap_address(
+    player_account: &Pubkey,
+    voucher_nonce: u64,
+    program_id: &Pubkey,
+) -> Pubkey {
+    let (voucher_bitmap, _nonce) = Pubkey::find_program_address(
+        &[
+            b"vouchers",
+            player_account.as_ref(),
+            &(voucher_nonce / VOUCHER_BITMAP_BITS).to_le_bytes(),
+        ],
+        program_id,
+    );
+    voucher_bitmap
+}
+
+fn build_redeem_voucher_transaction(
+    voucher_signer_keypair: &Keypair,
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    upline_account_keypair: Option<&Keypair>,
+    voucher: &Voucher,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let voucher_bitmap =
+        find_voucher_bitmap_address(&player_account_keypair.pubkey(), voucher.nonce, &program_id);
+    let mut accounts = vec![
+        AccountMeta::new(player_holder_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(voucher_bitmap, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
+    }
+    let redeem_voucher_instruction = [
+        build_ed25519_instruction(voucher_signer_keypair, &voucher.try_to_vec().unwrap()),
+        Instruction {
+            program_id,
+            accounts,
+            data: vec![12_u8], // Tag = 12
+        },
+    ];
+    let mut transaction =
+        Transaction::new_with_payer(&redeem_voucher_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_claim_referral_code_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    code: &[u8],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut claim_referral_code_data = vec![11_u8]; // Tag = 11
+    claim_referral_code_data.extend_from_slice(&code.to_vec().try_to_vec().unwrap());
+    let (referral_code, _nonce) = Pubkey::find_program_address(
+        &[
+            "code".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            code,
+        ],
+        &program_id,
+    );
+    let claim_referral_code_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(player_account_keypair.pubkey(), false),
+            AccountMeta::new(referral_code, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: claim_referral_code_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&claim_referral_code_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_register_player_with_code_transaction(
+    payer: &Keypair,
+    player_holder_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    code: &[u8],
+    upline: &Pubkey,
+    program_id: Pubkey,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let register_player_data = (1_u8, Some(code.to_vec())).try_to_vec().unwrap(); // Tag 1
+    let (referral_code, _nonce) = Pubkey::find_program_address(
+        &[
+            "code".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            code,
+        ],
+        &program_id,
+    );
+    let register_player_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(referral_code, false),
+            AccountMeta::new(*upline, false),
+        ],
+        data: register_player_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&register_player_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_upline_transaction(
+    authority_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    current_upline: Option<Pubkey>,
+    new_upline: Option<Pubkey>,
+    upline_chain: &[Pubkey],
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_upline_data = vec![10_u8]; // Tag = 10
+    let mut accounts = vec![
+        AccountMeta::new_readonly(authority_keypair.pubkey(), true),
+        AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+    ];
+    if let Some(current_upline) = current_upline {
+        accounts.push(AccountMeta::new(current_upline, false));
+    }
+    match new_upline {
+        Some(new_upline) => {
+            set_upline_data.push(1);
+            set_upline_data.extend_from_slice(new_upline.as_ref());
+            accounts.push(AccountMeta::new(new_upline, false));
         }
-        _ => {
-            panic!("Player two account not found");
+        None => set_upline_data.push(0),
+    }
+    for ancestor in upline_chain {
+        accounts.push(AccountMeta::new_readonly(*ancestor, false));
+    }
+    let set_upline_instruction = [Instruction {
+        program_id,
+        accounts,
+        data: set_upline_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_upline_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, authority_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_set_max_direct_referrals_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    max_direct_referrals: Option<u64>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut set_max_direct_referrals_data = vec![9_u8]; // Tag = 9
+    match max_direct_referrals {
+        Some(max_direct_referrals) => {
+            set_max_direct_referrals_data.push(1);
+            set_max_direct_referrals_data
+                .extend_from_slice(&u64::to_le_bytes(max_direct_referrals));
         }
-    };
+        None => set_max_direct_referrals_data.push(0),
+    }
+    let set_max_direct_referrals_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+        ],
+        data: set_max_direct_referrals_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&set_max_direct_referrals_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_init_lamports_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let (lamport_vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "lamport_vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let init_lamports_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(lamport_vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: vec![7_u8], // Tag = 7
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&init_lamports_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_deposit_transaction(
+    depositor_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    program_id: Pubkey,
+    amount: u64,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let (lamport_vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "lamport_vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let mut deposit_data = vec![8_u8]; // Tag = 8
+    deposit_data.extend_from_slice(&u64::to_le_bytes(amount));
+    let deposit_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(depositor_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(lamport_vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: deposit_data,
+    }];
+    let mut transaction = Transaction::new_with_payer(&deposit_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, depositor_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_claim_lamports_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let (lamport_vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "lamport_vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+        ],
+        &program_id,
+    );
+    let claim_reward_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(lamport_vault, false),
+        ],
+        data: vec![3_u8, 0], // Tag = 3, mint = None
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&claim_reward_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
 }
 
-#[tokio::test]
-async fn init_instruction() {
-    let (
-        mint_account_keypair,
-        admin_account_keypair,
-        program_account_keypair,
-        token_account_keypair,
-        player_one_holder_keypair,
-        player_one_account_keypair,
-        player_two_holder_keypair,
-        player_two_account_keypair,
-        player_one_token_account_keypair,
-        player_two_token_account_keypair,
+fn build_register_vault_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    mint: &Pubkey,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let (vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            mint.as_ref(),
+        ],
+        &program_id,
+    );
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let register_vault_instruction = [Instruction {
         program_id,
-        mut banks_client,
-        payer,
-        recent_blockhash,
-    ) = setup().await;
+        accounts: vec![
+            AccountMeta::new(admin_account_keypair.pubkey(), true),
+            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: vec![6_u8], // Tag = 6
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&register_vault_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
 
-    // Test init with invalid program_account
-    let invalid_program_account_keypair = Keypair::new();
-    let init_instruction_transaction = build_init_instruction_transaction(
-        &admin_account_keypair,
-        &invalid_program_account_keypair,
-        &token_account_keypair,
-        &payer,
-        program_id,
-        recent_blockhash,
+fn build_add_mint_reward_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    upline_account_keypair: Option<&Keypair>,
+    mint: &Pubkey,
+    program_id: Pubkey,
+    amount: u64,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut add_reward_data = vec![2_u8]; // Tag = 2
+    add_reward_data.extend_from_slice(&u64::to_le_bytes(amount)); // reward
+    add_reward_data.push(1); // mint = Some
+    add_reward_data.extend_from_slice(mint.as_ref());
+    let (vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            mint.as_ref(),
+        ],
+        &program_id,
     );
-    let result = banks_client
-        .process_transaction(init_instruction_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(error.to_string().contains("incorrect program id"), true);
-        }
+    let reward_balance = |player: &Pubkey| {
+        Pubkey::find_program_address(
+            &["balance".as_bytes(), player.as_ref(), mint.as_ref()],
+            &program_id,
+        )
+        .0
+    };
+    let mut accounts = vec![
+        AccountMeta::new(admin_account_keypair.pubkey(), true),
+        AccountMeta::new(program_account_keypair.pubkey(), false),
+        AccountMeta::new(player_account_keypair.pubkey(), false),
+    ];
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(upline_account_keypair.pubkey(), false));
     }
-    // End
-
-    // Test init with invalid token account
-    let invalid_token_account_keypair = Keypair::new();
-    let init_instruction_transaction = build_init_instruction_transaction(
-        &admin_account_keypair,
-        &program_account_keypair,
-        &invalid_token_account_keypair,
-        &payer,
+    accounts.push(AccountMeta::new_readonly(vault, false));
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    accounts.push(AccountMeta::new(
+        reward_balance(&player_account_keypair.pubkey()),
+        false,
+    ));
+    if let Some(upline_account_keypair) = upline_account_keypair {
+        accounts.push(AccountMeta::new(
+            reward_balance(&upline_account_keypair.pubkey()),
+            false,
+        ));
+    }
+    let add_reward_instruction = [Instruction {
         program_id,
-        recent_blockhash,
+        accounts,
+        data: add_reward_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&add_reward_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_claim_mint_reward_transaction(
+    player_holder_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    player_account_keypair: &Keypair,
+    mint: &Pubkey,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut claim_reward_data = vec![3_u8]; // Tag = 3
+    claim_reward_data.push(1); // mint = Some
+    claim_reward_data.extend_from_slice(mint.as_ref());
+    let (vault, _nonce) = Pubkey::find_program_address(
+        &[
+            "vault".as_bytes(),
+            program_account_keypair.pubkey().as_ref(),
+            mint.as_ref(),
+        ],
+        &program_id,
     );
-    let result = banks_client
-        .process_transaction(init_instruction_transaction)
-        .await;
-    match result {
-        Ok(()) => {}
-        Err(error) => {
-            assert_eq!(
-                error.to_string().contains("Provided owner is not allowed"),
-                true
-            );
+    let (pda, _nonce) = Pubkey::find_program_address(&["game_seed".as_bytes()], &program_id);
+    let (reward_balance, _nonce) = Pubkey::find_program_address(
+        &[
+            "balance".as_bytes(),
+            player_account_keypair.pubkey().as_ref(),
+            mint.as_ref(),
+        ],
+        &program_id,
+    );
+    let claim_reward_instruction = [Instruction {
+        accounts: vec![
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_account_keypair.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(pda, false),
+            AccountMeta::new(
+                get_associated_token_address(
+                    &player_holder_keypair.pubkey(),
+                    mint,
+                    &spl_token::id(),
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new(reward_balance, false),
+        ],
+        program_id,
+        data: claim_reward_data,
+    }];
+
+    let mut transaction =
+        Transaction::new_with_payer(&claim_reward_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, player_holder_keypair], recent_blockhash);
+    transaction
+}
+
+fn build_add_operator_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    operator: &Pubkey,
+    reward_cap: Option<u64>,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut add_operator_data = vec![4_u8]; // Tag = 4
+    match reward_cap {
+        Some(reward_cap) => {
+            add_operator_data.push(1);
+            add_operator_data.extend_from_slice(&u64::to_le_bytes(reward_cap));
         }
+        None => add_operator_data.push(0),
     }
-    // End
+    let add_operator_instruction = [Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(*operator, false),
+        ],
+        data: add_operator_data,
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&add_operator_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
+}
 
-    // Test init instruction
-    let init_instruction_transaction = build_init_instruction_transaction(
-        &admin_account_keypair,
-        &program_account_keypair,
-        &token_account_keypair,
-        &payer,
+fn build_remove_operator_transaction(
+    admin_account_keypair: &Keypair,
+    program_account_keypair: &Keypair,
+    operator: &Pubkey,
+    program_id: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let remove_operator_instruction = [Instruction {
         program_id,
-        recent_blockhash,
-    );
-    banks_client
-        .process_transaction(init_instruction_transaction)
-        .await
-        .unwrap();
-    // It should successfully execute init instruction
-    let program_account = banks_client
-        .get_account(program_account_keypair.pubkey())
-        .await
-        .unwrap();
-    match program_account {
-        Some(account) => {
-            let program_state = GameInfo::unpack(&account.data).unwrap();
-            assert_eq!(program_state.is_initialized, true);
-            assert_eq!(&program_state.admin, &admin_account_keypair.pubkey());
-            assert_eq!(
-                &program_state.spl_token_account,
-                &token_account_keypair.pubkey()
-            );
-        }
-        _ => {
-            panic!("Program account not found");
-        }
-    };
+        accounts: vec![
+            AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
+            AccountMeta::new_readonly(*operator, false),
+        ],
+        data: vec![5_u8], // Tag = 5
+    }];
+    let mut transaction =
+        Transaction::new_with_payer(&remove_operator_instruction, Some(&payer.pubkey()));
+    transaction.partial_sign(&[payer, admin_account_keypair], recent_blockhash);
+    transaction
 }
 
 fn build_claim_reward_transaction(
@@ -693,23 +10019,28 @@ fn build_claim_reward_transaction(
     player_account_keypair: &Keypair,
     token_account_keypair: &Keypair,
     pda: Pubkey,
-    player_token_account_keypair: &Keypair,
+    player_token_account: &Pubkey,
+    mint: &Pubkey,
     program_id: Pubkey,
     payer: &Keypair,
     recent_blockhash: Hash,
 ) -> Transaction {
     let claim_reward_instruction = [Instruction {
         accounts: vec![
-            AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
-            AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+            AccountMeta::new(player_holder_keypair.pubkey(), true),
+            AccountMeta::new(program_account_keypair.pubkey(), false),
             AccountMeta::new(player_account_keypair.pubkey(), false), // Claim from player two account
             AccountMeta::new(token_account_keypair.pubkey(), false),
             AccountMeta::new_readonly(pda, false),
-            AccountMeta::new(player_token_account_keypair.pubkey(), false), // Receive the claimed token using player one token account
+            AccountMeta::new(*player_token_account, false), // Receive the claimed token using player one token account
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
         ],
         program_id,
-        data: vec![3_u8], // Tag = 3
+        data: vec![3_u8, 0], // Tag = 3, mint = None
     }];
 
     let mut transaction =
@@ -730,12 +10061,13 @@ fn build_add_reward_transaction(
 ) -> Transaction {
     let mut add_reward_data = vec![2_u8]; // Tag = 2
     add_reward_data.extend_from_slice(&u64::to_le_bytes(amount)); // reward
+    add_reward_data.push(0); // mint = None
     if upline_account_keypair.is_some() {
         let add_reward_instruction = [Instruction {
             program_id,
             accounts: vec![
                 AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
-                AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+                AccountMeta::new(program_account_keypair.pubkey(), false),
                 AccountMeta::new(player_account_keypair.pubkey(), false),
                 AccountMeta::new(upline_account_keypair.unwrap().pubkey(), false),
             ],
@@ -750,7 +10082,7 @@ fn build_add_reward_transaction(
             program_id,
             accounts: vec![
                 AccountMeta::new_readonly(admin_account_keypair.pubkey(), true),
-                AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+                AccountMeta::new(program_account_keypair.pubkey(), false),
                 AccountMeta::new(player_account_keypair.pubkey(), false),
             ],
             data: add_reward_data.clone(),
@@ -777,8 +10109,8 @@ fn build_register_player_transaction(
             accounts: vec![
                 AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
                 AccountMeta::new(player_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(upline_account_keypair.unwrap().pubkey(), false), //upline
+                AccountMeta::new(program_account_keypair.pubkey(), false),
+                AccountMeta::new(upline_account_keypair.unwrap().pubkey(), false), //upline
             ],
             data: vec![1_u8], // Tag 1
         }];
@@ -792,7 +10124,7 @@ fn build_register_player_transaction(
             accounts: vec![
                 AccountMeta::new_readonly(player_holder_keypair.pubkey(), true),
                 AccountMeta::new(player_account_keypair.pubkey(), false),
-                AccountMeta::new_readonly(program_account_keypair.pubkey(), false),
+                AccountMeta::new(program_account_keypair.pubkey(), false),
             ],
             data: vec![1_u8], // Tag 1
         }];
@@ -845,6 +10177,7 @@ fn build_init_instruction_transaction(
     admin_account_keypair: &Keypair,
     program_account_keypair: &Keypair,
     token_account_keypair: &Keypair,
+    mint: &Pubkey,
     payer: &Keypair,
     program_id: Pubkey,
     recent_blockhash: Hash,
@@ -856,6 +10189,7 @@ fn build_init_instruction_transaction(
             AccountMeta::new(program_account_keypair.pubkey(), false),
             AccountMeta::new(token_account_keypair.pubkey(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(*mint, false),
         ],
         data: vec![0_u8], // Tag = 0
     };
@@ -880,6 +10214,93 @@ async fn setup() -> (
     BanksClient,
     Keypair,
     Hash,
+) {
+    let (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        context,
+    ) = setup_with_context().await;
+    (
+        mint_account_keypair,
+        admin_account_keypair,
+        program_account_keypair,
+        token_account_keypair,
+        player_one_holder_keypair,
+        player_one_account_keypair,
+        player_two_holder_keypair,
+        player_two_account_keypair,
+        player_one_token_account_keypair,
+        player_two_token_account_keypair,
+        program_id,
+        context.banks_client,
+        context.payer,
+        context.last_blockhash,
+    )
+}
+
+// Write an account owned by the program, as if created by a previous instruction
+fn preload_program_owned_account(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    data: &[u8],
+    program_id: &Pubkey,
+) {
+    let mut account =
+        AccountSharedData::new(Rent::default().minimum_balance(data.len()), 0, program_id);
+    account.set_data(data.to_vec());
+    context.set_account(address, &account);
+}
+
+// Write a token account of the mint, as if created by a previous instruction
+fn preload_token_account(
+    context: &mut ProgramTestContext,
+    address: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            state: AccountState::Initialized,
+            ..Default::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    let mut account = AccountSharedData::new(
+        Rent::default().minimum_balance(data.len()),
+        0,
+        &spl_token::id(),
+    );
+    account.set_data(data);
+    context.set_account(address, &account);
+}
+
+// Same as setup, but keep the test context to overwrite sysvars such as the clock
+async fn setup_with_context() -> (
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Keypair,
+    Pubkey,
+    ProgramTestContext,
 ) {
     let mint_account_keypair = Keypair::new();
     let admin_account_keypair = Keypair::new();
@@ -901,11 +10322,14 @@ async fn setup() -> (
         program_id,
         processor!(main),
     );
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let mut context = program_test.start_with_context().await;
+    let banks_client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
     // Create and initialize program, token account, mint account
     banks_client
         .process_transaction(build_create_and_init_token_and_program_account_transaction(
-            &payer,
+            payer,
             &program_account_keypair,
             &program_id,
             &mint_account_keypair,
@@ -919,7 +10343,7 @@ async fn setup() -> (
     // Mint to program token account
     banks_client
         .process_transaction(build_mint_transaction(
-            &payer,
+            payer,
             &mint_account_keypair,
             &token_account_keypair,
             &admin_account_keypair,
@@ -932,7 +10356,7 @@ async fn setup() -> (
     // Create and initialize player 1 token account
     banks_client
         .process_transaction(build_create_and_init_player_token_account(
-            &payer,
+            payer,
             &player_one_token_account_keypair,
             &player_one_holder_keypair,
             &mint_account_keypair,
@@ -944,7 +10368,7 @@ async fn setup() -> (
     // Create and initialize player 2 token account
     banks_client
         .process_transaction(build_create_and_init_player_token_account(
-            &payer,
+            payer,
             &player_two_token_account_keypair,
             &player_two_holder_keypair,
             &mint_account_keypair,
@@ -965,9 +10389,7 @@ async fn setup() -> (
         player_one_token_account_keypair,
         player_two_token_account_keypair,
         program_id,
-        banks_client,
-        payer,
-        recent_blockhash,
+        context,
     )
 }
 
@@ -1038,7 +10460,7 @@ fn build_create_and_init_token_and_program_account_transaction(
             &program_account_keypair.pubkey(),
             Rent::default().minimum_balance(GameInfo::LEN),
             GameInfo::LEN.try_into().unwrap(),
-            &program_id,
+            program_id,
         ),
         system_instruction::create_account(
             &payer.pubkey(),
@@ -1081,5 +10503,5 @@ fn build_create_and_init_token_and_program_account_transaction(
         ],
         recent_blockhash,
     );
-    return transaction;
+    transaction
 }